@@ -0,0 +1,503 @@
+// src/commands/agenda.rs
+use crate::commands::jrnl::{self, JOURNAL_PROJECT};
+use crate::commands::ls::project_matches;
+use crate::git;
+use crate::metadata::{NoteMeta, load_vault, parse_date, relative_path, split_frontmatter};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde_json::{Value as JsonValue, json};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Longest agenda shown, about ten years
+const MAX_DAYS: i64 = 3660;
+
+/// What puts an item on the agenda.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Marker {
+    Due,
+    Scheduled,
+    /// The `date:` of a note, when it is not the creation date
+    Date,
+}
+
+impl Marker {
+    fn name(&self) -> &'static str {
+        match self {
+            Marker::Due => "due",
+            Marker::Scheduled => "scheduled",
+            Marker::Date => "date",
+        }
+    }
+}
+
+/// An open task or a note with a date.
+#[derive(Debug, Clone, PartialEq)]
+struct AgendaItem {
+    date: NaiveDate,
+    marker: Marker,
+    /// The text of the task, or the title of the note
+    text: String,
+    task: bool,
+    note_title: String,
+    /// Path of the note relative to the vault
+    note: String,
+    /// Line of the task in the note, counting from 1
+    line: Option<usize>,
+}
+
+impl AgendaItem {
+    fn to_json(&self, today: NaiveDate) -> JsonValue {
+        json!({
+            "date": self.date.format("%Y-%m-%d").to_string(),
+            "kind": if self.task { "task" } else { "note" },
+            "marker": self.marker.name(),
+            "text": self.text,
+            "note": self.note,
+            "note_title": self.note_title,
+            "line": self.line,
+            "overdue": self.is_overdue(today),
+        })
+    }
+
+    // Only deadlines can be missed; scheduled tasks and dated notes simply pass
+    fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.marker == Marker::Due && self.date < today
+    }
+}
+
+/// The items of a number of days from today, plus whatever is overdue.
+#[derive(Debug, Default, PartialEq)]
+struct Agenda {
+    overdue: Vec<AgendaItem>,
+    days: BTreeMap<NaiveDate, Vec<AgendaItem>>,
+}
+
+/// Shows the open tasks with a due or scheduled date and the notes with a
+/// `due:` or `date:` field, by day, for `days` days starting today. With
+/// `journal`, today's part is also appended to today's journal entry.
+pub fn execute(days: i64, vault: Option<&str>, json: bool, journal: bool) -> Result<()> {
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(anyhow!("Days must be between 1 and {}", MAX_DAYS));
+    }
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+    let today = Local::now().date_naive();
+    let last = today + Duration::days(days - 1);
+
+    let mut items = Vec::new();
+    for note in load_vault(&vault_path)? {
+        let content = fs::read_to_string(&note.path)
+            .context(format!("Failed to read note: {}", note.path.display()))?;
+        let id = relative_path(&vault_path, &note.path);
+        items.extend(note_items(&note, &id, file_day(&note.path)));
+        items.extend(task_items(&content, &id, &note.title));
+    }
+    let agenda = build_agenda(items, today, last);
+
+    if json {
+        print_json(&agenda, today, last)?;
+    } else {
+        print_agenda(&agenda, today);
+    }
+
+    if journal {
+        let (note_path, _) = jrnl::append_today(&vault_path, &journal_text(&agenda, today))?;
+        eprintln!(
+            "Added today's agenda to {}",
+            relative_path(&vault_path, &note_path)
+        );
+        git::auto_commit_or_warn(&[&note_path]);
+    }
+    Ok(())
+}
+
+fn build_agenda(items: Vec<AgendaItem>, today: NaiveDate, last: NaiveDate) -> Agenda {
+    let mut agenda = Agenda::default();
+    for item in items {
+        if item.is_overdue(today) {
+            agenda.overdue.push(item);
+        } else if item.date >= today && item.date <= last {
+            agenda.days.entry(item.date).or_default().push(item);
+        }
+    }
+
+    let order = |a: &AgendaItem, b: &AgendaItem| {
+        (a.date, a.marker, &a.note, a.line).cmp(&(b.date, b.marker, &b.note, b.line))
+    };
+    agenda.overdue.sort_by(order);
+    for items in agenda.days.values_mut() {
+        items.sort_by(order);
+    }
+    agenda
+}
+
+fn print_agenda(agenda: &Agenda, today: NaiveDate) {
+    if agenda.overdue.is_empty() && agenda.days.is_empty() {
+        println!("Nothing on the agenda");
+        return;
+    }
+
+    if !agenda.overdue.is_empty() {
+        println!("Overdue");
+        for item in &agenda.overdue {
+            println!("  ! {}  {}", item.date.format("%Y-%m-%d"), describe(item));
+        }
+    }
+    for (date, items) in &agenda.days {
+        let label = if *date == today { "  (today)" } else { "" };
+        println!("{}{}", date.format("%a %Y-%m-%d"), label);
+        for item in items {
+            println!("  {}", describe(item));
+        }
+    }
+}
+
+// "[ ] Call Bob  (due, delta.md:5)" or "Review  (date, projects/review.md)"
+fn describe(item: &AgendaItem) -> String {
+    let location = match item.line {
+        Some(line) => format!("{}:{}", item.note, line),
+        None => item.note.clone(),
+    };
+    let checkbox = if item.task { "[ ] " } else { "" };
+    format!(
+        "{}{}  ({}, {})",
+        checkbox,
+        item.text,
+        item.marker.name(),
+        location
+    )
+}
+
+fn print_json(agenda: &Agenda, today: NaiveDate, last: NaiveDate) -> Result<()> {
+    let output = json!({
+        "from": today.format("%Y-%m-%d").to_string(),
+        "to": last.format("%Y-%m-%d").to_string(),
+        "overdue": agenda.overdue.iter().map(|item| item.to_json(today)).collect::<Vec<_>>(),
+        "days": agenda
+            .days
+            .iter()
+            .map(|(date, items)| json!({
+                "date": date.format("%Y-%m-%d").to_string(),
+                "items": items.iter().map(|item| item.to_json(today)).collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).context("Failed to serialize agenda")?
+    );
+    Ok(())
+}
+
+// Today's agenda as markdown for the journal, linking back to the notes
+fn journal_text(agenda: &Agenda, today: NaiveDate) -> String {
+    let mut text = String::from("Agenda");
+    let today_items = agenda
+        .days
+        .get(&today)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if agenda.overdue.is_empty() && today_items.is_empty() {
+        text.push_str("\n\nNothing on the agenda");
+        return text;
+    }
+
+    text.push('\n');
+    for item in agenda.overdue.iter().chain(today_items) {
+        let link = format!(
+            "[[{}|{}]]",
+            item.note.strip_suffix(".md").unwrap_or(&item.note),
+            item.note_title
+        );
+        let marker = if item.is_overdue(today) {
+            format!("overdue since {}", item.date.format("%Y-%m-%d"))
+        } else {
+            item.marker.name().to_string()
+        };
+        if item.task {
+            text.push_str(&format!("\n- [ ] {} ({}) {}", item.text, marker, link));
+        } else {
+            text.push_str(&format!("\n- {} ({})", link, marker));
+        }
+    }
+    text
+}
+
+// The day a note file was created, or last modified where the file system
+// does not record creation
+fn file_day(note_path: &Path) -> Option<NaiveDate> {
+    let meta = fs::metadata(note_path).ok()?;
+    let time = meta.created().or_else(|_| meta.modified()).ok()?;
+    Some(DateTime::<Local>::from(time).date_naive())
+}
+
+// A note is on the agenda by its `due:` and `date:` fields, unless it is
+// marked done. `ncy new` and the importers write the creation date to
+// `date:`, so it is left out when it falls on the day the note was made:
+// its `created:` field, or else the day of the file. Journal entries carry
+// the date they were written, so their `date:` is left out too
+fn note_items(note: &NoteMeta, id: &str, file_day: Option<NaiveDate>) -> Vec<AgendaItem> {
+    let frontmatter = &note.frontmatter;
+    let done = frontmatter.get("done").and_then(|v| v.as_bool()) == Some(true)
+        || frontmatter
+            .get("status")
+            .and_then(|v| v.as_str())
+            .is_some_and(|status| {
+                ["done", "completed", "cancelled"].contains(&status.to_lowercase().as_str())
+            });
+    if done {
+        return Vec::new();
+    }
+
+    let field_date = |key: &str| {
+        frontmatter
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(parse_date)
+            .map(|date| date.date_naive())
+    };
+    let made = field_date("created").or(file_day);
+    let dated = made != field_date("date") && !project_matches(&note.project, JOURNAL_PROJECT);
+    let mut items = Vec::new();
+    for (key, marker) in [("due", Marker::Due), ("date", Marker::Date)] {
+        if marker == Marker::Date && !dated {
+            continue;
+        }
+        let Some(date) = field_date(key) else {
+            continue;
+        };
+        items.push(AgendaItem {
+            date,
+            marker,
+            text: note.title.clone(),
+            task: false,
+            note_title: note.title.clone(),
+            note: id.to_string(),
+            line: None,
+        });
+    }
+    items
+}
+
+// The open tasks of a note with a due or scheduled date, outside code blocks
+fn task_items(content: &str, id: &str, note_title: &str) -> Vec<AgendaItem> {
+    let (_, body) = split_frontmatter(content);
+    let first_line = content[..content.len() - body.len()].lines().count();
+
+    let mut items = Vec::new();
+    let mut in_code = false;
+    for (i, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let Some(task) = parse_task(line) else {
+            continue;
+        };
+        for (marker, date) in task.dates {
+            items.push(AgendaItem {
+                date,
+                marker,
+                text: task.text.clone(),
+                task: true,
+                note_title: note_title.to_string(),
+                note: id.to_string(),
+                line: Some(first_line + i + 1),
+            });
+        }
+    }
+    items
+}
+
+#[derive(Debug, PartialEq)]
+struct Task {
+    /// The task without its date markers
+    text: String,
+    dates: Vec<(Marker, NaiveDate)>,
+}
+
+/// Parses an open task, `- [ ] text`, with its dates. Dates can be given as
+/// `due:2024-05-01` and `scheduled:2024-05-01`, as `@due(2024-05-01)`, the
+/// Dataview way as `[due:: 2024-05-01]`, or with the Tasks plugin emoji
+/// `📅 2024-05-01` and `⏳ 2024-05-01`. Tasks without dates are not returned.
+fn parse_task(line: &str) -> Option<Task> {
+    let rest = line.trim_start();
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| rest.strip_prefix(bullet))
+        .or_else(|| {
+            // Numbered lists: "1. [ ]" or "1) [ ]"
+            let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+            let after = &rest[digits..];
+            (digits > 0)
+                .then(|| {
+                    after
+                        .strip_prefix(". ")
+                        .or_else(|| after.strip_prefix(") "))
+                })
+                .flatten()
+        })?;
+    let rest = rest.strip_prefix("[ ]")?;
+
+    // Bring every way of writing a date to `key:date`
+    let mut normalized = rest.to_string();
+    for (emoji, key) in [('📅', "due"), ('⏳', "scheduled")] {
+        while let Some(start) = normalized.find(emoji) {
+            let after = start + emoji.len_utf8();
+            let spaces = normalized[after..].len() - normalized[after..].trim_start().len();
+            normalized.replace_range(start..after + spaces, &format!(" {}:", key));
+        }
+    }
+    for key in ["due", "scheduled"] {
+        for (open, close) in [(format!("[{}::", key), ']'), (format!("@{}(", key), ')')] {
+            while let Some(start) = normalized.find(&open) {
+                let value_start = start + open.len();
+                let Some(length) = normalized[value_start..].find(close) else {
+                    break;
+                };
+                let value = normalized[value_start..value_start + length]
+                    .trim()
+                    .to_string();
+                normalized.replace_range(
+                    start..value_start + length + 1,
+                    &format!(" {}:{} ", key, value),
+                );
+            }
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut dates = Vec::new();
+    for word in normalized.split_whitespace() {
+        let marker = [("due:", Marker::Due), ("scheduled:", Marker::Scheduled)]
+            .iter()
+            .find_map(|(prefix, marker)| {
+                let date = parse_date(word.strip_prefix(prefix)?)?;
+                Some((*marker, date.date_naive()))
+            });
+        match marker {
+            Some(marker) => dates.push(marker),
+            None => words.push(word),
+        }
+    }
+
+    if dates.is_empty() {
+        return None;
+    }
+    Some(Task {
+        text: words.join(" "),
+        dates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_task() {
+        let task = parse_task("  - [ ] Send invoice due:2026-10-20 #work, due: soon").unwrap();
+        assert_eq!(task.text, "Send invoice #work, due: soon");
+        assert_eq!(task.dates, vec![(Marker::Due, date("2026-10-20"))]);
+
+        let task = parse_task("1. [ ] Call Bob 📅 2026-10-19 ⏳ 2026-10-18").unwrap();
+        assert_eq!(task.text, "Call Bob");
+        assert_eq!(
+            task.dates,
+            vec![
+                (Marker::Due, date("2026-10-19")),
+                (Marker::Scheduled, date("2026-10-18"))
+            ]
+        );
+
+        let task = parse_task("* [ ] Plan [scheduled:: 2026-11-01] trip @due(2026-11-05)").unwrap();
+        assert_eq!(task.text, "Plan trip");
+        assert_eq!(task.dates.len(), 2);
+
+        assert_eq!(parse_task("- [x] Done due:2026-10-20"), None);
+        assert_eq!(parse_task("- [ ] No date"), None);
+        assert_eq!(parse_task("Text due:2026-10-20"), None);
+    }
+
+    #[test]
+    fn test_build_agenda() {
+        let content = "---\ntitle: Work\n---\n- [ ] Late due:2026-10-10\n```\n- [ ] Code due:2026-10-18\n```\n- [ ] Soon scheduled:2026-10-19\n- [ ] Past scheduled:2026-10-01\n- [ ] Later due:2026-12-01\n";
+        let items = task_items(content, "work.md", "Work");
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0].line, Some(4));
+
+        let agenda = build_agenda(items, date("2026-10-18"), date("2026-10-24"));
+        assert_eq!(agenda.overdue.len(), 1);
+        assert_eq!(agenda.overdue[0].text, "Late");
+        assert_eq!(agenda.days.len(), 1);
+        assert_eq!(agenda.days[&date("2026-10-19")][0].text, "Soon");
+
+        let text = journal_text(&agenda, date("2026-10-18"));
+        assert_eq!(
+            text,
+            "Agenda\n\n- [ ] Late (overdue since 2026-10-10) [[work|Work]]"
+        );
+    }
+
+    #[test]
+    fn test_note_items_skip_creation_dates() {
+        let note = |project: &str, frontmatter: JsonValue| NoteMeta {
+            path: format!("/vault/{}/note.md", project).into(),
+            title: "Review".to_string(),
+            project: project.to_string(),
+            frontmatter,
+            tags: Vec::new(),
+            created: Local::now(),
+            modified: Local::now(),
+        };
+
+        let made = Some(date("2026-10-18"));
+
+        // What `ncy new` and the importers write: `date:` is the creation date
+        let fresh = note("projects", json!({"title": "Review", "date": "2026-10-18"}));
+        assert!(note_items(&fresh, "projects/review.md", made).is_empty());
+        let stamped = note(
+            "projects",
+            json!({"created": "2026-10-18T09:30:00+02:00", "date": "2026-10-18"}),
+        );
+        assert!(note_items(&stamped, "projects/review.md", None).is_empty());
+
+        // A hand-written `date:` on another day than the file was made
+        let planned = note("projects", json!({"date": "2026-10-25"}));
+        let items = note_items(&planned, "projects/review.md", made);
+        assert_eq!(
+            items.iter().map(|i| (i.marker, i.date)).collect::<Vec<_>>(),
+            vec![(Marker::Date, date("2026-10-25"))]
+        );
+
+        let meeting = note(
+            "projects",
+            json!({"created": "2026-10-01", "date": "2026-10-20", "due": "2026-10-22"}),
+        );
+        let items = note_items(&meeting, "projects/review.md", made);
+        assert_eq!(
+            items.iter().map(|i| (i.marker, i.date)).collect::<Vec<_>>(),
+            vec![
+                (Marker::Due, date("2026-10-22")),
+                (Marker::Date, date("2026-10-20"))
+            ]
+        );
+
+        let entry = note(
+            JOURNAL_PROJECT,
+            json!({"created": "2026-10-01", "date": "2026-10-20"}),
+        );
+        assert!(note_items(&entry, "journal/10-20-2026.md", made).is_empty());
+    }
+}
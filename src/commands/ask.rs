@@ -0,0 +1,85 @@
+// src/commands/ask.rs
+use crate::embeddings::{Embedder, EmbeddingCache, content_hash, cosine, model_directory};
+use crate::metadata::{load_vault, relative_path, split_frontmatter};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashSet;
+use std::fs;
+
+/// Ranks the notes of a vault by how close their meaning is to a question,
+/// embedding any notes that changed since the last run first.
+pub fn execute(question: &str, vault: Option<&str>, limit: usize) -> Result<()> {
+    let question = question.trim();
+    if question.is_empty() {
+        return Err(anyhow!(
+            "Ask a question or describe what you are looking for"
+        ));
+    }
+
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let model_dir = model_directory(&config)?;
+
+    let mut notes = load_vault(&vault_path)?;
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    if notes.is_empty() {
+        return Err(anyhow!("No markdown notes found in vault: {}", vault_name));
+    }
+
+    let mut texts = Vec::with_capacity(notes.len());
+    for note in &notes {
+        let content = fs::read_to_string(&note.path)
+            .context(format!("Failed to read note: {}", note.path.display()))?;
+        texts.push(format!(
+            "{}\n{}",
+            note.title,
+            split_frontmatter(&content).1.trim()
+        ));
+    }
+    let hashes: Vec<String> = texts.iter().map(|text| content_hash(text)).collect();
+
+    let embedder = Embedder::load(&model_dir)?;
+    let mut cache = EmbeddingCache::load(&vault_name, &model_dir)?;
+
+    // The same text may show up in several notes; embed it once
+    let mut seen = HashSet::new();
+    let stale: Vec<usize> = (0..notes.len())
+        .filter(|i| cache.get(&hashes[*i]).is_none() && seen.insert(&hashes[*i]))
+        .collect();
+    if !stale.is_empty() {
+        eprintln!("Embedding {} new or changed notes...", stale.len());
+        let stale_texts: Vec<&str> = stale.iter().map(|i| texts[*i].as_str()).collect();
+        for (i, embedding) in stale.iter().zip(embedder.embed(&stale_texts)?) {
+            cache.insert(hashes[*i].clone(), embedding);
+        }
+    }
+
+    let current: HashSet<&str> = hashes.iter().map(|hash| hash.as_str()).collect();
+    if cache.retain_hashes(&current) || !stale.is_empty() {
+        cache.save(&vault_name)?;
+    }
+
+    let query = embedder
+        .embed(&[question])?
+        .pop()
+        .context("The model returned no embedding for the question")?;
+
+    let mut ranked: Vec<(usize, f32)> = hashes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, hash)| cache.get(hash).map(|e| (i, cosine(&query, e))))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+
+    for (i, score) in ranked {
+        println!(
+            "  {:.2}  {}  {}",
+            score,
+            notes[i].title,
+            relative_path(&vault_path, &notes[i].path)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,406 @@
+// src/commands/attachments.rs
+use crate::commands::import::slugify;
+use crate::crypto;
+use crate::git;
+use crate::links::{
+    find_markdown_images, find_markdown_links, find_wikilinks, relative_url, resolve_relative,
+};
+use crate::metadata::{find_note, relative_path, split_frontmatter};
+use crate::utils::{read_config, resolve_vault, vault_setting};
+use anyhow::{Context, Result, anyhow};
+use notemancy_core::notes::utils::list_all_notes_alt;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the folders attachments are kept in: the vault's own, and the
+/// ones `ncy import` makes in each project.
+pub const ATTACHMENTS_DIR: &str = "attachments";
+// Hidden, so nothing in it is listed as a note or an attachment
+const TRASH_DIR: &str = ".trash";
+
+/// Copies a file into the vault's attachments folder and links it from a
+/// note, or prints the link when no note is given. Images are embedded;
+/// `embed` embeds other files too.
+pub fn attach(file: &Path, note: Option<&str>, vault: Option<&str>, embed: bool) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let folder = vault_setting(&config, &vault_name, "attachments").unwrap_or(ATTACHMENTS_DIR);
+
+    // Check the note before copying anything
+    let note_path = note.map(|note| find_note(&vault_path, note)).transpose()?;
+    if let Some(note_path) = &note_path
+        && crypto::is_encrypted_note(note_path)
+    {
+        return Err(anyhow!(
+            "Cannot add a link to an encrypted note. Attach the file without --note and add the link while editing the note"
+        ));
+    }
+
+    let data = fs::read(file).context(format!("Failed to read {}", file.display()))?;
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .context(format!("Not a file: {}", file.display()))?;
+    let mut store = AttachmentStore::new(&vault_path, folder.trim_matches('/'), false);
+    let attachment = store.save(&name, &data)?;
+    if store.written.is_empty() {
+        println!("Already in the vault as {}", attachment);
+    } else {
+        println!("Copied to {}", attachment);
+    }
+
+    let mut changed = store.written.clone();
+    match note_path {
+        Some(note_path) => {
+            let from = relative_path(&vault_path, &note_path);
+            let link = media_link(&from, &attachment, embed || is_image(&attachment));
+            let content = fs::read_to_string(&note_path)
+                .context(format!("Failed to read note: {}", note_path.display()))?;
+            fs::write(&note_path, format!("{}\n\n{}\n", content.trim_end(), link))
+                .context(format!("Failed to write note: {}", note_path.display()))?;
+            println!("Linked from {}", from);
+            changed.push(note_path);
+        }
+        // Links from notes at the root of the vault
+        None => println!(
+            "{}",
+            media_link("", &attachment, embed || is_image(&attachment))
+        ),
+    }
+
+    let paths: Vec<&Path> = changed.iter().map(|path| path.as_path()).collect();
+    git::auto_commit_or_warn(&paths);
+    Ok(())
+}
+
+/// Lists the attachments no note links to or embeds.
+pub fn orphans(vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let folder = vault_setting(&config, &vault_name, "attachments").unwrap_or(ATTACHMENTS_DIR);
+
+    let (orphans, encrypted) = find_orphans(&vault_path, folder)?;
+    for orphan in &orphans {
+        println!("{}", orphan);
+    }
+    if orphans.is_empty() {
+        eprintln!("No unused attachments in vault '{}'", vault_name);
+    }
+    warn_encrypted(encrypted);
+    Ok(())
+}
+
+/// Moves the attachments no note uses to the vault's `.trash` folder,
+/// keeping their paths so they are easy to restore. Nothing is moved while
+/// the vault has encrypted notes, unless `include_encrypted` is set.
+pub fn gc(vault: Option<&str>, include_encrypted: bool) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let folder = vault_setting(&config, &vault_name, "attachments").unwrap_or(ATTACHMENTS_DIR);
+
+    let (orphans, encrypted) = find_orphans(&vault_path, folder)?;
+    check_encrypted(encrypted, include_encrypted)?;
+    warn_encrypted(encrypted);
+    if orphans.is_empty() {
+        println!("No unused attachments in vault '{}'", vault_name);
+        return Ok(());
+    }
+
+    let mut moved = Vec::new();
+    for orphan in &orphans {
+        let source = vault_path.join(orphan);
+        let target = trash_path(&vault_path, orphan);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).context("Failed to create trash directory")?;
+        }
+        fs::rename(&source, &target).context(format!("Failed to move {} to the trash", orphan))?;
+        println!("{} -> {}", orphan, relative_path(&vault_path, &target));
+        moved.push(source);
+    }
+
+    println!(
+        "Moved {} unused attachments to {}/",
+        orphans.len(),
+        TRASH_DIR
+    );
+    let paths: Vec<&Path> = moved.iter().map(|path| path.as_path()).collect();
+    git::auto_commit_or_warn(&paths);
+    Ok(())
+}
+
+// Attachments only encrypted notes use would be trashed, so collecting needs
+// to be asked for explicitly
+fn check_encrypted(encrypted: usize, include_encrypted: bool) -> Result<()> {
+    if encrypted > 0 && !include_encrypted {
+        return Err(anyhow!(
+            "{} encrypted notes cannot be checked for the attachments they use. Run 'ncy attachments orphans' to review the list, then pass --include-encrypted to move them anyway",
+            encrypted
+        ));
+    }
+    Ok(())
+}
+
+// Links inside encrypted notes cannot be seen, so what they use looks unused
+fn warn_encrypted(encrypted: usize) {
+    if encrypted > 0 {
+        eprintln!(
+            "Warning: {} encrypted notes were not checked; attachments only they use are counted as unused",
+            encrypted
+        );
+    }
+}
+
+/// Saves files to an attachments folder. A file that is already there with
+/// the same content is reused; other clashes get a numeric suffix.
+pub struct AttachmentStore {
+    vault_path: PathBuf,
+    dir: String,
+    dry_run: bool,
+    taken: BTreeSet<String>,
+    /// Files written so far
+    pub written: Vec<PathBuf>,
+    /// Files saved so far, also in a dry run
+    pub count: usize,
+}
+
+impl AttachmentStore {
+    /// A store for `dir`, relative to the vault.
+    pub fn new(vault_path: &Path, dir: &str, dry_run: bool) -> Self {
+        AttachmentStore {
+            vault_path: vault_path.to_path_buf(),
+            dir: dir.to_string(),
+            dry_run,
+            taken: BTreeSet::new(),
+            written: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Saves a file and returns its path relative to the vault.
+    pub fn save(&mut self, name: &str, data: &[u8]) -> Result<String> {
+        let name = slugify(name);
+        let (base, extension) = match name.rfind('.') {
+            Some(dot) if dot > 0 => name.split_at(dot),
+            _ => (name.as_str(), ""),
+        };
+
+        let mut counter = 1;
+        loop {
+            let candidate = if counter == 1 {
+                format!("{}/{}", self.dir, name)
+            } else {
+                format!("{}/{}-{}{}", self.dir, base, counter, extension)
+            };
+            counter += 1;
+
+            let path = self.vault_path.join(&candidate);
+            if path.exists() {
+                if fs::read(&path).is_ok_and(|existing| existing == data) {
+                    self.taken.insert(candidate.clone());
+                    return Ok(candidate);
+                }
+                continue;
+            }
+            if !self.taken.insert(candidate.clone()) {
+                continue;
+            }
+
+            if !self.dry_run {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).context("Failed to create attachments directory")?;
+                }
+                fs::write(&path, data).context(format!("Failed to write {}", path.display()))?;
+                self.written.push(path);
+            }
+            self.count += 1;
+            return Ok(candidate);
+        }
+    }
+}
+
+/// A markdown link to an attachment from a note, as an image when `image`.
+pub fn media_link(from: &str, path: &str, image: bool) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let url = relative_url(from, path);
+    if image {
+        format!("![{}]({})", name, url)
+    } else {
+        format!("[{}]({})", name, url)
+    }
+}
+
+pub fn is_image(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"].contains(&extension.as_str())
+}
+
+// The unused attachments of a vault, and how many encrypted notes were skipped
+fn find_orphans(vault_path: &Path, folder: &str) -> Result<(Vec<String>, usize)> {
+    let attachments = list_attachments(vault_path, folder.trim_matches('/'))?;
+
+    let mut notes = Vec::new();
+    let mut encrypted = 0;
+    for note_path in list_all_notes_alt(vault_path, false)? {
+        let note_path = Path::new(&note_path);
+        let content = fs::read_to_string(note_path)
+            .context(format!("Failed to read note: {}", note_path.display()))?;
+        if crypto::is_encrypted(&content) {
+            encrypted += 1;
+        }
+        notes.push((relative_path(vault_path, note_path), content));
+    }
+
+    Ok((unreferenced(&attachments, &notes), encrypted))
+}
+
+/// Lists the files in attachments folders: the configured one and any
+/// folder named `attachments`. Hidden files and folders are skipped.
+fn list_attachments(vault_path: &Path, folder: &str) -> Result<Vec<String>> {
+    fn walk(
+        dir: &Path,
+        in_attachments: bool,
+        ctx: (&Path, &str),
+        out: &mut Vec<String>,
+    ) -> Result<()> {
+        let (vault_path, folder) = ctx;
+        for entry in fs::read_dir(dir).context(format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let relative = relative_path(vault_path, &path);
+            if path.is_dir() {
+                let is_attachments = name == ATTACHMENTS_DIR || relative == folder;
+                walk(&path, in_attachments || is_attachments, ctx, out)?;
+            } else if in_attachments && path.extension().is_none_or(|ext| ext != "md") {
+                out.push(relative);
+            }
+        }
+        Ok(())
+    }
+
+    let mut attachments = Vec::new();
+    walk(vault_path, false, (vault_path, folder), &mut attachments)?;
+    attachments.sort();
+    Ok(attachments)
+}
+
+/// The attachments not used by any of the notes, given as (path, content).
+/// Markdown links and images count by path; wikilinks and embeds by path or
+/// by file name, like Obsidian resolves them. A path mentioned in the
+/// frontmatter, such as a cover image, counts too.
+fn unreferenced(attachments: &[String], notes: &[(String, String)]) -> Vec<String> {
+    let mut paths: BTreeSet<String> = BTreeSet::new();
+    let mut names: BTreeSet<String> = BTreeSet::new();
+    let mut frontmatters = String::new();
+
+    for (id, content) in notes {
+        let (frontmatter, body) = split_frontmatter(content);
+        frontmatters.push_str(frontmatter.unwrap_or_default());
+
+        for target in find_markdown_links(body)
+            .into_iter()
+            .chain(find_markdown_images(body))
+        {
+            paths.insert(resolve_relative(id, &target).to_lowercase());
+        }
+        for link in find_wikilinks(body) {
+            let target = link.target.to_lowercase();
+            paths.insert(resolve_relative(id, &target));
+            names.insert(target.rsplit('/').next().unwrap_or(&target).to_string());
+            paths.insert(target);
+        }
+    }
+
+    attachments
+        .iter()
+        .filter(|attachment| {
+            let lowercase = attachment.to_lowercase();
+            let name = lowercase.rsplit('/').next().unwrap_or(&lowercase);
+            !paths.contains(&lowercase)
+                && !names.contains(name)
+                && !frontmatters.contains(attachment.as_str())
+        })
+        .cloned()
+        .collect()
+}
+
+// Where an attachment goes in the trash: the same path below it, with a
+// numeric suffix when something of that name was trashed before
+fn trash_path(vault_path: &Path, attachment: &str) -> PathBuf {
+    let trash = vault_path.join(TRASH_DIR);
+    let (base, extension) = match attachment.rfind('.') {
+        Some(dot) if dot > attachment.rfind('/').map_or(0, |slash| slash + 1) => {
+            attachment.split_at(dot)
+        }
+        _ => (attachment, ""),
+    };
+
+    let mut candidate = trash.join(attachment);
+    let mut counter = 2;
+    while candidate.exists() {
+        candidate = trash.join(format!("{}-{}{}", base, counter, extension));
+        counter += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gc_refuses_with_encrypted_notes() {
+        assert!(check_encrypted(0, false).is_ok());
+        assert!(check_encrypted(2, false).is_err());
+        assert!(check_encrypted(2, true).is_ok());
+    }
+
+    #[test]
+    fn test_unreferenced() {
+        let attachments: Vec<String> = [
+            "attachments/diagram.png",
+            "attachments/report.pdf",
+            "attachments/unused.png",
+            "projects/x/attachments/Photo.JPG",
+            "attachments/cover.jpg",
+            "attachments/old.zip",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+        let notes = vec![
+            (
+                "projects/x/note.md".to_string(),
+                "![d](../../attachments/diagram.png) ![[photo.jpg]]\n`[[old.zip]]`".to_string(),
+            ),
+            (
+                "home.md".to_string(),
+                "---\ncover: attachments/cover.jpg\n---\n[r](attachments/report.pdf#page=2)"
+                    .to_string(),
+            ),
+        ];
+
+        assert_eq!(
+            unreferenced(&attachments, &notes),
+            vec!["attachments/unused.png", "attachments/old.zip"]
+        );
+    }
+
+    #[test]
+    fn test_media_link() {
+        assert_eq!(
+            media_link("projects/n.md", "attachments/a b.png", true),
+            "![a b.png](../attachments/a%20b.png)"
+        );
+        assert_eq!(
+            media_link("", "attachments/r.pdf", false),
+            "[r.pdf](attachments/r.pdf)"
+        );
+        assert!(is_image("x/Scan.JPEG"));
+        assert!(!is_image("x/scan.pdf"));
+    }
+}
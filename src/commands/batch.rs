@@ -0,0 +1,246 @@
+// src/commands/batch.rs
+use crate::commands::encrypt;
+use crate::commands::ls::{NoteFilter, normalize_project};
+use crate::crypto;
+use crate::git;
+use crate::history::{self, Action};
+use crate::metadata::{add_tag, load_note, relative_path, update_frontmatter};
+use crate::picker::{NoteChoice, PickOptions, Picked, pick_notes, vault_choices};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// What to do with the notes picked in `ncy batch`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchAction {
+    Open,
+    Paths,
+    Move(String),
+    Tag(String),
+    Export(String),
+}
+
+impl BatchAction {
+    pub fn parse(action: &str, target: Option<&str>) -> Result<Self> {
+        let require_target = |what: &str| {
+            target
+                .map(|t| t.to_string())
+                .context(format!("The '{}' action requires {}", action, what))
+        };
+
+        match action {
+            "open" => Ok(BatchAction::Open),
+            "paths" => Ok(BatchAction::Paths),
+            "move" => Ok(BatchAction::Move(require_target("a project path")?)),
+            "tag" => Ok(BatchAction::Tag(require_target("a tag")?)),
+            "export" => Ok(BatchAction::Export(require_target("a target directory")?)),
+            _ => Err(anyhow!("Unknown batch action '{}'", action)),
+        }
+    }
+}
+
+pub fn execute(
+    action: &BatchAction,
+    vault: Option<&str>,
+    filter: &NoteFilter,
+    use_external: bool,
+) -> Result<()> {
+    // Get configuration
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    // Only offer the notes that pass the filter
+    let mut choices = Vec::new();
+    for choice in vault_choices(&vault_path)? {
+        if filter.matches(&load_note(&vault_path, Path::new(&choice.path))?) {
+            choices.push(choice);
+        }
+    }
+
+    if choices.is_empty() {
+        return Err(anyhow!("No matching notes found in vault: {}", vault_name));
+    }
+
+    let options = PickOptions {
+        external: use_external,
+        multi: true,
+        allow_create: false,
+    };
+    let notes = match pick_notes(&choices, options)? {
+        Picked::Notes(notes) => notes,
+        Picked::Create(_) => return Err(anyhow!("No note selected")),
+    };
+
+    match action {
+        BatchAction::Open => open_in_editor(&notes),
+        BatchAction::Paths => {
+            print_paths(&notes);
+            Ok(())
+        }
+        BatchAction::Move(project) => move_notes(&notes, &vault_path, project),
+        BatchAction::Tag(tag) => tag_notes(&notes, tag),
+        BatchAction::Export(target) => export_notes(&notes, &vault_path, Path::new(target)),
+    }
+}
+
+/// Opens all notes in the default editor with a single invocation.
+/// Encrypted notes are opened one by one after it, each through a
+/// decrypted temporary copy.
+pub fn open_in_editor(notes: &[NoteChoice]) -> Result<()> {
+    let (encrypted, notes): (Vec<&NoteChoice>, Vec<&NoteChoice>) = notes
+        .iter()
+        .partition(|note| crypto::is_encrypted_note(Path::new(&note.path)));
+    if !notes.is_empty() {
+        open_plain_in_editor(&notes)?;
+    }
+    for note in encrypted {
+        println!("Opening note: {}", note.title);
+        history::record_or_warn(Path::new(&note.path), Action::Open);
+        encrypt::edit(Path::new(&note.path), None)?;
+    }
+    Ok(())
+}
+
+fn open_plain_in_editor(notes: &[&NoteChoice]) -> Result<()> {
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+
+    if let [note] = notes {
+        println!("Opening note: {} with {}", note.title, editor);
+    } else {
+        println!("Opening {} notes with {}", notes.len(), editor);
+    }
+
+    for note in notes {
+        history::record_or_warn(Path::new(&note.path), Action::Open);
+    }
+
+    let status = Command::new(&editor)
+        .args(notes.iter().map(|note| &note.path))
+        .status()
+        .context(format!("Failed to open editor '{}' for note", editor))?;
+
+    if !status.success() {
+        return Err(anyhow!("Editor exited with non-zero status"));
+    }
+
+    let paths: Vec<&Path> = notes.iter().map(|note| Path::new(&note.path)).collect();
+    git::auto_commit_or_warn(&paths);
+    Ok(())
+}
+
+/// Prints the absolute path of every note, one per line.
+pub fn print_paths(notes: &[NoteChoice]) {
+    for note in notes {
+        println!("{}", note.path);
+        history::record_or_warn(Path::new(&note.path), Action::Open);
+    }
+}
+
+fn move_notes(notes: &[NoteChoice], vault_path: &Path, project: &str) -> Result<()> {
+    let project = normalize_project(project);
+    let target_dir = vault_path.join(&project);
+    fs::create_dir_all(&target_dir).context(format!(
+        "Failed to create project directory: {}",
+        target_dir.display()
+    ))?;
+
+    run_for_each(notes, "move", |note| {
+        let source = Path::new(&note.path);
+        let file_name = source.file_name().context("Note path has no file name")?;
+        let target = target_dir.join(file_name);
+
+        if target == source {
+            return Ok(());
+        }
+        if target.exists() {
+            return Err(anyhow!("{} already exists", target.display()));
+        }
+
+        fs::rename(source, &target).context("Failed to move note")?;
+        println!(
+            "Moved '{}' to {}",
+            note.title,
+            relative_path(vault_path, &target)
+        );
+        Ok(())
+    })
+}
+
+fn tag_notes(notes: &[NoteChoice], tag: &str) -> Result<()> {
+    run_for_each(notes, "tag", |note| {
+        let content = fs::read_to_string(&note.path).context("Failed to read note")?;
+
+        let mut added = false;
+        let updated = update_frontmatter(&content, |mapping| {
+            added = add_tag(mapping, tag);
+            Ok(())
+        })?;
+
+        if added {
+            fs::write(&note.path, updated).context("Failed to write note")?;
+            println!("Tagged '{}' with '{}'", note.title, tag);
+        }
+        Ok(())
+    })
+}
+
+fn export_notes(notes: &[NoteChoice], vault_path: &Path, target: &Path) -> Result<()> {
+    run_for_each(notes, "export", |note| {
+        // Keep the vault layout so notes with the same file name do not clash
+        let relative = relative_path(vault_path, Path::new(&note.path));
+        let destination = target.join(&relative);
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).context("Failed to create export directory")?;
+        }
+        fs::copy(&note.path, &destination).context("Failed to copy note")?;
+        println!("Exported '{}' to {}", note.title, destination.display());
+        Ok(())
+    })
+}
+
+/// Applies an action to every note, reporting failures per note instead of
+/// stopping at the first one.
+fn run_for_each<F>(notes: &[NoteChoice], verb: &str, mut action: F) -> Result<()>
+where
+    F: FnMut(&NoteChoice) -> Result<()>,
+{
+    let mut failures = 0;
+
+    for note in notes {
+        if let Err(e) = action(note) {
+            eprintln!("Failed to {} '{}': {}", verb, note.title, e);
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!(
+            "Failed to {} {} of {} notes",
+            verb,
+            failures,
+            notes.len()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch_action() {
+        assert_eq!(BatchAction::parse("open", None).unwrap(), BatchAction::Open);
+        assert_eq!(
+            BatchAction::parse("move", Some("archive/2024")).unwrap(),
+            BatchAction::Move("archive/2024".to_string())
+        );
+        assert!(BatchAction::parse("tag", None).is_err());
+        assert!(BatchAction::parse("delete", None).is_err());
+    }
+}
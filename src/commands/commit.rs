@@ -0,0 +1,70 @@
+// src/commands/commit.rs
+use crate::git;
+use crate::metadata::resolve_note_path;
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Result, anyhow};
+use std::path::Path;
+
+/// Commits every change in the vault, with a generated message unless one is given.
+pub fn execute(vault: Option<&str>, message: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    ensure_repository(&vault_name, &vault_path)?;
+
+    let changes = git::commit_all(&vault_path, message)?;
+    if changes.is_empty() {
+        println!("Nothing to commit in vault: {}", vault_name);
+        return Ok(());
+    }
+
+    println!(
+        "Committed {} change{} in vault: {}",
+        changes.len(),
+        if changes.len() == 1 { "" } else { "s" },
+        vault_name
+    );
+    Ok(())
+}
+
+/// Lists the revisions of a note, or prints the note at one revision (or the
+/// changes that revision made with `diff`).
+pub fn history(note: &str, revision: Option<&str>, vault: Option<&str>, diff: bool) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    ensure_repository(&vault_name, &vault_path)?;
+
+    // Deleted notes still have a history, so fall back to the path as given
+    let note = resolve_note_path(&vault_path, note)
+        .unwrap_or_else(|_| note.trim_start_matches("./").to_string());
+
+    if let Some(revision) = revision {
+        let output = if diff {
+            git::show_diff(&vault_path, revision, &note)?
+        } else {
+            git::show_revision(&vault_path, revision, &note)?
+        };
+        print!("{}", output);
+        return Ok(());
+    }
+
+    let revisions = git::file_history(&vault_path, &note)?;
+    if revisions.is_empty() {
+        return Err(anyhow!("No committed revisions of '{}'", note));
+    }
+
+    for revision in revisions {
+        println!("{}  {}  {}", revision.hash, revision.date, revision.subject);
+    }
+    Ok(())
+}
+
+fn ensure_repository(vault_name: &str, vault_path: &Path) -> Result<()> {
+    if !git::is_repository(vault_path) {
+        return Err(anyhow!(
+            "Vault '{}' is not a git repository. Run 'git init' in {} first.",
+            vault_name,
+            vault_path.display()
+        ));
+    }
+    Ok(())
+}
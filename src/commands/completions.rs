@@ -0,0 +1,245 @@
+// src/commands/completions.rs
+use crate::metadata::list_projects;
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Result, anyhow};
+use clap::{App, Shell};
+use std::io::{self, Write};
+
+// Each script asks `ncy __complete` first and falls back to the completions
+// clap generates when it has nothing to offer
+const BASH_SCRIPT: &str = r#"
+_ncy_dynamic() {
+    local words=("${COMP_WORDS[@]:0:COMP_CWORD+1}")
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    # '@' breaks words in bash, so '@proj' arrives as '@' and 'proj'
+    if [ "$cur" = "@" ]; then
+        words+=("")
+        cur=""
+    fi
+
+    local candidates
+    candidates="$(ncy __complete "${words[@]}" 2>/dev/null)"
+    if [ -n "$candidates" ]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "$candidates" -- "$cur"))
+        return 0
+    fi
+    _ncy "$@"
+}
+complete -F _ncy_dynamic -o bashdefault -o default ncy
+"#;
+
+const ZSH_SCRIPT: &str = r#"
+_ncy_dynamic() {
+    local -a candidates
+    candidates=("${(@f)$(ncy __complete "${(@)words[1,CURRENT]}" 2>/dev/null)}")
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+        return
+    fi
+    _ncy "$@"
+}
+compdef _ncy_dynamic ncy
+"#;
+
+// An empty command substitution gives no argument at all in fish, so the
+// word being completed is quoted to reach `ncy __complete` even when empty
+const FISH_SCRIPT: &str = r#"
+function __ncy_complete
+    set -l current (commandline -ct)
+    ncy __complete (commandline -opc) "$current"
+end
+complete -c ncy -a "(__ncy_complete)"
+"#;
+
+/// What the word being completed should be.
+#[derive(Debug, PartialEq)]
+enum Candidates {
+    Vaults,
+    /// Projects of a vault, or of the default vault
+    Projects(Option<String>),
+}
+
+/// Prints the completion script for a shell: clap's completions for the
+/// commands and options, plus dynamic vault and project names.
+pub fn execute(shell: &str, mut app: App) -> Result<()> {
+    let (clap_shell, script) = match shell {
+        "bash" => (Shell::Bash, BASH_SCRIPT),
+        "zsh" => (Shell::Zsh, ZSH_SCRIPT),
+        "fish" => (Shell::Fish, FISH_SCRIPT),
+        _ => {
+            return Err(anyhow!(
+                "Unsupported shell '{}'. Supported shells are: bash, zsh, fish",
+                shell
+            ));
+        }
+    };
+
+    let mut generated = Vec::new();
+    app.gen_completions_to("ncy", clap_shell, &mut generated);
+    let mut generated = String::from_utf8(generated)?;
+    if shell == "zsh" {
+        // The generated file is meant for $fpath, where it ends by running
+        // itself; when sourced, the function is registered below instead
+        generated = generated.replace("\n_ncy \"$@\"", "\n");
+    }
+
+    let mut stdout = io::stdout();
+    write!(stdout, "{}{}", generated.trim_end(), script)?;
+    Ok(())
+}
+
+/// Prints the candidates for the last of the given words, one per line.
+/// `words` is the command line from `ncy` up to the word being completed.
+pub fn complete(words: &[&str]) -> Result<()> {
+    let Some(current) = words.last() else {
+        return Ok(());
+    };
+    let Some((candidates, prefix)) = candidates_for(words) else {
+        return Ok(());
+    };
+
+    let config = read_config()?;
+    let names = match candidates {
+        Candidates::Vaults => config
+            .get("vaults")
+            .and_then(|v| v.as_array())
+            .map(|vaults| {
+                vaults
+                    .iter()
+                    .filter_map(|vault| vault.get("name").and_then(|n| n.as_str()))
+                    .map(|name| name.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Candidates::Projects(vault) => {
+            let (_, vault_path) = resolve_vault(&config, vault.as_deref())?;
+            list_projects(&vault_path)
+        }
+    };
+
+    for name in names {
+        let candidate = format!("{}{}", prefix, name);
+        if candidate.starts_with(current) {
+            println!("{}", candidate);
+        }
+    }
+    Ok(())
+}
+
+// What to complete for the last word, and the prefix candidates start with
+fn candidates_for(words: &[&str]) -> Option<(Candidates, &'static str)> {
+    // Skip the program name and global flags before the subcommand
+    let words: Vec<&str> = words
+        .iter()
+        .skip(1)
+        .copied()
+        .skip_while(|word| word.starts_with('-'))
+        .collect();
+    // `ncy @project` picks notes within a project
+    if let [current] = words[..]
+        && current.starts_with('@')
+    {
+        return Some((Candidates::Projects(None), "@"));
+    }
+    if words.len() < 2 {
+        return None;
+    }
+    let subcommand = words[0];
+    let current = words[words.len() - 1];
+    let previous = words[words.len() - 2];
+
+    if previous == "-v" || previous == "--vault" {
+        return Some((Candidates::Vaults, ""));
+    }
+
+    match subcommand {
+        "new" | "n" => {
+            let earlier = &words[1..words.len() - 1];
+            if current.starts_with('+') {
+                Some((Candidates::Vaults, "+"))
+            } else if previous == "+" {
+                Some((Candidates::Vaults, ""))
+            } else if current.starts_with('@') {
+                Some((Candidates::Projects(vault_in(earlier)), "@"))
+            } else if previous == "@" {
+                Some((Candidates::Projects(vault_in(earlier)), ""))
+            } else {
+                None
+            }
+        }
+        // bash splits '@project' into '@' and 'project'
+        "@" if words.len() == 2 => Some((Candidates::Projects(None), "")),
+        "project" if words.len() == 3 && words[1] == "archive" && !current.starts_with('-') => {
+            Some((Candidates::Projects(None), ""))
+        }
+        "set" if words.len() == 2 && !current.starts_with('-') => Some((Candidates::Vaults, "")),
+        _ => None,
+    }
+}
+
+// The vault named with '+' among the words of `ncy new`
+fn vault_in(words: &[&str]) -> Option<String> {
+    words.iter().enumerate().find_map(|(i, word)| {
+        let name = match word.strip_prefix('+')? {
+            "" => *words.get(i + 1)?,
+            name => name,
+        };
+        Some(name.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_for_new() {
+        assert_eq!(
+            candidates_for(&["ncy", "new", "Plan", "+wo"]),
+            Some((Candidates::Vaults, "+"))
+        );
+        assert_eq!(
+            candidates_for(&["ncy", "-e", "n", "Plan", "@", "pro"]),
+            Some((Candidates::Projects(None), ""))
+        );
+        assert_eq!(
+            candidates_for(&["ncy", "new", "Plan", "+work", "@pro"]),
+            Some((Candidates::Projects(Some("work".to_string())), "@"))
+        );
+        assert_eq!(candidates_for(&["ncy", "new", "Pla"]), None);
+    }
+
+    #[test]
+    fn test_candidates_for_vault_names() {
+        assert_eq!(
+            candidates_for(&["ncy", "set", ""]),
+            Some((Candidates::Vaults, ""))
+        );
+        assert_eq!(
+            candidates_for(&["ncy", "ls", "--vault", "m"]),
+            Some((Candidates::Vaults, ""))
+        );
+        assert_eq!(
+            candidates_for(&["ncy", "new", "--vault", ""]),
+            Some((Candidates::Vaults, ""))
+        );
+        assert_eq!(candidates_for(&["ncy", "set", "main", ""]), None);
+        assert_eq!(candidates_for(&["ncy", "s"]), None);
+        assert_eq!(
+            candidates_for(&["ncy", "@pro"]),
+            Some((Candidates::Projects(None), "@"))
+        );
+        assert_eq!(
+            candidates_for(&["ncy", "project", "archive", "pro"]),
+            Some((Candidates::Projects(None), ""))
+        );
+    }
+
+    #[test]
+    fn test_scripts_pass_empty_word() {
+        // Without the empty word, `ncy set <TAB>` would complete "set" itself
+        assert_eq!(candidates_for(&["ncy", "set"]), None);
+        assert!(FISH_SCRIPT.contains(r#"(commandline -opc) "$current""#));
+    }
+}
@@ -0,0 +1,108 @@
+// src/commands/encrypt.rs
+use crate::crypto::{self, PrivateDir};
+use crate::git;
+use crate::metadata::{find_note, relative_path};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use notemancy_core::notes::utils::get_title;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use zeroize::Zeroizing;
+
+/// Encrypts the body of a note with a passphrase.
+pub fn encrypt(note: &str, vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+    let note_path = find_note(&vault_path, note)?;
+    let passphrase = crypto::read_passphrase(true)?;
+
+    encrypt_file(&note_path, &passphrase)?;
+    println!("Encrypted {}", relative_path(&vault_path, &note_path));
+    if git::is_repository(&vault_path) {
+        println!("Earlier versions of the note remain readable in the vault's git history");
+    }
+    git::auto_commit_or_warn(&[&note_path]);
+    Ok(())
+}
+
+/// Decrypts an encrypted note back to plain text.
+pub fn decrypt(note: &str, vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+    let note_path = find_note(&vault_path, note)?;
+    let content = read_note(&note_path)?;
+    if !crypto::is_encrypted(&content) {
+        return Err(anyhow!("The note is not encrypted: {}", note));
+    }
+
+    let passphrase = crypto::read_passphrase(false)?;
+    let plain = crypto::decrypt_note(&content, &passphrase)?;
+    fs::write(&note_path, plain.as_str())
+        .context(format!("Failed to write note: {}", note_path.display()))?;
+
+    println!("Decrypted {}", relative_path(&vault_path, &note_path));
+    git::auto_commit_or_warn(&[&note_path]);
+    Ok(())
+}
+
+/// Encrypts a note in place with the given passphrase.
+pub fn encrypt_file(note_path: &Path, passphrase: &str) -> Result<()> {
+    let content = read_note(note_path)?;
+    let title = get_title(note_path)?;
+    let encrypted = crypto::encrypt_note(&content, &title, passphrase)?;
+    fs::write(note_path, encrypted)
+        .context(format!("Failed to write note: {}", note_path.display()))
+}
+
+/// Opens an encrypted note in the editor: the note is decrypted into a
+/// private temporary file, and whatever the editor saved there is encrypted
+/// back into the note. The temporary file is wiped afterwards, also when
+/// something fails. The passphrase is asked for unless given.
+pub fn edit(note_path: &Path, passphrase: Option<&str>) -> Result<()> {
+    let content = read_note(note_path)?;
+    let passphrase = match passphrase {
+        Some(passphrase) => Zeroizing::new(passphrase.to_string()),
+        None => crypto::read_passphrase(false)?,
+    };
+    let plain = crypto::decrypt_note(&content, &passphrase)?;
+
+    let private = PrivateDir::new()?;
+    let file_name = note_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "note.md".to_string());
+    let temp_path = private.write(&file_name, &plain)?;
+
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+    println!("Opening encrypted note with {}", editor);
+
+    let status = Command::new(&editor)
+        .arg(&temp_path)
+        .status()
+        .context(format!("Failed to open editor '{}' for note", editor))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "Editor exited with non-zero status, the note was left unchanged"
+        ));
+    }
+
+    let edited =
+        Zeroizing::new(fs::read_to_string(&temp_path).context("Failed to read the edited note")?);
+    // An unchanged note keeps its encrypted block, so it does not show up as changed in git
+    if *edited == *plain {
+        return Ok(());
+    }
+
+    let title = get_title(&temp_path)?;
+    let encrypted = crypto::encrypt_note(&edited, &title, &passphrase)?;
+    fs::write(note_path, encrypted)
+        .context(format!("Failed to write note: {}", note_path.display()))?;
+    git::auto_commit_or_warn(&[note_path]);
+    Ok(())
+}
+
+fn read_note(note_path: &Path) -> Result<String> {
+    fs::read_to_string(note_path).context(format!("Failed to read note: {}", note_path.display()))
+}
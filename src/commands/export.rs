@@ -0,0 +1,631 @@
+// src/commands/export.rs
+use crate::commands::ls::{normalize_project, project_matches};
+use crate::commands::query::QUERIES_DIR;
+use crate::links::{LinkIndex, WikiLink, relative_url, replace_wikilinks};
+use crate::metadata::{load_vault, relative_path, split_frontmatter};
+use crate::schema::SCHEMA_FILE;
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, html};
+use serde_json::{Value as JsonValue, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{self, Path};
+
+// Written to the output directory so a later export knows it may clear it
+const EXPORT_MARKER: &str = ".ncy-export";
+// Generated at the export root; a note page never gets these names and an
+// attachment with one of them stops the export
+const TAGS_PAGE: &str = "_tags.html";
+const SEARCH_INDEX: &str = "_search.json";
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+nav{margin-bottom:1.5rem}\
+.tags a{margin-right:.5rem}\
+.missing-link{color:#b00;text-decoration:underline dotted}\
+pre{overflow-x:auto;background:#f4f4f4;padding:.5rem}";
+
+/// A note to be rendered, with paths relative to the export root.
+#[derive(Debug, Clone)]
+pub struct SourceNote {
+    pub path: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub body: String,
+}
+
+pub fn execute(
+    format: &str,
+    outdir: &Path,
+    vault: Option<&str>,
+    project: Option<&str>,
+) -> Result<()> {
+    if format != "html" {
+        return Err(anyhow!("Unknown export format '{}'", format));
+    }
+
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    // The export root is either the whole vault or a project subtree
+    let project = normalize_project(project.unwrap_or_default());
+    let root_path = vault_path.join(&project);
+    if !root_path.is_dir() {
+        return Err(anyhow!("Project not found in vault: {}", project));
+    }
+
+    let mut notes = Vec::new();
+    for note in load_vault(&vault_path)? {
+        if !project_matches(&note.project, &project) {
+            continue;
+        }
+
+        let content = fs::read_to_string(&note.path)
+            .context(format!("Failed to read note: {}", note.path.display()))?;
+        notes.push(SourceNote {
+            path: relative_path(&root_path, &note.path),
+            title: note.title,
+            tags: note.tags,
+            body: split_frontmatter(&content).1.to_string(),
+        });
+    }
+
+    if notes.is_empty() {
+        return Err(anyhow!("No notes to export in vault: {}", vault_name));
+    }
+
+    let attachments = list_attachments(&root_path, project.is_empty())?;
+    let site_title = if project.is_empty() {
+        vault_name
+    } else {
+        project
+    };
+    let site = render_site(&site_title, &notes, &attachments)?;
+
+    prepare_output_dir(outdir, &root_path)?;
+
+    for (path, content) in &site {
+        write_file(&outdir.join(path), content.as_bytes())?;
+    }
+    for attachment in &attachments {
+        let target = outdir.join(attachment);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).context("Failed to create export directory")?;
+        }
+        fs::copy(root_path.join(attachment), &target)
+            .context(format!("Failed to copy attachment: {}", attachment))?;
+    }
+    write_file(&outdir.join(EXPORT_MARKER), b"")?;
+
+    println!(
+        "Exported {} notes and {} attachments to {}",
+        notes.len(),
+        attachments.len(),
+        outdir.display()
+    );
+    Ok(())
+}
+
+/// Renders the whole site to a map of output path to file content. Notes
+/// are processed in path order and nothing depends on the clock or on the
+/// file system, so the same notes always give the same site.
+pub fn render_site(
+    site_title: &str,
+    notes: &[SourceNote],
+    attachments: &[String],
+) -> Result<BTreeMap<String, String>> {
+    let mut notes: Vec<&SourceNote> = notes.iter().collect();
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let index = LinkIndex::new(notes.iter().map(|n| (n.path.as_str(), n.title.as_str())));
+    let attachment_names: BTreeMap<String, &str> = attachments
+        .iter()
+        .rev()
+        .map(|a| (file_name(a).to_lowercase(), a.as_str()))
+        .collect();
+
+    let mut site = BTreeMap::new();
+    let mut search = Vec::new();
+    let mut tags: BTreeMap<String, (String, Vec<&SourceNote>)> = BTreeMap::new();
+
+    for note in &notes {
+        let output = page_path(&note.path);
+
+        let markdown = replace_wikilinks(&note.body, |link| {
+            render_wikilink(link, &output, &index, attachments, &attachment_names)
+        });
+        let (content, text) = render_markdown(&markdown);
+
+        site.insert(
+            output.clone(),
+            page(
+                &note.title,
+                &output,
+                &render_tags(&note.tags, &output),
+                &content,
+            ),
+        );
+
+        search.push(json!({
+            "title": note.title,
+            "url": output,
+            "project": parent_dir(&note.path),
+            "tags": note.tags,
+            "text": text,
+        }));
+
+        for tag in &note.tags {
+            tags.entry(tag.to_lowercase())
+                .or_insert_with(|| (tag.clone(), Vec::new()))
+                .1
+                .push(note);
+        }
+    }
+
+    for (dir, content) in render_directory_indexes(site_title, &notes) {
+        site.insert(dir, content);
+    }
+    site.insert(TAGS_PAGE.to_string(), render_tag_index(&tags));
+
+    let mut search_json = serde_json::to_string_pretty(&JsonValue::Array(search))
+        .context("Failed to serialize search index")?;
+    search_json.push('\n');
+    site.insert(SEARCH_INDEX.to_string(), search_json);
+
+    if let Some(attachment) = attachments.iter().find(|a| site.contains_key(a.as_str())) {
+        return Err(anyhow!(
+            "Attachment has the name of a generated page: {}",
+            attachment
+        ));
+    }
+
+    Ok(site)
+}
+
+/// Turns a wikilink into a markdown link to the exported page or attachment.
+/// Links that do not resolve are kept visible but marked as missing.
+fn render_wikilink(
+    link: &WikiLink,
+    from: &str,
+    index: &LinkIndex,
+    attachments: &[String],
+    attachment_names: &BTreeMap<String, &str>,
+) -> String {
+    let label = escape_markdown(link.label());
+
+    // A link to a heading of the same note
+    if link.target.is_empty() {
+        return label;
+    }
+
+    if let Some(note) = index.resolve(&link.target) {
+        return format!("[{}](<{}>)", label, relative_url(from, &page_path(note)));
+    }
+
+    let attachment = attachments
+        .iter()
+        .find(|a| a.eq_ignore_ascii_case(&link.target))
+        .map(|a| a.as_str())
+        .or_else(|| attachment_names.get(&link.target.to_lowercase()).copied());
+    if let Some(attachment) = attachment {
+        let url = relative_url(from, attachment);
+        return if link.embed && is_image(attachment) {
+            format!("![{}](<{}>)", label, url)
+        } else {
+            format!("[{}](<{}>)", label, url)
+        };
+    }
+
+    format!(
+        "<span class=\"missing-link\">{}</span>",
+        escape_html(link.label())
+    )
+}
+
+/// Renders markdown to HTML and also returns its plain text for searching.
+/// Relative links to other `.md` files are pointed at their exported pages.
+fn render_markdown(markdown: &str) -> (String, String) {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+
+    let mut text = String::new();
+    let events: Vec<Event> = Parser::new_ext(markdown, options)
+        .map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: rewrite_md_link(dest_url),
+                title,
+                id,
+            }),
+            event => event,
+        })
+        .inspect(|event| match event {
+            Event::Text(t) | Event::Code(t) => {
+                text.push_str(t);
+            }
+            Event::SoftBreak | Event::HardBreak | Event::End(_) if !text.ends_with(' ') => {
+                text.push(' ');
+            }
+            _ => {}
+        })
+        .collect();
+
+    let mut content = String::new();
+    html::push_html(&mut content, events.into_iter());
+
+    (content, text.trim().to_string())
+}
+
+fn rewrite_md_link(url: CowStr) -> CowStr {
+    if url.contains("://") || url.starts_with('#') || url.starts_with('/') {
+        return url;
+    }
+
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (url.as_ref(), None),
+    };
+    match path.strip_suffix(".md") {
+        Some(stem) => {
+            let mut rewritten = page_path(&format!("{}.md", stem));
+            if let Some(fragment) = fragment {
+                rewritten.push('#');
+                rewritten.push_str(fragment);
+            }
+            CowStr::from(rewritten)
+        }
+        None => url,
+    }
+}
+
+fn render_tags(tags: &[String], from: &str) -> String {
+    if tags.is_empty() {
+        return String::new();
+    }
+
+    let links: Vec<String> = tags
+        .iter()
+        .map(|tag| {
+            format!(
+                "<a href=\"{}#{}\">#{}</a>",
+                relative_url(from, TAGS_PAGE),
+                tag_anchor(tag),
+                escape_html(tag)
+            )
+        })
+        .collect();
+    format!("<p class=\"tags\">{}</p>\n", links.join(""))
+}
+
+/// Builds an `index.html` for every directory that contains notes, listing
+/// its subdirectories and notes.
+fn render_directory_indexes(site_title: &str, notes: &[&SourceNote]) -> Vec<(String, String)> {
+    let mut dirs: BTreeSet<String> = BTreeSet::new();
+    for note in notes {
+        let mut dir = parent_dir(&note.path);
+        loop {
+            dirs.insert(dir.clone());
+            if dir.is_empty() {
+                break;
+            }
+            dir = parent_dir(&dir);
+        }
+    }
+
+    let mut pages = Vec::new();
+    for dir in &dirs {
+        let output = if dir.is_empty() {
+            "index.html".to_string()
+        } else {
+            format!("{}/index.html", dir)
+        };
+
+        let mut body = String::new();
+        let subdirs: Vec<&String> = dirs
+            .iter()
+            .filter(|d| !d.is_empty() && parent_dir(d) == *dir)
+            .collect();
+        if !subdirs.is_empty() {
+            body.push_str("<h2>Projects</h2>\n<ul>\n");
+            for subdir in subdirs {
+                body.push_str(&format!(
+                    "<li><a href=\"{}\">{}/</a></li>\n",
+                    relative_url(&output, &format!("{}/index.html", subdir)),
+                    escape_html(file_name(subdir))
+                ));
+            }
+            body.push_str("</ul>\n");
+        }
+
+        let mut dir_notes: Vec<&&SourceNote> = notes
+            .iter()
+            .filter(|note| parent_dir(&note.path) == *dir)
+            .collect();
+        dir_notes.sort_by(|a, b| {
+            (a.title.to_lowercase(), &a.path).cmp(&(b.title.to_lowercase(), &b.path))
+        });
+        if !dir_notes.is_empty() {
+            body.push_str("<h2>Notes</h2>\n");
+            body.push_str(&note_list(&dir_notes, &output));
+        }
+
+        let title = if dir.is_empty() { site_title } else { dir };
+        pages.push((output.clone(), page(title, &output, "", &body)));
+    }
+
+    pages
+}
+
+fn render_tag_index(tags: &BTreeMap<String, (String, Vec<&SourceNote>)>) -> String {
+    let mut body = String::new();
+    for (tag, notes) in tags.values() {
+        body.push_str(&format!(
+            "<h2 id=\"{}\">#{} ({})</h2>\n",
+            tag_anchor(tag),
+            escape_html(tag),
+            notes.len()
+        ));
+        let notes: Vec<&&SourceNote> = notes.iter().collect();
+        body.push_str(&note_list(&notes, TAGS_PAGE));
+    }
+
+    page("Tags", TAGS_PAGE, "", &body)
+}
+
+fn note_list(notes: &[&&SourceNote], from: &str) -> String {
+    let mut list = String::from("<ul>\n");
+    for note in notes {
+        list.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            relative_url(from, &page_path(&note.path)),
+            escape_html(&note.title)
+        ));
+    }
+    list.push_str("</ul>\n");
+    list
+}
+
+fn page(title: &str, output: &str, header: &str, content: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+<html lang=\"en\">\n\
+<head>\n\
+<meta charset=\"utf-8\">\n\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+<title>{title}</title>\n\
+<style>{style}</style>\n\
+</head>\n\
+<body>\n\
+<nav><a href=\"{index}\">Index</a> &middot; <a href=\"{tags}\">Tags</a></nav>\n\
+<main>\n\
+<h1>{title}</h1>\n\
+{header}{content}</main>\n\
+</body>\n\
+</html>\n",
+        title = escape_html(title),
+        style = STYLE,
+        index = relative_url(output, "index.html"),
+        tags = relative_url(output, TAGS_PAGE),
+        header = header,
+        content = content,
+    )
+}
+
+/// Output path of a note page: `.md` becomes `.html`. A note called `index`
+/// gets another name since every directory has a generated `index.html`, and
+/// so does one that would take the place of the tags page.
+fn page_path(note_path: &str) -> String {
+    let stem = note_path.strip_suffix(".md").unwrap_or(note_path);
+    if file_name(stem) == "index" || format!("{}.html", stem) == TAGS_PAGE {
+        format!("{}-note.html", stem)
+    } else {
+        format!("{}.html", stem)
+    }
+}
+
+fn parent_dir(path: &str) -> String {
+    path.rsplit_once('/')
+        .map(|(dir, _)| dir.to_string())
+        .unwrap_or_default()
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn tag_anchor(tag: &str) -> String {
+    let slug: String = tag
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("tag-{}", slug)
+}
+
+fn is_image(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"].contains(&extension.as_str())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '[' | ']' | '\\' | '*' | '_' | '`' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Lists the non-markdown files below the export root, relative to it.
+/// Hidden files are skipped, as are the vault's `workspaces/` and `queries/`
+/// folders and its schema file.
+fn list_attachments(root_path: &Path, is_vault_root: bool) -> Result<Vec<String>> {
+    fn walk(dir: &Path, root: &Path, skip_vault_files: bool, out: &mut Vec<String>) -> Result<()> {
+        for entry in fs::read_dir(dir).context(format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let vault_file = name == "workspaces" || name == QUERIES_DIR || name == SCHEMA_FILE;
+            if name.starts_with('.') || (skip_vault_files && dir == root && vault_file) {
+                continue;
+            }
+
+            if path.is_dir() {
+                walk(&path, root, skip_vault_files, out)?;
+            } else if path.extension().is_none_or(|ext| ext != "md") {
+                out.push(relative_path(root, &path));
+            }
+        }
+        Ok(())
+    }
+
+    let mut attachments = Vec::new();
+    walk(root_path, root_path, is_vault_root, &mut attachments)?;
+    attachments.sort();
+    Ok(attachments)
+}
+
+/// Makes sure the output directory is empty or holds a previous export, which
+/// is cleared so stale pages do not survive.
+fn prepare_output_dir(outdir: &Path, root_path: &Path) -> Result<()> {
+    let outdir_abs = path::absolute(outdir).context("Failed to resolve output directory")?;
+    let root_abs = path::absolute(root_path).context("Failed to resolve vault directory")?;
+    if outdir_abs.starts_with(&root_abs) || root_abs.starts_with(&outdir_abs) {
+        return Err(anyhow!(
+            "The output directory must not overlap the exported notes: {}",
+            outdir.display()
+        ));
+    }
+
+    if !outdir.exists() {
+        return fs::create_dir_all(outdir).context(format!(
+            "Failed to create output directory: {}",
+            outdir.display()
+        ));
+    }
+
+    let entries: Vec<_> = fs::read_dir(outdir)
+        .context("Failed to read output directory")?
+        .collect::<Result<_, _>>()?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    if !outdir.join(EXPORT_MARKER).exists() {
+        return Err(anyhow!(
+            "Output directory is not empty and does not hold a previous export: {}",
+            outdir.display()
+        ));
+    }
+
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        }
+        .context(format!(
+            "Failed to remove old export file: {}",
+            path.display()
+        ))?;
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create export directory")?;
+    }
+    fs::write(path, content).context(format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(path: &str, title: &str, tags: &[&str], body: &str) -> SourceNote {
+        SourceNote {
+            path: path.to_string(),
+            title: title.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_page_path() {
+        assert_eq!(page_path("projects/alpha.md"), "projects/alpha.html");
+        assert_eq!(page_path("projects/index.md"), "projects/index-note.html");
+        assert_eq!(page_path("_tags.md"), "_tags-note.html");
+        assert_eq!(page_path("projects/_tags.md"), "projects/_tags.html");
+    }
+
+    #[test]
+    fn test_render_site_links_and_indexes() {
+        let notes = vec![
+            note(
+                "research/alpha.md",
+                "Alpha",
+                &["ml"],
+                "See [[Beta|the beta]] and [[nowhere]].\n",
+            ),
+            note(
+                "beta.md",
+                "Beta",
+                &["ML", "draft"],
+                "Back to [[research/alpha]], [link](research/alpha.md).\n",
+            ),
+        ];
+        let attachments = vec!["research/diagram.png".to_string()];
+        let site = render_site("main", &notes, &attachments).unwrap();
+
+        let alpha = &site["research/alpha.html"];
+        assert!(alpha.contains("<a href=\"../beta.html\">the beta</a>"));
+        assert!(alpha.contains("<span class=\"missing-link\">nowhere</span>"));
+        assert!(alpha.contains("href=\"../_tags.html#tag-ml\""));
+
+        let beta = &site["beta.html"];
+        assert!(beta.contains("<a href=\"research/alpha.html\">research/alpha</a>"));
+        assert!(beta.contains("<a href=\"research/alpha.html\">link</a>"));
+
+        assert!(site["index.html"].contains("<a href=\"research/index.html\">research/</a>"));
+        assert!(site["research/index.html"].contains("<a href=\"alpha.html\">Alpha</a>"));
+        assert!(site[TAGS_PAGE].contains("#ML (2)"));
+        assert!(site[SEARCH_INDEX].contains("\"url\": \"beta.html\""));
+
+        let clashing = vec!["research/index.html".to_string()];
+        assert!(render_site("main", &notes, &clashing).is_err());
+    }
+
+    #[test]
+    fn test_render_site_is_deterministic() {
+        let notes = vec![
+            note("b.md", "B", &["x"], "![[pic.png]] [[a]]"),
+            note("a.md", "A", &["y", "x"], "# A\n\ntext"),
+        ];
+        let mut reversed = notes.clone();
+        reversed.reverse();
+        let attachments = vec!["img/pic.png".to_string()];
+
+        let first = render_site("main", &notes, &attachments).unwrap();
+        let second = render_site("main", &reversed, &attachments).unwrap();
+        assert_eq!(first, second);
+        assert!(first["b.html"].contains("<img src=\"img/pic.png\" alt=\"pic.png\" />"));
+    }
+}
@@ -0,0 +1,425 @@
+// src/commands/graph.rs
+use crate::commands::ls::NoteFilter;
+use crate::index::NoteIndex;
+use crate::links::{LinkIndex, find_markdown_links, find_wikilinks, resolve_relative};
+use crate::metadata::{find_note, relative_path};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+    Json,
+}
+
+impl GraphFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(anyhow!("Unknown graph format '{}'", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LinkKind {
+    Wikilink,
+    Embed,
+    Markdown,
+}
+
+impl LinkKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            LinkKind::Wikilink => "wikilink",
+            LinkKind::Embed => "embed",
+            LinkKind::Markdown => "markdown",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "wikilink" => Some(LinkKind::Wikilink),
+            "embed" => Some(LinkKind::Embed),
+            "markdown" => Some(LinkKind::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A link in the body of a note, with its target as written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteLink {
+    pub target: String,
+    pub kind: LinkKind,
+}
+
+/// A note in the graph, identified by its path relative to the vault.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub title: String,
+    pub project: String,
+    pub tags: Vec<String>,
+}
+
+/// Links of one kind from one note to another. `count` is how many times the
+/// source links to the target that way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub source: String,
+    pub target: String,
+    pub kind: LinkKind,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Which part of the graph to print, and how.
+pub struct GraphOptions<'a> {
+    pub format: GraphFormat,
+    pub filter: NoteFilter,
+    /// Only keep notes within `depth` links of this note, in either direction
+    pub note: Option<&'a str>,
+    pub depth: usize,
+}
+
+/// Prints the link graph of a vault. Links that do not resolve to a note,
+/// such as links to attachments or missing notes, are left out.
+pub fn execute(vault: Option<&str>, options: &GraphOptions) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    let index = NoteIndex::load_or_build(&vault_path)?;
+    let mut sources = Vec::new();
+    let mut included = BTreeSet::new();
+    for note in index.notes() {
+        let node = Node {
+            id: relative_path(&vault_path, &note.meta.path),
+            title: note.meta.title.clone(),
+            project: note.meta.project.clone(),
+            tags: note.meta.tags.clone(),
+        };
+        if options.filter.matches(&note.meta) {
+            included.insert(node.id.clone());
+        }
+        sources.push((node, note.links.clone()));
+    }
+
+    let mut graph = build(&sources);
+    graph.retain(|id| included.contains(id));
+
+    if let Some(note) = options.note {
+        let center = relative_path(&vault_path, &find_note(&vault_path, note)?);
+        if !included.contains(&center) {
+            return Err(anyhow!(
+                "Note '{}' is excluded by the filters in vault '{}'",
+                note,
+                vault_name
+            ));
+        }
+        let nearby = graph.neighborhood(&center, options.depth);
+        graph.retain(|id| nearby.contains(id));
+    }
+
+    let output = match options.format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::GraphMl => graph.to_graphml(),
+        GraphFormat::Json => {
+            serde_json::to_string_pretty(&graph.to_json()).context("Failed to serialize graph")?
+        }
+    };
+    println!("{}", output.trim_end());
+
+    Ok(())
+}
+
+/// Finds the wikilinks, embeds and markdown links in the body of a note.
+pub fn note_links(body: &str) -> Vec<NoteLink> {
+    let wikilinks = find_wikilinks(body).into_iter().map(|link| NoteLink {
+        kind: if link.embed {
+            LinkKind::Embed
+        } else {
+            LinkKind::Wikilink
+        },
+        target: link.target,
+    });
+    let markdown_links = find_markdown_links(body)
+        .into_iter()
+        .map(|target| NoteLink {
+            target,
+            kind: LinkKind::Markdown,
+        });
+    wikilinks.chain(markdown_links).collect()
+}
+
+/// Builds the graph from notes and the links in their bodies. Wikilinks
+/// resolve like they do everywhere else; markdown links resolve relative to
+/// the linking note. Links from a note to itself are skipped.
+pub fn build(notes: &[(Node, Vec<NoteLink>)]) -> Graph {
+    let index = LinkIndex::new(
+        notes
+            .iter()
+            .map(|(node, _)| (node.id.as_str(), node.title.as_str())),
+    );
+
+    let mut counts: BTreeMap<(String, String, LinkKind), usize> = BTreeMap::new();
+    for (node, links) in notes {
+        for link in links {
+            let target = match link.kind {
+                LinkKind::Markdown => index.resolve(&resolve_relative(&node.id, &link.target)),
+                _ => index.resolve(&link.target),
+            };
+            if let Some(target) = target.filter(|target| *target != node.id) {
+                *counts
+                    .entry((node.id.clone(), target.to_string(), link.kind))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut nodes: Vec<Node> = notes.iter().map(|(node, _)| node.clone()).collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Graph {
+        nodes,
+        edges: counts
+            .into_iter()
+            .map(|((source, target, kind), count)| Edge {
+                source,
+                target,
+                kind,
+                count,
+            })
+            .collect(),
+    }
+}
+
+impl Graph {
+    /// Keeps the nodes whose id passes the check, and the edges between them.
+    pub fn retain<F: Fn(&String) -> bool>(&mut self, keep: F) {
+        self.nodes.retain(|node| keep(&node.id));
+        self.edges
+            .retain(|edge| keep(&edge.source) && keep(&edge.target));
+    }
+
+    /// Ids of the nodes at most `depth` links away from `center`, following
+    /// links in both directions.
+    pub fn neighborhood(&self, center: &str, depth: usize) -> BTreeSet<String> {
+        let mut adjacent: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in &self.edges {
+            adjacent.entry(&edge.source).or_default().push(&edge.target);
+            adjacent.entry(&edge.target).or_default().push(&edge.source);
+        }
+
+        let mut seen = BTreeSet::from([center.to_string()]);
+        let mut queue = VecDeque::from([(center, 0)]);
+        while let Some((id, distance)) = queue.pop_front() {
+            if distance == depth {
+                continue;
+            }
+            for next in adjacent.get(id).into_iter().flatten() {
+                if seen.insert(next.to_string()) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+
+        seen
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph notes {\n    node [shape=box];\n");
+        for node in &self.nodes {
+            dot.push_str(&format!(
+                "    {} [label={}, project={}, tags={}];\n",
+                dot_string(&node.id),
+                dot_string(&node.title),
+                dot_string(&node.project),
+                dot_string(&node.tags.join(","))
+            ));
+        }
+        for edge in &self.edges {
+            let mut attributes = vec![format!("type={}", dot_string(edge.kind.name()))];
+            match edge.kind {
+                LinkKind::Wikilink => {}
+                LinkKind::Embed => attributes.push("style=dashed".to_string()),
+                LinkKind::Markdown => attributes.push("style=dotted".to_string()),
+            }
+            if edge.count > 1 {
+                attributes.push(format!("weight={}", edge.count));
+            }
+            dot.push_str(&format!(
+                "    {} -> {} [{}];\n",
+                dot_string(&edge.source),
+                dot_string(&edge.target),
+                attributes.join(", ")
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+             <key id=\"title\" for=\"node\" attr.name=\"title\" attr.type=\"string\"/>\n  \
+             <key id=\"project\" for=\"node\" attr.name=\"project\" attr.type=\"string\"/>\n  \
+             <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n  \
+             <key id=\"type\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n  \
+             <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n  \
+             <graph id=\"notes\" edgedefault=\"directed\">\n",
+        );
+        for node in &self.nodes {
+            xml.push_str(&format!(
+                "    <node id=\"{}\">\n      \
+                 <data key=\"title\">{}</data>\n      \
+                 <data key=\"project\">{}</data>\n      \
+                 <data key=\"tags\">{}</data>\n    \
+                 </node>\n",
+                escape_xml(&node.id),
+                escape_xml(&node.title),
+                escape_xml(&node.project),
+                escape_xml(&node.tags.join(","))
+            ));
+        }
+        for edge in &self.edges {
+            xml.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n      \
+                 <data key=\"type\">{}</data>\n      \
+                 <data key=\"weight\">{}</data>\n    \
+                 </edge>\n",
+                escape_xml(&edge.source),
+                escape_xml(&edge.target),
+                edge.kind.name(),
+                edge.count
+            ));
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "nodes": self.nodes.iter().map(|node| json!({
+                "id": node.id,
+                "title": node.title,
+                "project": node.project,
+                "tags": node.tags,
+            })).collect::<Vec<_>>(),
+            "edges": self.edges.iter().map(|edge| json!({
+                "source": edge.source,
+                "target": edge.target,
+                "type": edge.kind.name(),
+                "count": edge.count,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+// Quotes a DOT identifier, escaping backslashes, quotes and newlines
+fn dot_string(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: &str, title: &str, body: &str) -> (Node, Vec<NoteLink>) {
+        let project = id.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        let node = Node {
+            id: id.to_string(),
+            title: title.to_string(),
+            project: project.to_string(),
+            tags: Vec::new(),
+        };
+        (node, note_links(body))
+    }
+
+    fn sample() -> Graph {
+        build(&[
+            note(
+                "a.md",
+                "Alpha",
+                "[[Beta]] [[beta]] ![[c]] [[a]] [[missing]]",
+            ),
+            note("x/b.md", "Beta", "[back](../a.md) [img](pic.png)"),
+            note("x/c.md", "Gamma", "[[d]]"),
+            note("d.md", "Delta \"D\"", ""),
+        ])
+    }
+
+    #[test]
+    fn test_build_graph() {
+        let graph = sample();
+        let edges: Vec<(&str, &str, &str, usize)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.kind.name(), e.count))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("a.md", "x/b.md", "wikilink", 2),
+                ("a.md", "x/c.md", "embed", 1),
+                ("x/b.md", "a.md", "markdown", 1),
+                ("x/c.md", "d.md", "wikilink", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        let graph = sample();
+        let ids = |depth| {
+            graph
+                .neighborhood("x/b.md", depth)
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(0), vec!["x/b.md"]);
+        assert_eq!(ids(1), vec!["a.md", "x/b.md"]);
+        assert_eq!(ids(2), vec!["a.md", "x/b.md", "x/c.md"]);
+        assert_eq!(ids(3).len(), 4);
+    }
+
+    #[test]
+    fn test_render_escaping() {
+        let mut graph = sample();
+        graph.retain(|id| id == "d.md");
+        assert!(graph.edges.is_empty());
+        assert!(
+            graph
+                .to_dot()
+                .contains("\"d.md\" [label=\"Delta \\\"D\\\"\", project=\"\", tags=\"\"];")
+        );
+        assert!(
+            graph
+                .to_graphml()
+                .contains("<data key=\"title\">Delta &quot;D&quot;</data>")
+        );
+    }
+}
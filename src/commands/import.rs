@@ -0,0 +1,1127 @@
+// src/commands/import.rs
+use crate::commands::attachments::{ATTACHMENTS_DIR, AttachmentStore, is_image, media_link};
+use crate::commands::ls::normalize_project;
+use crate::commands::new;
+use crate::git;
+use crate::html_to_md;
+use crate::links::{LinkIndex, WikiLink, relative_url, replace_wikilinks};
+use crate::metadata::{
+    frontmatter_tags, parse_date, relative_path, split_frontmatter, update_frontmatter,
+};
+use crate::utils::{read_config, resolve_vault, yaml_to_json};
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use notemancy_core::notes::utils::{get_title, list_all_notes_alt};
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Something that could not be converted faithfully, tied to the source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub file: String,
+    pub message: String,
+}
+
+/// Parses the import target in the same format as `ncy new` without the
+/// title: '@ project/path +vault', both parts optional.
+pub fn parse_target(args: &str) -> (String, Option<String>) {
+    let mut rest = args.trim().to_string();
+    let mut vault = None;
+
+    if let Some(plus_pos) = rest.rfind('+') {
+        vault = Some(rest[plus_pos + 1..].trim().to_string()).filter(|v| !v.is_empty());
+        rest = rest[..plus_pos].to_string();
+    }
+
+    (normalize_project(&rest), vault)
+}
+
+/// Imports an Obsidian vault into a vault. Notes get slugged file names and
+/// frontmatter with a `title` and `date`; wikilinks and embeds are rewritten
+/// to the new paths and attachments are copied alongside.
+pub fn obsidian(source: &Path, target: &str, dry_run: bool) -> Result<()> {
+    if !source.is_dir() {
+        return Err(anyhow!("Not a directory: {}", source.display()));
+    }
+
+    let (project, vault) = parse_target(target);
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault.as_deref())?;
+
+    let (notes, attachments) = list_source_files(source)?;
+    if notes.is_empty() {
+        return Err(anyhow!("No markdown notes found in {}", source.display()));
+    }
+
+    // Decide every destination first so links can point at the new paths
+    let mut taken = BTreeSet::new();
+    let note_targets: BTreeMap<&str, String> = notes
+        .iter()
+        .map(|note| (note.as_str(), destination(&project, note, &mut taken)))
+        .collect();
+    let attachment_targets: BTreeMap<&str, String> = attachments
+        .iter()
+        .map(|file| (file.as_str(), destination(&project, file, &mut taken)))
+        .collect();
+
+    // Obsidian resolves links by file name, so index the notes by their stem
+    let index = LinkIndex::new(notes.iter().map(|note| (note.as_str(), stem(note))));
+    let mut issues = Vec::new();
+    let mut created = Vec::new();
+
+    for note in &notes {
+        let new_path = &note_targets[note.as_str()];
+        let target_path = vault_path.join(new_path);
+        if target_path.exists() {
+            issues.push(issue(note, format!("skipped, {} already exists", new_path)));
+            continue;
+        }
+
+        let source_path = source.join(note);
+        let content = fs::read_to_string(&source_path)
+            .context(format!("Failed to read note: {}", source_path.display()))?;
+        let modified = fs::metadata(&source_path)
+            .and_then(|meta| meta.modified())
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now());
+
+        let mut note_issues = Vec::new();
+        let body = replace_wikilinks(split_frontmatter(&content).1, |link| {
+            rewrite_link(
+                link,
+                new_path,
+                &index,
+                &note_targets,
+                &attachment_targets,
+                &mut note_issues,
+            )
+        });
+        let converted = convert_note(&content, &body, stem(note), modified, &mut note_issues);
+        issues.extend(note_issues.into_iter().map(|message| issue(note, message)));
+
+        if !dry_run {
+            write_new_file(&target_path, converted.as_bytes())?;
+        }
+        created.push(target_path);
+    }
+
+    let mut copied = Vec::new();
+    for file in &attachments {
+        let new_path = &attachment_targets[file.as_str()];
+        let target_path = vault_path.join(new_path);
+        if target_path.exists() {
+            issues.push(issue(file, format!("skipped, {} already exists", new_path)));
+            continue;
+        }
+        if file.ends_with(".canvas") {
+            issues.push(issue(
+                file,
+                "canvas copied as is, ncy cannot display it".into(),
+            ));
+        }
+
+        if !dry_run {
+            if let Some(parent) = target_path.parent() {
+                fs::create_dir_all(parent).context("Failed to create attachment directory")?;
+            }
+            fs::copy(source.join(file), &target_path)
+                .context(format!("Failed to copy attachment: {}", file))?;
+        }
+        copied.push(target_path);
+    }
+
+    print_report(&vault_name, created.len(), copied.len(), &issues, dry_run);
+    if !dry_run {
+        commit_imported(&created, &copied);
+    }
+    Ok(())
+}
+
+/// Rewrites one wikilink of an imported note to point at the new location.
+/// Image embeds become markdown images, other embeds become links.
+fn rewrite_link(
+    link: &WikiLink,
+    from: &str,
+    index: &LinkIndex,
+    note_targets: &BTreeMap<&str, String>,
+    attachment_targets: &BTreeMap<&str, String>,
+    issues: &mut Vec<String>,
+) -> String {
+    let original = || {
+        format!(
+            "{}[[{}{}{}]]",
+            if link.embed { "!" } else { "" },
+            link.target,
+            link.heading
+                .as_ref()
+                .map(|h| format!("#{}", h))
+                .unwrap_or_default(),
+            link.alias
+                .as_ref()
+                .map(|a| format!("|{}", a))
+                .unwrap_or_default()
+        )
+    };
+
+    // Links to a heading in the same note need no rewriting
+    if link.target.is_empty() {
+        return original();
+    }
+
+    if let Some(note) = index.resolve(&link.target) {
+        if link.embed {
+            issues.push(format!(
+                "embedded note '{}' converted to a link",
+                link.target
+            ));
+        }
+
+        let path = &note_targets[note];
+        let mut rewritten = format!("[[{}", path.strip_suffix(".md").unwrap_or(path));
+        if let Some(heading) = &link.heading {
+            rewritten.push('#');
+            rewritten.push_str(heading);
+        }
+        rewritten.push('|');
+        rewritten.push_str(link.label());
+        rewritten.push_str("]]");
+        return rewritten;
+    }
+
+    if let Some(attachment) = find_attachment(&link.target, attachment_targets) {
+        let url = relative_url(from, attachment);
+        let name = link.target.rsplit('/').next().unwrap_or(&link.target);
+        // Obsidian uses the alias of an embed for its size, e.g. ![[a.png|300]]
+        let label = link
+            .alias
+            .as_deref()
+            .filter(|alias| !alias.chars().all(|c| c.is_ascii_digit() || c == 'x'))
+            .unwrap_or(name);
+
+        return if link.embed && is_image(attachment) {
+            format!("![{}](<{}>)", label, url)
+        } else {
+            format!("[{}](<{}>)", label, url)
+        };
+    }
+
+    issues.push(format!("unresolved link {}", original()));
+    original()
+}
+
+fn find_attachment<'a>(target: &str, attachments: &'a BTreeMap<&str, String>) -> Option<&'a str> {
+    let target = target.trim().trim_start_matches('/');
+
+    attachments
+        .iter()
+        .find(|(source, _)| source.eq_ignore_ascii_case(target))
+        .or_else(|| {
+            // Like notes, attachments are usually referenced by file name only
+            attachments.iter().find(|(source, _)| {
+                source
+                    .rsplit('/')
+                    .next()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(target))
+            })
+        })
+        .map(|(_, new_path)| new_path.as_str())
+}
+
+/// Builds the imported note: converted frontmatter followed by the rewritten
+/// body. Frontmatter that cannot be parsed is kept in a code block at the top
+/// of the body so nothing is lost.
+fn convert_note(
+    content: &str,
+    body: &str,
+    stem: &str,
+    modified: DateTime<Local>,
+    issues: &mut Vec<String>,
+) -> String {
+    let (yaml, _) = split_frontmatter(content);
+
+    let (mapping, broken) = match yaml.map(serde_yaml::from_str::<YamlValue>) {
+        None | Some(Ok(YamlValue::Null)) => (Mapping::new(), None),
+        Some(Ok(YamlValue::Mapping(mapping))) => (mapping, None),
+        Some(_) => (Mapping::new(), yaml),
+    };
+
+    let mut body = body.to_string();
+    if let Some(broken) = broken {
+        issues.push("frontmatter could not be parsed and was moved into the body".into());
+        body = format!("```yaml\n{}```\n{}", broken, body);
+    }
+
+    let mapping = convert_frontmatter(mapping, stem, modified, issues);
+    let yaml = serde_yaml::to_string(&mapping).unwrap_or_default();
+
+    format!(
+        "---\n{}\n---\n{}",
+        yaml.strip_prefix("---\n").unwrap_or(&yaml).trim_end(),
+        body
+    )
+}
+
+/// Puts `title` and `date` first, turns `tags` and `aliases` into plain lists
+/// and keeps every other field as it was.
+fn convert_frontmatter(
+    mapping: Mapping,
+    stem: &str,
+    modified: DateTime<Local>,
+    issues: &mut Vec<String>,
+) -> Mapping {
+    let key = |k: &str| YamlValue::String(k.to_string());
+    let field = |k: &str| mapping.get(&key(k)).cloned();
+    let mut converted = Mapping::new();
+
+    let title = match field("title") {
+        Some(YamlValue::String(title)) if !title.trim().is_empty() => title,
+        _ => stem.to_string(),
+    };
+    converted.insert(key("title"), YamlValue::String(title));
+
+    let date = field("date")
+        .or_else(|| field("created"))
+        .and_then(|date| date.as_str().map(|d| d.to_string()));
+    let date = match date {
+        Some(date) if parse_date(&date).is_some() => date,
+        Some(date) => {
+            issues.push(format!(
+                "unrecognized date '{}', used the file modification date",
+                date
+            ));
+            modified.format("%Y-%m-%d").to_string()
+        }
+        None => modified.format("%Y-%m-%d").to_string(),
+    };
+    converted.insert(key("date"), YamlValue::String(date));
+
+    if let Some(tags) = field("tags").or_else(|| field("tag")) {
+        let tags = frontmatter_tags(&serde_json::json!({ "tags": yaml_to_json(tags) }));
+        if !tags.is_empty() {
+            let tags = tags.into_iter().map(YamlValue::String).collect();
+            converted.insert(key("tags"), YamlValue::Sequence(tags));
+        }
+    }
+
+    let aliases: Vec<YamlValue> = match field("aliases").or_else(|| field("alias")) {
+        Some(YamlValue::Sequence(items)) => items,
+        Some(YamlValue::String(alias)) => alias
+            .split(',')
+            .map(|a| YamlValue::String(a.trim().to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !aliases.is_empty() {
+        converted.insert(key("aliases"), YamlValue::Sequence(aliases));
+    }
+
+    // Every other field is copied over in its original order
+    let handled = ["title", "date", "tags", "tag", "aliases", "alias"];
+    for (k, v) in &mapping {
+        if !k.as_str().is_some_and(|k| handled.contains(&k)) {
+            converted.insert(k.clone(), v.clone());
+        }
+    }
+    converted
+}
+
+/// Slugs every path segment of a source file and places it under the target
+/// project. Clashing names get a numeric suffix.
+fn destination(project: &str, source: &str, taken: &mut BTreeSet<String>) -> String {
+    let mut segments: Vec<String> = source.split('/').map(slugify).collect();
+    if !project.is_empty() {
+        segments.insert(0, project.to_string());
+    }
+    let path = segments.join("/");
+
+    // The counter goes before the extension of the file name
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    let (base, extension) = match path[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => path.split_at(name_start + dot),
+        _ => (path.as_str(), ""),
+    };
+    let (base, extension) = (base.to_string(), extension.to_string());
+
+    let mut candidate = path;
+    let mut counter = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        candidate = format!("{}-{}{}", base, counter, extension);
+        counter += 1;
+    }
+    candidate
+}
+
+/// Lowercases a file or directory name and replaces anything that is not
+/// alphanumeric, '.', '_' or '-' with dashes.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() || c == '_' {
+            slug.push(c);
+        } else if c == '.' {
+            // Keep "name.md" instead of "name-.md"
+            if slug.ends_with('-') {
+                slug.pop();
+            }
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Lists the notes and other files of the source vault, relative to it.
+/// Hidden entries such as `.obsidian/` and `.trash/` are skipped.
+fn list_source_files(source: &Path) -> Result<(Vec<String>, Vec<String>)> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir).context(format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            {
+                continue;
+            }
+
+            if path.is_dir() {
+                walk(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(source, &mut files)?;
+
+    let mut notes = Vec::new();
+    let mut attachments = Vec::new();
+    for file in files {
+        let relative = relative_path(source, &file);
+        if file.extension().is_some_and(|ext| ext == "md") {
+            notes.push(relative);
+        } else {
+            attachments.push(relative);
+        }
+    }
+    notes.sort();
+    attachments.sort();
+
+    Ok((notes, attachments))
+}
+
+/// A note read from an Evernote export.
+#[derive(Debug, Default, PartialEq)]
+struct EnexNote {
+    title: String,
+    /// The body in ENML, Evernote's XHTML dialect
+    content: String,
+    created: Option<DateTime<Local>>,
+    updated: Option<DateTime<Local>>,
+    tags: Vec<String>,
+    source_url: Option<String>,
+    resources: Vec<Resource>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Resource {
+    data: Vec<u8>,
+    mime: String,
+    file_name: Option<String>,
+}
+
+impl Resource {
+    /// The hash `<en-media>` elements use to refer to the resource.
+    fn hash(&self) -> String {
+        format!("{:x}", md5::compute(&self.data))
+    }
+}
+
+/// Imports an Evernote export (.enex). Every note is created the way
+/// `ncy new` creates notes, then gets its converted body, dates, tags and
+/// source URL. Embedded resources go to the `attachments` folder of the project.
+pub fn enex(file: &Path, target: &str, dry_run: bool) -> Result<()> {
+    let xml = fs::read_to_string(file).context(format!("Failed to read {}", file.display()))?;
+    let notes = parse_enex(&xml)?;
+    if notes.is_empty() {
+        return Err(anyhow!("No notes found in {}", file.display()));
+    }
+
+    let (project, vault) = parse_target(target);
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault.as_deref())?;
+
+    let mut titles = existing_titles(&vault_path)?;
+    let mut attachments = AttachmentStore::new(
+        &vault_path,
+        &join_project(&project, ATTACHMENTS_DIR),
+        dry_run,
+    );
+    let mut issues = Vec::new();
+    let mut created = Vec::new();
+    let mut imported = 0;
+
+    for note in notes {
+        let title = unique_title(&note.title, &mut titles);
+
+        let note_path = if dry_run {
+            None
+        } else {
+            Some(new::create_with_schemas(
+                &config,
+                &vault_name,
+                &vault_path,
+                &title,
+                &project,
+                false,
+            )?)
+        };
+        // Only the directory of the note matters for links to attachments
+        let from = match &note_path {
+            Some(path) => relative_path(&vault_path, path),
+            None => join_project(&project, "note.md"),
+        };
+
+        // The body refers to resources by the MD5 hash of their data
+        let mut media = BTreeMap::new();
+        for resource in &note.resources {
+            let hash = resource.hash();
+            let name = match &resource.file_name {
+                Some(name) => name.clone(),
+                None => format!("{}.{}", hash, extension_for_mime(&resource.mime)),
+            };
+            let path = attachments.save(&name, &resource.data)?;
+            media.insert(hash, (path, resource.mime.starts_with("image/")));
+        }
+
+        let mut note_issues = Vec::new();
+        let (enml, encrypted) = remove_elements(&note.content, "en-crypt");
+        if encrypted > 0 {
+            note_issues.push(format!("{} encrypted section(s) left out", encrypted));
+        }
+
+        let body = html_to_md::convert(&enml, |name, attributes| match name {
+            "en-todo" => {
+                let checked = attributes.get("checked").is_some_and(|c| c == "true");
+                Some(if checked { "[x] " } else { "[ ] " }.to_string())
+            }
+            "en-media" => {
+                let hash = attributes
+                    .get("hash")
+                    .map(|hash| hash.to_lowercase())
+                    .unwrap_or_default();
+                match media.get(&hash) {
+                    Some((path, image)) => Some(media_link(&from, path, *image)),
+                    None => {
+                        note_issues.push(format!("resource {} not found in the export", hash));
+                        Some(String::new())
+                    }
+                }
+            }
+            _ => None,
+        });
+        issues.extend(
+            note_issues
+                .into_iter()
+                .map(|message| issue(&title, message)),
+        );
+        imported += 1;
+
+        let Some(note_path) = note_path else {
+            continue;
+        };
+
+        let mut fields = Vec::new();
+        if let Some(date) = note.created {
+            fields.push(("created", YamlValue::String(format_timestamp(date))));
+        }
+        if let Some(date) = note.updated {
+            fields.push(("updated", YamlValue::String(format_timestamp(date))));
+        }
+        if !note.tags.is_empty() {
+            let tags = note.tags.into_iter().map(YamlValue::String).collect();
+            fields.push(("tags", YamlValue::Sequence(tags)));
+        }
+        if let Some(url) = note.source_url {
+            fields.push(("source", YamlValue::String(url)));
+        }
+
+        fill_note(&note_path, note.created, fields, &body)?;
+        if let Some(date) = note.updated.or(note.created) {
+            set_modified(&note_path, date);
+        }
+        created.push(note_path);
+    }
+
+    print_report(&vault_name, imported, attachments.count, &issues, dry_run);
+    commit_imported(&created, &attachments.written);
+    Ok(())
+}
+
+/// Imports an HTML file, or every HTML file in a directory, through the same
+/// path as `ncy import enex`. Local images are copied to the attachments
+/// folder of the project; remote ones are linked as they are.
+pub fn html(source: &Path, target: &str, dry_run: bool) -> Result<()> {
+    let files: Vec<PathBuf> = if source.is_dir() {
+        list_source_files(source)?
+            .1
+            .into_iter()
+            .filter(|file| is_html(file))
+            .map(|file| source.join(file))
+            .collect()
+    } else if source.is_file() {
+        vec![source.to_path_buf()]
+    } else {
+        return Err(anyhow!("Not found: {}", source.display()));
+    };
+    if files.is_empty() {
+        return Err(anyhow!("No HTML files found in {}", source.display()));
+    }
+
+    let (project, vault) = parse_target(target);
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault.as_deref())?;
+
+    let mut titles = existing_titles(&vault_path)?;
+    let mut attachments = AttachmentStore::new(
+        &vault_path,
+        &join_project(&project, ATTACHMENTS_DIR),
+        dry_run,
+    );
+    let mut issues = Vec::new();
+    let mut created = Vec::new();
+
+    for file in &files {
+        let bytes = fs::read(file).context(format!("Failed to read {}", file.display()))?;
+        let content = String::from_utf8_lossy(&bytes);
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let modified = fs::metadata(file)
+            .and_then(|meta| meta.modified())
+            .map(DateTime::<Local>::from)
+            .ok();
+
+        let fallback = name
+            .rsplit_once('.')
+            .map_or(name.as_str(), |(stem, _)| stem);
+        let title = html_title(&content).unwrap_or_else(|| fallback.to_string());
+        let title = unique_title(&title, &mut titles);
+
+        let note_path = if dry_run {
+            None
+        } else {
+            Some(new::create_with_schemas(
+                &config,
+                &vault_name,
+                &vault_path,
+                &title,
+                &project,
+                false,
+            )?)
+        };
+        let from = match &note_path {
+            Some(path) => relative_path(&vault_path, path),
+            None => join_project(&project, "note.md"),
+        };
+
+        let base = file.parent().unwrap_or(Path::new("."));
+        let mut file_issues = Vec::new();
+        let mut save_error = None;
+        let body = html_to_md::convert(&content, |tag, attributes| {
+            let src = attributes.get("src").filter(|_| tag == "img")?;
+            if src.contains(':') || src.starts_with("//") {
+                return None;
+            }
+
+            let local = base.join(src.split(['?', '#']).next().unwrap_or(src));
+            let Ok(data) = fs::read(&local) else {
+                file_issues.push(format!("image {} not found", src));
+                return None;
+            };
+            let image_name = local
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            match attachments.save(&image_name, &data) {
+                Ok(path) => Some(media_link(&from, &path, true)),
+                Err(e) => {
+                    save_error.get_or_insert(e);
+                    None
+                }
+            }
+        });
+        if let Some(e) = save_error {
+            return Err(e);
+        }
+        issues.extend(file_issues.into_iter().map(|message| issue(&name, message)));
+
+        let Some(note_path) = note_path else {
+            continue;
+        };
+        fill_note(&note_path, modified, Vec::new(), &body)?;
+        if let Some(date) = modified {
+            set_modified(&note_path, date);
+        }
+        created.push(note_path);
+    }
+
+    print_report(
+        &vault_name,
+        files.len(),
+        attachments.count,
+        &issues,
+        dry_run,
+    );
+    commit_imported(&created, &attachments.written);
+    Ok(())
+}
+
+fn parse_enex(xml: &str) -> Result<Vec<EnexNote>> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document = roxmltree::Document::parse_with_options(xml, options)
+        .context("Failed to parse ENEX file")?;
+
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|child| child.has_tag_name(name))
+            .map(|child| child.text().unwrap_or_default().trim().to_string())
+    };
+
+    let mut notes = Vec::new();
+    for node in document.root_element().children() {
+        if !node.has_tag_name("note") {
+            continue;
+        }
+
+        let mut note = EnexNote {
+            title: child_text(node, "title").unwrap_or_default(),
+            content: child_text(node, "content").unwrap_or_default(),
+            created: child_text(node, "created").and_then(|date| parse_enex_date(&date)),
+            updated: child_text(node, "updated").and_then(|date| parse_enex_date(&date)),
+            ..Default::default()
+        };
+
+        for child in node.children() {
+            match child.tag_name().name() {
+                "tag" => {
+                    let tag = child.text().unwrap_or_default().trim();
+                    if !tag.is_empty() {
+                        note.tags.push(tag.to_string());
+                    }
+                }
+                "note-attributes" => {
+                    note.source_url = child_text(child, "source-url").filter(|url| !url.is_empty());
+                }
+                "resource" => {
+                    let encoded: String = child_text(child, "data")
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .collect();
+                    let data = BASE64
+                        .decode(encoded)
+                        .context(format!("Invalid resource data in note '{}'", note.title))?;
+                    let file_name = child
+                        .children()
+                        .find(|c| c.has_tag_name("resource-attributes"))
+                        .and_then(|attributes| child_text(attributes, "file-name"))
+                        .filter(|name| !name.is_empty());
+
+                    note.resources.push(Resource {
+                        data,
+                        mime: child_text(child, "mime").unwrap_or_default(),
+                        file_name,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        notes.push(note);
+    }
+
+    Ok(notes)
+}
+
+// ENEX dates are UTC, e.g. 20230105T143000Z
+fn parse_enex_date(value: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(value.trim(), "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|naive| Utc.from_utc_datetime(&naive).with_timezone(&Local))
+}
+
+fn format_timestamp(date: DateTime<Local>) -> String {
+    date.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Removes every `<name>...</name>` element from markup and returns how many
+/// were removed.
+fn remove_elements(html: &str, name: &str) -> (String, usize) {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut removed = 0;
+
+    while let Some(start) = rest.find(&open) {
+        result.push_str(&rest[..start]);
+        removed += 1;
+        rest = match rest[start..].find(&close) {
+            Some(end) => &rest[start + end + close.len()..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+
+    (result, removed)
+}
+
+/// Returns the contents of the `<title>` element of an HTML document.
+fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let content_start = start + lower[start..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</title>")?;
+
+    let title = html_to_md::decode_entities(&html[content_start..content_end]);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(title).filter(|title| !title.is_empty())
+}
+
+fn extension_for_mime(mime: &str) -> &str {
+    match mime {
+        "image/jpeg" => "jpg",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        "text/plain" => "txt",
+        "audio/mpeg" => "mp3",
+        "audio/x-m4a" => "m4a",
+        _ => mime
+            .split('/')
+            .nth(1)
+            .filter(|subtype| subtype.chars().all(char::is_alphanumeric))
+            .unwrap_or("bin"),
+    }
+}
+
+fn is_html(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    extension == "html" || extension == "htm"
+}
+
+/// Lowercased titles of the notes already in the vault.
+fn existing_titles(vault_path: &Path) -> Result<BTreeSet<String>> {
+    let mut titles = BTreeSet::new();
+    for note in list_all_notes_alt(vault_path, false)? {
+        if let Ok(title) = get_title(Path::new(&note)) {
+            titles.insert(title.to_lowercase());
+        }
+    }
+    Ok(titles)
+}
+
+/// Makes a title unique among `titles` by appending " (2)", " (3)", ...
+fn unique_title(title: &str, titles: &mut BTreeSet<String>) -> String {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = if title.is_empty() {
+        "Untitled".to_string()
+    } else {
+        title
+    };
+
+    let mut candidate = title.clone();
+    let mut counter = 2;
+    while !titles.insert(candidate.to_lowercase()) {
+        candidate = format!("{} ({})", title, counter);
+        counter += 1;
+    }
+    candidate
+}
+
+/// Adds imported metadata to the frontmatter of a newly created note and
+/// appends the body after whatever the note was created with.
+fn fill_note(
+    note_path: &Path,
+    date: Option<DateTime<Local>>,
+    fields: Vec<(&str, YamlValue)>,
+    body: &str,
+) -> Result<()> {
+    let content = fs::read_to_string(note_path)
+        .context(format!("Failed to read note: {}", note_path.display()))?;
+
+    let mut content = update_frontmatter(&content, |mapping| {
+        if let Some(date) = date {
+            let value = YamlValue::String(date.format("%Y-%m-%d").to_string());
+            mapping.insert(YamlValue::String("date".to_string()), value);
+        }
+        for (key, value) in fields {
+            mapping.insert(YamlValue::String(key.to_string()), value);
+        }
+        Ok(())
+    })?;
+
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(body);
+
+    fs::write(note_path, content).context(format!("Failed to write {}", note_path.display()))
+}
+
+// Keeps the original modification time so imported notes sort by it
+fn set_modified(path: &Path, date: DateTime<Local>) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(date.into());
+    }
+}
+
+fn join_project(project: &str, path: &str) -> String {
+    if project.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", project, path)
+    }
+}
+
+fn commit_imported(notes: &[PathBuf], attachments: &[PathBuf]) {
+    let paths: Vec<&Path> = notes
+        .iter()
+        .chain(attachments)
+        .map(|path| path.as_path())
+        .collect();
+    if !paths.is_empty() {
+        git::auto_commit_or_warn(&paths);
+    }
+}
+
+fn print_report(
+    vault_name: &str,
+    notes: usize,
+    attachments: usize,
+    issues: &[Issue],
+    dry_run: bool,
+) {
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!(
+        "{} {} notes and {} attachments into vault: {}",
+        verb, notes, attachments, vault_name
+    );
+
+    if issues.is_empty() {
+        return;
+    }
+
+    println!("\n{} issue(s):", issues.len());
+    for issue in issues {
+        println!("  {}: {}", issue.file, issue.message);
+    }
+}
+
+fn write_new_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create project directory")?;
+    }
+    fs::write(path, content).context(format!("Failed to write {}", path.display()))
+}
+
+fn issue(file: &str, message: String) -> Issue {
+    Issue {
+        file: file.to_string(),
+        message,
+    }
+}
+
+fn stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::find_wikilinks;
+
+    fn date() -> DateTime<Local> {
+        parse_date("2024-05-01").unwrap()
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("+work"),
+            (String::new(), Some("work".to_string()))
+        );
+        assert_eq!(
+            parse_target("@ imported/obsidian +work"),
+            ("imported/obsidian".to_string(), Some("work".to_string()))
+        );
+        assert_eq!(parse_target(""), (String::new(), None));
+    }
+
+    #[test]
+    fn test_slugify_and_destination() {
+        assert_eq!(slugify("My Note (draft).md"), "my-note-draft.md");
+        assert_eq!(slugify("  "), "untitled");
+
+        let mut taken = BTreeSet::new();
+        assert_eq!(
+            destination("", "Daily/My Note.md", &mut taken),
+            "daily/my-note.md"
+        );
+        assert_eq!(
+            destination("", "Daily/my note.md", &mut taken),
+            "daily/my-note-2.md"
+        );
+        assert_eq!(destination("obs", "img.png", &mut taken), "obs/img.png");
+    }
+
+    #[test]
+    fn test_convert_note_frontmatter() {
+        let content =
+            "---\ntags: [\"#a\", b]\naliases: Short\ncreated: 2023-01-05\nrating: 5\n---\nBody\n";
+        let mut issues = Vec::new();
+        let converted = convert_note(content, "Body\n", "My Note", date(), &mut issues);
+
+        assert_eq!(
+            converted,
+            "---\ntitle: My Note\ndate: 2023-01-05\ntags:\n  - a\n  - b\naliases:\n  - Short\ncreated: 2023-01-05\nrating: 5\n---\nBody\n"
+        );
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_convert_note_without_or_with_broken_frontmatter() {
+        let mut issues = Vec::new();
+        let converted = convert_note("Just text\n", "Just text\n", "Plain", date(), &mut issues);
+        assert_eq!(
+            converted,
+            "---\ntitle: Plain\ndate: 2024-05-01\n---\nJust text\n"
+        );
+
+        let content = "---\n: [broken\n---\nText\n";
+        let converted = convert_note(content, "Text\n", "Broken", date(), &mut issues);
+        assert!(converted.ends_with("---\n```yaml\n: [broken\n```\nText\n"));
+        assert_eq!(issues.len(), 1);
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let index = LinkIndex::new([("Daily/My Note.md", "My Note"), ("Other.md", "Other")]);
+        let notes: BTreeMap<&str, String> = [
+            ("Daily/My Note.md", "daily/my-note.md".to_string()),
+            ("Other.md", "other.md".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let attachments: BTreeMap<&str, String> =
+            [("assets/Pic 1.png", "assets/pic-1.png".to_string())]
+                .into_iter()
+                .collect();
+
+        let text = "[[My Note|alias]] ![[Pic 1.png|300]] ![[Other]] [[Missing]]";
+        let mut issues = Vec::new();
+        let rewritten = replace_wikilinks(text, |link| {
+            rewrite_link(
+                link,
+                "daily/other.md",
+                &index,
+                &notes,
+                &attachments,
+                &mut issues,
+            )
+        });
+
+        assert_eq!(
+            rewritten,
+            "[[daily/my-note|alias]] ![Pic 1.png](<../assets/pic-1.png>) [[other|Other]] [[Missing]]"
+        );
+        assert_eq!(issues.len(), 2);
+        assert_eq!(find_wikilinks(&rewritten).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_enex() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export4.dtd">
+<en-export>
+  <note>
+    <title>Trip &amp; Plans</title>
+    <content><![CDATA[<?xml version="1.0"?><en-note><div>Hi<en-media hash="5d41402abc4b2a76b9719d911017c592" type="text/plain"/></div></en-note>]]></content>
+    <created>20230105T143000Z</created>
+    <tag>travel</tag>
+    <tag>2023</tag>
+    <note-attributes><source-url>https://example.org</source-url></note-attributes>
+    <resource>
+      <data encoding="base64">aGVs
+bG8=</data>
+      <mime>text/plain</mime>
+      <resource-attributes><file-name>hello.txt</file-name></resource-attributes>
+    </resource>
+  </note>
+</en-export>"#;
+
+        let notes = parse_enex(xml).unwrap();
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.title, "Trip & Plans");
+        assert!(note.content.contains("<en-media"));
+        assert_eq!(note.created, parse_enex_date("20230105T143000Z"));
+        assert_eq!(note.updated, None);
+        assert_eq!(note.tags, vec!["travel", "2023"]);
+        assert_eq!(note.source_url.as_deref(), Some("https://example.org"));
+        assert_eq!(note.resources[0].data, b"hello");
+        assert_eq!(note.resources[0].hash(), "5d41402abc4b2a76b9719d911017c592");
+        assert_eq!(note.resources[0].file_name.as_deref(), Some("hello.txt"));
+    }
+
+    #[test]
+    fn test_parse_enex_date() {
+        let date = parse_enex_date("20230105T143000Z").unwrap();
+        assert_eq!(
+            date.with_timezone(&Utc).to_rfc3339(),
+            "2023-01-05T14:30:00+00:00"
+        );
+        assert_eq!(parse_enex_date("2023-01-05"), None);
+    }
+
+    #[test]
+    fn test_remove_elements_and_html_title() {
+        let (html, removed) = remove_elements(
+            "<p>a</p><en-crypt hint=\"x\">QUJD</en-crypt><p>b</p>",
+            "en-crypt",
+        );
+        assert_eq!(html, "<p>a</p><p>b</p>");
+        assert_eq!(removed, 1);
+
+        assert_eq!(
+            html_title("<html><HEAD><Title>\n Q&amp;A </title></head></html>"),
+            Some("Q&A".to_string())
+        );
+        assert_eq!(html_title("<p>no title</p>"), None);
+    }
+
+    #[test]
+    fn test_unique_title() {
+        let mut titles: BTreeSet<String> = ["notes".to_string()].into_iter().collect();
+        assert_eq!(unique_title("Notes", &mut titles), "Notes (2)");
+        assert_eq!(unique_title("  ", &mut titles), "Untitled");
+        assert_eq!(unique_title("notes", &mut titles), "notes (3)");
+    }
+}
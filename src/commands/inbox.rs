@@ -0,0 +1,408 @@
+// src/commands/inbox.rs
+use crate::commands::{jrnl, new, project};
+use crate::crypto;
+use crate::git;
+use crate::metadata::relative_path;
+use crate::picker::{PickOptions, Picked, pick_notes, vault_choices};
+use crate::utils::{read_config, resolve_vault, vault_setting};
+use anyhow::{Context, Result, anyhow};
+use chrono::{Local, NaiveDateTime};
+use serde_json::Value as JsonValue;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_INBOX: &str = "inbox.md";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+// Longest title suggested for a note made from an item
+const MAX_TITLE_LENGTH: usize = 60;
+
+/// A captured item: `- [timestamp] text`, where further lines of the text
+/// are indented by two spaces.
+#[derive(Debug, Clone, PartialEq)]
+struct InboxItem {
+    captured: String,
+    text: String,
+    /// Byte range of the item in the inbox note
+    range: Range<usize>,
+}
+
+/// Appends text to the inbox note of a vault as a timestamped item, creating
+/// the note if needed. `-` reads the text from stdin.
+pub fn capture(text: &str, vault: Option<&str>) -> Result<()> {
+    let text = if text == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read from stdin")?;
+        input
+    } else {
+        text.to_string()
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(anyhow!("Nothing to capture"));
+    }
+
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let inbox = inbox_path(&config, &vault_name, &vault_path);
+
+    if !inbox.exists() {
+        create_inbox(&config, &vault_name, &vault_path, &inbox)?;
+    }
+
+    let item = format_item(&Local::now().format(TIMESTAMP_FORMAT).to_string(), text);
+    append_item(&inbox, &item)?;
+
+    println!("Captured to {}", relative_path(&vault_path, &inbox));
+    git::auto_commit_or_warn(&[&inbox]);
+    Ok(())
+}
+
+/// Walks the items of the inbox one by one, asking what to do with each.
+pub fn execute(vault: Option<&str>, external: bool) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let inbox = inbox_path(&config, &vault_name, &vault_path);
+
+    let items = match fs::read_to_string(&inbox) {
+        Ok(content) => parse_items(&content),
+        Err(_) => Vec::new(),
+    };
+    if items.is_empty() {
+        println!("The inbox of '{}' is empty", vault_name);
+        return Ok(());
+    }
+
+    let mut changed: Vec<PathBuf> = Vec::new();
+    let total = items.len();
+    for (i, item) in items.iter().enumerate() {
+        println!("\n[{}/{}] {}", i + 1, total, item.captured);
+        for line in item.text.lines() {
+            println!("{}", format!("  {}", line).trim_end());
+        }
+
+        let done = loop {
+            let answer =
+                prompt("(n)ew note, (m)ove to a note, (j)ournal, (d)iscard, (s)kip, (q)uit: ")?;
+            let result = match answer.as_deref() {
+                Some("n") => to_new_note(&config, &vault_name, &vault_path, item).map(Some),
+                Some("m") => to_note(&vault_path, item, external).map(Some),
+                Some("j") => {
+                    jrnl::append_today(&vault_path, &item.text).map(|(path, _)| Some(path))
+                }
+                Some("d") => Ok(None),
+                Some("s") => break false,
+                Some("q") | None => {
+                    return finish(&vault_name, &inbox, changed);
+                }
+                Some(_) => continue,
+            };
+
+            match result {
+                Ok(target) => {
+                    if let Some(target) = target {
+                        println!("  -> {}", relative_path(&vault_path, &target));
+                        changed.push(target);
+                    }
+                    break true;
+                }
+                // A failed step leaves the item in the inbox to try again
+                Err(e) => eprintln!("  {}", e),
+            }
+        };
+
+        if done {
+            remove_item(&inbox, item)?;
+            changed.push(inbox.clone());
+        }
+    }
+
+    finish(&vault_name, &inbox, changed)
+}
+
+fn finish(vault_name: &str, inbox: &Path, mut changed: Vec<PathBuf>) -> Result<()> {
+    let left = fs::read_to_string(inbox)
+        .map(|content| parse_items(&content).len())
+        .unwrap_or(0);
+    println!("\nItems left in the inbox of '{}': {}", vault_name, left);
+
+    changed.sort();
+    changed.dedup();
+    let paths: Vec<&Path> = changed.iter().map(|path| path.as_path()).collect();
+    git::auto_commit_or_warn(&paths);
+    Ok(())
+}
+
+fn to_new_note(
+    config: &JsonValue,
+    vault_name: &str,
+    vault_path: &Path,
+    item: &InboxItem,
+) -> Result<PathBuf> {
+    let suggested = suggested_title(&item.text);
+    let title = prompt(&format!("  Title [{}]: ", suggested))?
+        .filter(|title| !title.is_empty())
+        .unwrap_or(suggested);
+    let project = prompt("  Project (empty for the vault root): ")?.unwrap_or_default();
+
+    let note_path =
+        new::create_with_schemas(config, vault_name, vault_path, &title, &project, true)?;
+    append_text(&note_path, &item.text)?;
+    Ok(note_path)
+}
+
+fn to_note(vault_path: &Path, item: &InboxItem, external: bool) -> Result<PathBuf> {
+    let options = PickOptions {
+        external,
+        ..Default::default()
+    };
+    let note_path = match pick_notes(&vault_choices(vault_path)?, options)? {
+        Picked::Notes(notes) => notes
+            .into_iter()
+            .next()
+            .map(|note| PathBuf::from(note.path))
+            .context("No note selected")?,
+        Picked::Create(_) => return Err(anyhow!("No note selected")),
+    };
+    append_text(&note_path, &item.text)?;
+    Ok(note_path)
+}
+
+// Adds text at the end of a note, after a blank line. Encrypted notes are
+// refused, as text after their encrypted block would break them
+fn append_text(note_path: &Path, text: &str) -> Result<()> {
+    if crypto::is_encrypted_note(note_path) {
+        return Err(anyhow!(
+            "Cannot move an item to an encrypted note. Open the note to add it"
+        ));
+    }
+    let content = fs::read_to_string(note_path)
+        .context(format!("Failed to read note: {}", note_path.display()))?;
+    fs::write(note_path, format!("{}\n\n{}\n", content.trim_end(), text))
+        .context(format!("Failed to write note: {}", note_path.display()))
+}
+
+// Adds an item at the end of the inbox, unless the inbox is encrypted and
+// the item would land after its encrypted block
+fn append_item(inbox: &Path, item: &str) -> Result<()> {
+    let content =
+        fs::read_to_string(inbox).context(format!("Failed to read inbox: {}", inbox.display()))?;
+    if crypto::is_encrypted(&content) {
+        return Err(anyhow!(
+            "The inbox is encrypted. Decrypt it to capture items: {}",
+            inbox.display()
+        ));
+    }
+    let separator = if content.ends_with('\n') { "" } else { "\n" };
+    fs::write(inbox, format!("{}{}{}", content, separator, item))
+        .context(format!("Failed to write inbox: {}", inbox.display()))
+}
+
+// Removes an item from the inbox as it is now on disk, in case something
+// was captured while the inbox was being walked
+fn remove_item(inbox: &Path, item: &InboxItem) -> Result<()> {
+    let content =
+        fs::read_to_string(inbox).context(format!("Failed to read inbox: {}", inbox.display()))?;
+    let Some(current) = parse_items(&content)
+        .into_iter()
+        .find(|i| i.captured == item.captured && i.text == item.text)
+    else {
+        return Ok(());
+    };
+
+    let updated = format!(
+        "{}{}",
+        &content[..current.range.start],
+        &content[current.range.end..]
+    );
+    fs::write(inbox, updated).context(format!("Failed to write inbox: {}", inbox.display()))
+}
+
+// Reads a line from stdin. None at the end of the input
+fn prompt(question: &str) -> Result<Option<String>> {
+    print!("{}", question);
+    io::stdout().flush().context("Failed to write prompt")?;
+
+    let mut line = String::new();
+    if io::stdin()
+        .read_line(&mut line)
+        .context("Failed to read answer")?
+        == 0
+    {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// Path of the inbox note: `inbox` of the vault in config.yaml, relative to
+/// the vault, or inbox.md at its root.
+fn inbox_path(config: &JsonValue, vault_name: &str, vault_path: &Path) -> PathBuf {
+    vault_path.join(vault_setting(config, vault_name, "inbox").unwrap_or(DEFAULT_INBOX))
+}
+
+// Creates the inbox like any other note, titled after its file name
+fn create_inbox(
+    config: &JsonValue,
+    vault_name: &str,
+    vault_path: &Path,
+    inbox: &Path,
+) -> Result<()> {
+    let relative = relative_path(vault_path, inbox);
+    let (project, file_name) = relative.rsplit_once('/').unwrap_or(("", &relative));
+    let title = project::index_title(file_name.strip_suffix(".md").unwrap_or(file_name));
+
+    let note_path =
+        new::create_with_schemas(config, vault_name, vault_path, &title, project, false)?;
+    // A file name the title does not give back, such as "Inbox.md", is kept
+    if note_path != inbox {
+        fs::rename(&note_path, inbox)
+            .context(format!("Failed to create inbox: {}", inbox.display()))?;
+    }
+    Ok(())
+}
+
+fn format_item(captured: &str, text: &str) -> String {
+    let mut lines = text.lines();
+    let mut item = format!("- [{}] {}\n", captured, lines.next().unwrap_or_default());
+    for line in lines {
+        if line.trim().is_empty() {
+            item.push('\n');
+        } else {
+            item.push_str(&format!("  {}\n", line));
+        }
+    }
+    item
+}
+
+// Finds the captured items in the content of an inbox note. Anything else
+// in the note is left alone
+fn parse_items(content: &str) -> Vec<InboxItem> {
+    let lines: Vec<(usize, &str)> = content
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .collect();
+
+    let mut items = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (start, line) = lines[i];
+        i += 1;
+        let Some((captured, first)) = parse_item_line(line) else {
+            continue;
+        };
+
+        let mut text = first.to_string();
+        let mut end = start + line.len();
+        // Indented lines continue the item, also after blank lines
+        let mut j = i;
+        while j < lines.len() {
+            let (line_start, next) = lines[j];
+            if next.trim().is_empty() {
+                j += 1;
+                continue;
+            }
+            if !next.starts_with("  ") {
+                break;
+            }
+            for (_, skipped) in &lines[i..j] {
+                text.push_str(&format!("\n{}", skipped.trim_end_matches(['\r', '\n'])));
+            }
+            text.push_str(&format!("\n{}", next[2..].trim_end_matches(['\r', '\n'])));
+            end = line_start + next.len();
+            j += 1;
+            i = j;
+        }
+
+        items.push(InboxItem {
+            captured: captured.to_string(),
+            text,
+            range: start..end,
+        });
+    }
+    items
+}
+
+// Splits `- [timestamp] text` into the timestamp and the text
+fn parse_item_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_end_matches(['\r', '\n']).strip_prefix("- [")?;
+    let (captured, text) = rest.split_once("] ")?;
+    NaiveDateTime::parse_from_str(captured, TIMESTAMP_FORMAT).ok()?;
+    Some((captured, text))
+}
+
+// The first line of an item, shortened at a word boundary
+fn suggested_title(text: &str) -> String {
+    let first = text.lines().next().unwrap_or_default().trim();
+    if first.chars().count() <= MAX_TITLE_LENGTH {
+        return first.to_string();
+    }
+
+    let mut title = String::new();
+    for word in first.split_whitespace() {
+        if title.chars().count() + word.chars().count() + 1 > MAX_TITLE_LENGTH {
+            break;
+        }
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(word);
+    }
+    title
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_items() {
+        let first = format_item("2026-10-18 09:30", "Buy compost");
+        let second = format_item("2026-10-18 10:02", "Idea\n\nwith details");
+        let content = format!(
+            "---\ntitle: Inbox\n---\nSome notes\n{}{}- [not a date] left alone\n",
+            first, second
+        );
+
+        let items = parse_items(&content);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].captured, "2026-10-18 09:30");
+        assert_eq!(items[0].text, "Buy compost");
+        assert_eq!(&content[items[0].range.clone()], first);
+        assert_eq!(items[1].text, "Idea\n\nwith details");
+        assert_eq!(&content[items[1].range.clone()], second);
+    }
+
+    #[test]
+    fn test_suggested_title() {
+        assert_eq!(suggested_title("Short one\nmore"), "Short one");
+        let long = "word ".repeat(20);
+        let title = suggested_title(&long);
+        assert!(title.len() <= MAX_TITLE_LENGTH);
+        assert!(title.ends_with("word"));
+    }
+
+    #[test]
+    fn test_append_item_refuses_encrypted_inbox() {
+        let dir = std::env::temp_dir().join(format!("ncy-inbox-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let inbox = dir.join(DEFAULT_INBOX);
+        let item = format_item("2026-10-18 09:30", "Buy compost");
+
+        fs::write(&inbox, "---\ntitle: Inbox\n---\n").unwrap();
+        append_item(&inbox, &item).unwrap();
+        assert!(fs::read_to_string(&inbox).unwrap().ends_with(&item));
+
+        let encrypted = "---\ntitle: Inbox\nencrypted: true\n---\n-----BEGIN NCY ENCRYPTED NOTE-----\nAAAA\n-----END NCY ENCRYPTED NOTE-----\n";
+        fs::write(&inbox, encrypted).unwrap();
+        assert!(append_item(&inbox, &item).is_err());
+        assert_eq!(fs::read_to_string(&inbox).unwrap(), encrypted);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
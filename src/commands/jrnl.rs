@@ -1,13 +1,19 @@
 // src/commands/jrnl.rs
+use crate::crypto;
+use crate::git;
+use crate::history::{self, Action};
 use crate::utils::read_config;
 use anyhow::{Context, Result, anyhow};
 use chrono::Local;
 use notemancy_core::notes::crud::{append_to_note, create_note};
 use notemancy_core::notes::utils::get_file_path;
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Project inside the vault that holds the journal entries.
+pub const JOURNAL_PROJECT: &str = "journal";
+
 pub fn execute(args: &str, external: bool) -> Result<()> {
     // Get configuration
     let config = read_config()?;
@@ -22,42 +28,27 @@ pub fn execute(args: &str, external: bool) -> Result<()> {
     let vault_directory = find_vault_directory(&config, default_vault)?;
     let vault_path = Path::new(&vault_directory);
 
-    // Get today's date in MM-DD-YYYY format
-    let today = Local::now();
-    let date_str = today.format("%m-%d-%Y").to_string();
-
-    // Set up the journal path inside the vault
-    let journal_project = "journal";
-
-    // Variable to hold the note path
+    let date_str = today_title();
     let note_path;
 
     // If args is empty, we're just accessing today's journal
     if args.is_empty() {
-        // Check if today's journal entry exists
-        match get_file_path(&date_str, vault_path) {
-            Ok(path) => {
-                // Today's journal exists
-                if !external {
-                    println!("Opening today's journal entry ({}).", date_str);
-                }
-                note_path = path;
-            }
-            Err(_) => {
-                // Today's journal doesn't exist, create it
-                let new_note_path = create_note(&date_str, vault_path, journal_project)?;
-                if !external {
-                    println!("Created new journal entry for today ({}).", date_str);
-                }
-                note_path = new_note_path.to_string_lossy().to_string();
+        let (path, created) = today_entry(vault_path)?;
+        if !external {
+            if created {
+                println!("Created new journal entry for today ({}).", date_str);
+            } else {
+                println!("Opening today's journal entry ({}).", date_str);
             }
         }
+        note_path = path.to_string_lossy().to_string();
 
         // Only open the editor if we're not in external mode and no args were provided
         if !external {
             // Open the note in the default editor
             let editor = env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
             println!("Opening journal with {}", editor);
+            history::record_or_warn(Path::new(&note_path), Action::Open);
 
             let status = Command::new(&editor)
                 .arg(&note_path)
@@ -70,28 +61,13 @@ pub fn execute(args: &str, external: bool) -> Result<()> {
         }
     } else {
         // Adding text to today's journal
-        // Format the text to append with the separator
-        let text_to_append = format!("\n\n--\n{}", args);
-
-        // Check if today's journal entry exists
-        match get_file_path(&date_str, vault_path) {
-            Ok(path) => {
-                // Today's journal exists, append to it
-                append_to_note(&date_str, vault_path, &text_to_append)?;
-                println!("Added entry to today's journal ({}).", date_str);
-                note_path = path;
-            }
-            Err(_) => {
-                // Today's journal doesn't exist, create it
-                let new_note_path = create_note(&date_str, vault_path, journal_project)?;
-
-                // Now append the content (since create_note only creates with frontmatter)
-                append_to_note(&date_str, vault_path, &text_to_append)?;
-
-                println!("Created new journal entry for today ({}).", date_str);
-                note_path = new_note_path.to_string_lossy().to_string();
-            }
+        let (path, created) = append_today(vault_path, args)?;
+        if created {
+            println!("Created new journal entry for today ({}).", date_str);
+        } else {
+            println!("Added entry to today's journal ({}).", date_str);
         }
+        note_path = path.to_string_lossy().to_string();
     }
 
     // If external mode is enabled, print the path regardless of whether args were provided
@@ -100,9 +76,44 @@ pub fn execute(args: &str, external: bool) -> Result<()> {
         println!("{}", note_path);
     }
 
+    // Commit the new or updated entry if the vault has auto_commit enabled
+    git::auto_commit_or_warn(&[Path::new(&note_path)]);
     Ok(())
 }
 
+/// Title of today's journal entry, which is also its file name.
+pub fn today_title() -> String {
+    Local::now().format("%m-%d-%Y").to_string()
+}
+
+/// Returns the path of today's journal entry, creating the entry in the
+/// vault's journal project if needed. The flag tells whether it was created.
+pub fn today_entry(vault_path: &Path) -> Result<(PathBuf, bool)> {
+    let date_str = today_title();
+    if let Ok(path) = get_file_path(&date_str, vault_path) {
+        return Ok((PathBuf::from(path), false));
+    }
+
+    let note_path = create_note(&date_str, vault_path, JOURNAL_PROJECT)?;
+    history::record_or_warn(&note_path, Action::Create);
+    Ok((note_path, true))
+}
+
+/// Appends text to today's journal entry as a new `--` separated section,
+/// creating the entry first if needed. An encrypted entry is left alone,
+/// since text after its encrypted block would damage it.
+pub fn append_today(vault_path: &Path, text: &str) -> Result<(PathBuf, bool)> {
+    let (note_path, created) = today_entry(vault_path)?;
+    if crypto::is_encrypted_note(&note_path) {
+        return Err(anyhow!(
+            "Today's journal entry is encrypted. Open it to add to it: {}",
+            note_path.display()
+        ));
+    }
+    append_to_note(&today_title(), vault_path, &format!("\n\n--\n{}", text))?;
+    Ok((note_path, created))
+}
+
 // Reusing the function from other commands to find vault directory
 fn find_vault_directory(config: &serde_json::Value, vault_name: &str) -> Result<String> {
     // Get the vaults array from config
@@ -127,3 +138,28 @@ fn find_vault_directory(config: &serde_json::Value, vault_name: &str) -> Result<
         vault_name
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_append_today_refuses_encrypted_entry() {
+        let vault = env::temp_dir().join(format!("ncy-jrnl-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&vault);
+        fs::create_dir_all(vault.join(JOURNAL_PROJECT)).unwrap();
+        let entry = vault
+            .join(JOURNAL_PROJECT)
+            .join(format!("{}.md", today_title()));
+        let content = format!(
+            "---\ntitle: {}\nencrypted: true\n---\n-----BEGIN NCY ENCRYPTED NOTE-----\nAAAA\n-----END NCY ENCRYPTED NOTE-----\n",
+            today_title()
+        );
+        fs::write(&entry, &content).unwrap();
+
+        assert!(append_today(&vault, "more").is_err());
+        assert_eq!(fs::read_to_string(&entry).unwrap(), content);
+        fs::remove_dir_all(&vault).unwrap();
+    }
+}
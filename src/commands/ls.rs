@@ -0,0 +1,290 @@
+// src/commands/ls.rs
+use crate::metadata::{NoteMeta, load_vault, parse_date};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local};
+use serde_json::Value as JsonValue;
+
+/// Filters that narrow down the notes of a vault. Shared by every command
+/// that operates on "a filtered set of notes".
+#[derive(Debug, Default, Clone)]
+pub struct NoteFilter {
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub fields: Vec<(String, Option<String>)>,
+    pub created_after: Option<DateTime<Local>>,
+    pub created_before: Option<DateTime<Local>>,
+    pub modified_after: Option<DateTime<Local>>,
+    pub modified_before: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Title,
+    Modified,
+    Created,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Titles,
+    Paths,
+    Json,
+}
+
+pub struct ListOptions {
+    pub vault: Option<String>,
+    pub filter: NoteFilter,
+    pub sort: SortKey,
+    pub reverse: bool,
+    pub format: OutputFormat,
+}
+
+pub fn execute(options: &ListOptions) -> Result<()> {
+    // Get configuration
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, options.vault.as_deref())?;
+
+    let mut notes: Vec<NoteMeta> = load_vault(&vault_path)?
+        .into_iter()
+        .filter(|note| options.filter.matches(note))
+        .collect();
+
+    sort_notes(&mut notes, options.sort, options.reverse);
+    print_notes(&notes, options.format)
+}
+
+pub fn print_notes(notes: &[NoteMeta], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Titles => {
+            for note in notes {
+                println!("{}", note.title);
+            }
+        }
+        OutputFormat::Paths => {
+            for note in notes {
+                println!("{}", note.path.display());
+            }
+        }
+        OutputFormat::Json => {
+            let json = JsonValue::Array(notes.iter().map(NoteMeta::to_json).collect());
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json).context("Failed to serialize notes")?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn sort_notes(notes: &mut [NoteMeta], sort: SortKey, reverse: bool) {
+    match sort {
+        SortKey::Title => notes.sort_by_key(|note| note.title.to_lowercase()),
+        SortKey::Modified => notes.sort_by_key(|note| note.modified),
+        SortKey::Created => notes.sort_by_key(|note| note.created),
+    }
+
+    if reverse {
+        notes.reverse();
+    }
+}
+
+impl NoteFilter {
+    pub fn matches(&self, note: &NoteMeta) -> bool {
+        if let Some(project) = &self.project
+            && !project_matches(&note.project, project)
+        {
+            return false;
+        }
+
+        for tag in &self.tags {
+            if !note.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
+
+        for (key, expected) in &self.fields {
+            if !field_matches(note.frontmatter.get(key), expected.as_deref()) {
+                return false;
+            }
+        }
+
+        let in_range = |value: DateTime<Local>,
+                        after: Option<DateTime<Local>>,
+                        before: Option<DateTime<Local>>| {
+            after.is_none_or(|after| value >= after) && before.is_none_or(|before| value < before)
+        };
+
+        in_range(note.created, self.created_after, self.created_before)
+            && in_range(note.modified, self.modified_after, self.modified_before)
+    }
+}
+
+/// Checks whether a note's project lies under the given project prefix.
+/// The prefix must match whole path segments: "work" matches "work/q4"
+/// but not "workshop".
+pub fn project_matches(note_project: &str, prefix: &str) -> bool {
+    let prefix = normalize_project(prefix);
+    if prefix.is_empty() {
+        return true;
+    }
+
+    note_project == prefix
+        || note_project
+            .strip_prefix(&prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Strips the optional leading '@' and surrounding slashes from a project path.
+pub fn normalize_project(project: &str) -> String {
+    project
+        .trim()
+        .trim_start_matches('@')
+        .trim()
+        .trim_matches('/')
+        .to_string()
+}
+
+fn field_matches(value: Option<&JsonValue>, expected: Option<&str>) -> bool {
+    match (value, expected) {
+        (None, _) | (Some(JsonValue::Null), _) => false,
+        (Some(_), None) => true,
+        (Some(JsonValue::String(s)), Some(expected)) => s == expected,
+        (Some(JsonValue::Array(items)), Some(expected)) => items
+            .iter()
+            .any(|item| field_matches(Some(item), Some(expected))),
+        (Some(JsonValue::Number(n)), Some(expected)) => expected.parse::<f64>().ok() == n.as_f64(),
+        (Some(JsonValue::Bool(b)), Some(expected)) => expected.parse::<bool>().ok() == Some(*b),
+        (Some(_), Some(_)) => false,
+    }
+}
+
+/// Parses a `key=value` field filter. A bare `key` only requires the field
+/// to be present.
+pub fn parse_field_filter(filter: &str) -> Result<(String, Option<String>)> {
+    let (key, value) = match filter.split_once('=') {
+        Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+        None => (filter.trim(), None),
+    };
+
+    if key.is_empty() {
+        return Err(anyhow!(
+            "Invalid field filter '{}'. Use 'key' or 'key=value'",
+            filter
+        ));
+    }
+
+    Ok((key.to_string(), value))
+}
+
+pub fn parse_date_arg(value: Option<&str>) -> Result<Option<DateTime<Local>>> {
+    match value {
+        Some(value) => parse_date(value)
+            .map(Some)
+            .context(format!("Invalid date '{}'. Use YYYY-MM-DD", value)),
+        None => Ok(None),
+    }
+}
+
+pub fn parse_sort_key(value: &str) -> Result<SortKey> {
+    match value {
+        "title" => Ok(SortKey::Title),
+        "modified" | "mtime" => Ok(SortKey::Modified),
+        "created" => Ok(SortKey::Created),
+        _ => Err(anyhow!("Unknown sort key '{}'", value)),
+    }
+}
+
+pub fn parse_output_format(value: &str) -> Result<OutputFormat> {
+    match value {
+        "titles" => Ok(OutputFormat::Titles),
+        "paths" => Ok(OutputFormat::Paths),
+        "json" => Ok(OutputFormat::Json),
+        _ => Err(anyhow!("Unknown output format '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn note(project: &str, tags: &[&str], frontmatter: JsonValue) -> NoteMeta {
+        let date = parse_date("2024-03-10").unwrap();
+        NoteMeta {
+            path: PathBuf::from(format!("/vault/{}/note.md", project)),
+            title: "Note".to_string(),
+            project: project.to_string(),
+            frontmatter,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created: date,
+            modified: date,
+        }
+    }
+
+    #[test]
+    fn test_project_matches_whole_segments() {
+        assert!(project_matches("work/q4", "work"));
+        assert!(project_matches("work", "@work/"));
+        assert!(!project_matches("workshop", "work"));
+        assert!(project_matches("anything", ""));
+    }
+
+    #[test]
+    fn test_filter_by_tag_and_field() {
+        let n = note(
+            "work",
+            &["q4"],
+            json!({"status": "active", "owners": ["ana"]}),
+        );
+
+        let filter = NoteFilter {
+            tags: vec!["Q4".to_string()],
+            fields: vec![
+                ("status".to_string(), Some("active".to_string())),
+                ("owners".to_string(), Some("ana".to_string())),
+            ],
+            ..Default::default()
+        };
+        assert!(filter.matches(&n));
+
+        let missing = NoteFilter {
+            fields: vec![("due".to_string(), None)],
+            ..Default::default()
+        };
+        assert!(!missing.matches(&n));
+    }
+
+    #[test]
+    fn test_filter_by_date_range() {
+        let n = note("", &[], json!({}));
+
+        let filter = NoteFilter {
+            created_after: parse_date("2024-03-01"),
+            created_before: parse_date("2024-03-11"),
+            ..Default::default()
+        };
+        assert!(filter.matches(&n));
+
+        let filter = NoteFilter {
+            modified_before: parse_date("2024-03-10"),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&n));
+    }
+
+    #[test]
+    fn test_parse_field_filter() {
+        assert_eq!(
+            parse_field_filter("status = done").unwrap(),
+            ("status".to_string(), Some("done".to_string()))
+        );
+        assert_eq!(
+            parse_field_filter("due").unwrap(),
+            ("due".to_string(), None)
+        );
+        assert!(parse_field_filter("=x").is_err());
+    }
+}
@@ -0,0 +1,523 @@
+// src/commands/lsp.rs
+use crate::commands::serve::{Loaded, LoadedNote, VaultCache, VaultState};
+use crate::links::{WikiLink, file_uri, find_wikilinks, path_from_uri};
+use crate::metadata::{relative_path, split_frontmatter};
+use crate::rpc::{Connection, METHOD_NOT_FOUND, PARSE_ERROR, Request, RpcError};
+use crate::utils::read_config;
+use anyhow::{Result, anyhow};
+use serde_json::{Value as JsonValue, json};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use std::path::Path;
+
+// Lines of a note shown when hovering a link to it
+const PREVIEW_LINES: usize = 20;
+
+// LSP constants
+const TEXT_DOCUMENT_SYNC_FULL: u8 = 1;
+const COMPLETION_KIND_FILE: u8 = 17;
+const SEVERITY_WARNING: u8 = 2;
+
+// A line and a UTF-16 offset within it, as LSP counts positions
+type Position = (usize, usize);
+
+struct LanguageServer {
+    cache: VaultCache,
+    vault: Option<String>,
+    /// Text of the documents open in the editor, by URI
+    documents: BTreeMap<String, String>,
+    shutdown: bool,
+}
+
+impl LanguageServer {
+    fn vault(&mut self) -> Result<&mut VaultState, RpcError> {
+        Ok(self.cache.vault(self.vault.as_deref())?)
+    }
+
+    fn handle(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": {
+                        "openClose": true,
+                        "change": TEXT_DOCUMENT_SYNC_FULL,
+                        "save": true,
+                    },
+                    "completionProvider": {"triggerCharacters": ["["]},
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                },
+                "serverInfo": {"name": "ncy", "version": env!("CARGO_PKG_VERSION")},
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(JsonValue::Null)
+            }
+            "textDocument/completion" => self.completion(&request.params),
+            "textDocument/definition" => self.definition(&request.params),
+            "textDocument/references" => self.references(&request.params),
+            "textDocument/hover" => self.hover(&request.params),
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        }
+    }
+
+    // Keeps open documents current. Returns the URIs whose diagnostics need
+    // to be published again
+    fn handle_notification(&mut self, request: &Request) -> Result<Vec<String>, RpcError> {
+        let params = &request.params;
+        match request.method.as_str() {
+            "textDocument/didOpen" => {
+                let uri = document_uri(params)?.to_string();
+                let text = params["textDocument"]["text"]
+                    .as_str()
+                    .ok_or_else(|| RpcError::invalid_params("Missing document text"))?;
+                self.documents.insert(uri.clone(), text.to_string());
+                Ok(vec![uri])
+            }
+            "textDocument/didChange" => {
+                let uri = document_uri(params)?.to_string();
+                // With full sync the last change holds the whole text
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing document text"))?;
+                self.documents.insert(uri.clone(), text.to_string());
+                Ok(vec![uri])
+            }
+            "textDocument/didSave" => {
+                // A saved note may fix links in every other open document
+                self.vault()?.mark_changed();
+                Ok(self.documents.keys().cloned().collect())
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(document_uri(params)?);
+                Ok(Vec::new())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn text(&self, uri: &str) -> Result<&str, RpcError> {
+        self.documents
+            .get(uri)
+            .map(|text| text.as_str())
+            .ok_or_else(|| RpcError::invalid_params(format!("Document is not open: {}", uri)))
+    }
+
+    // The wikilink under the cursor, if any
+    fn link_at(&self, params: &JsonValue) -> Result<Option<WikiLink>, RpcError> {
+        let text = self.text(document_uri(params)?)?;
+        let offset = offset_at(text, position(params)?);
+        Ok(body_links(text)
+            .into_iter()
+            .find(|link| link.start <= offset && offset < link.end))
+    }
+
+    fn completion(&mut self, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        let text = self.text(document_uri(params)?)?;
+        let Some((start, end, closed)) = typed_link(text, position(params)?) else {
+            return Ok(JsonValue::Null);
+        };
+
+        let loaded = self.vault()?.loaded()?;
+        let mut titles: HashMap<String, usize> = HashMap::new();
+        for note in &loaded.notes {
+            *titles.entry(note.meta.title.to_lowercase()).or_insert(0) += 1;
+        }
+
+        let items: Vec<JsonValue> = loaded
+            .notes
+            .iter()
+            .map(|note| {
+                let title = &note.meta.title;
+                // A title shared by several notes would resolve to the first
+                // of them, so link those by path instead
+                let mut link = if titles[&title.to_lowercase()] > 1 {
+                    format!("{}|{}", note_target(&note.id), title)
+                } else {
+                    title.clone()
+                };
+                if !closed {
+                    link.push_str("]]");
+                }
+                json!({
+                    "label": title,
+                    "kind": COMPLETION_KIND_FILE,
+                    "detail": note.id,
+                    "filterText": title,
+                    "textEdit": {
+                        "range": range(start, end),
+                        "newText": link,
+                    },
+                })
+            })
+            .collect();
+        Ok(json!({"isIncomplete": false, "items": items}))
+    }
+
+    fn definition(&mut self, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        let Some(link) = self.link_at(params)? else {
+            return Ok(JsonValue::Null);
+        };
+        let uri = document_uri(params)?.to_string();
+
+        let vault = self.vault()?;
+        let vault_path = vault.path.clone();
+        let loaded = vault.loaded()?;
+        let Some(note) = link_note(loaded, &vault_path, &uri, &link) else {
+            return Ok(JsonValue::Null);
+        };
+
+        let line = link
+            .heading
+            .as_deref()
+            .and_then(|heading| heading_line(&note.content, heading))
+            .unwrap_or(0);
+        Ok(json!({
+            "uri": file_uri(&note.meta.path),
+            "range": range((line, 0), (line, 0)),
+        }))
+    }
+
+    // Every link to the note of the document, in open documents as they are
+    // in the editor and in other notes as they are on disk
+    fn references(&mut self, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        let uri = document_uri(params)?.to_string();
+        // Borrow the cache alone, as the open documents are read below
+        let vault = self.cache.vault(self.vault.as_deref())?;
+        let Some(target) = note_id(&vault.path, &uri) else {
+            return Ok(json!([]));
+        };
+        let loaded = vault.loaded()?;
+        // Notes that are not open only need a look if the graph has them
+        // linking to the target
+        let sources: BTreeSet<String> = loaded
+            .graph()
+            .edges
+            .iter()
+            .filter(|edge| edge.target == target)
+            .map(|edge| edge.source.clone())
+            .collect();
+
+        let mut locations = Vec::new();
+        for note in &loaded.notes {
+            let note_uri = file_uri(&note.meta.path);
+            let text = match self.documents.get(&note_uri) {
+                Some(text) => text.as_str(),
+                None if sources.contains(&note.id) => &note.content,
+                None => continue,
+            };
+
+            for link in body_links(text) {
+                let resolved = if link.target.is_empty() {
+                    Some(note.id.as_str())
+                } else {
+                    loaded.links.resolve(&link.target)
+                };
+                if resolved == Some(target.as_str()) && note.id != target {
+                    locations.push(json!({
+                        "uri": note_uri,
+                        "range": range(position_at(text, link.start), position_at(text, link.end)),
+                    }));
+                }
+            }
+        }
+        Ok(json!(locations))
+    }
+
+    fn hover(&mut self, params: &JsonValue) -> Result<JsonValue, RpcError> {
+        let Some(link) = self.link_at(params)? else {
+            return Ok(JsonValue::Null);
+        };
+        let uri = document_uri(params)?.to_string();
+        let text = self.text(&uri)?.to_string();
+
+        let vault = self.vault()?;
+        let vault_path = vault.path.clone();
+        let loaded = vault.loaded()?;
+        let Some(note) = link_note(loaded, &vault_path, &uri, &link) else {
+            return Ok(JsonValue::Null);
+        };
+
+        Ok(json!({
+            "contents": {"kind": "markdown", "value": preview(note)},
+            "range": range(position_at(&text, link.start), position_at(&text, link.end)),
+        }))
+    }
+
+    fn diagnostics(&mut self, uri: &str) -> Result<JsonValue, RpcError> {
+        let text = self.text(uri)?.to_string();
+        let loaded = self.vault()?.loaded()?;
+
+        let diagnostics: Vec<JsonValue> = body_links(&text)
+            .into_iter()
+            .filter(|link| is_broken(loaded, link))
+            .map(|link| {
+                json!({
+                    "range": range(position_at(&text, link.start), position_at(&text, link.end)),
+                    "severity": SEVERITY_WARNING,
+                    "source": "ncy",
+                    "message": format!("No note found for '{}'", link.target),
+                })
+            })
+            .collect();
+        Ok(json!({"uri": uri, "diagnostics": diagnostics}))
+    }
+}
+
+// A link to a heading of the same note has an empty target. Links to files
+// other than notes, such as embedded images, are not checked
+fn is_broken(loaded: &Loaded, link: &WikiLink) -> bool {
+    let target = link.target.as_str();
+    let is_attachment = Path::new(target).extension().is_some_and(|ext| ext != "md");
+    !target.is_empty() && !is_attachment && loaded.links.resolve(target).is_none()
+}
+
+// The note a link points to; links to a heading point to their own note
+fn link_note<'a>(
+    loaded: &'a Loaded,
+    vault_path: &Path,
+    uri: &str,
+    link: &WikiLink,
+) -> Option<&'a LoadedNote> {
+    let id = if link.target.is_empty() {
+        note_id(vault_path, uri)?
+    } else {
+        loaded.links.resolve(&link.target)?.to_string()
+    };
+    loaded.note(&id)
+}
+
+// The id of the note a document URI points to, if it is in the vault
+fn note_id(vault_path: &Path, uri: &str) -> Option<String> {
+    let path = path_from_uri(uri)?;
+    path.starts_with(vault_path)
+        .then(|| relative_path(vault_path, &path))
+}
+
+// Wikilinks in the body of a document, with offsets into the whole text
+fn body_links(text: &str) -> Vec<WikiLink> {
+    let body = split_frontmatter(text).1;
+    let body_start = text.len() - body.len();
+    find_wikilinks(body)
+        .into_iter()
+        .map(|mut link| {
+            link.start += body_start;
+            link.end += body_start;
+            link
+        })
+        .collect()
+}
+
+// Link target for a note path: the path without `.md`
+fn note_target(id: &str) -> &str {
+    id.strip_suffix(".md").unwrap_or(id)
+}
+
+fn preview(note: &LoadedNote) -> String {
+    let body = note.body().trim();
+    let mut lines: Vec<&str> = body.lines().take(PREVIEW_LINES).collect();
+    if body.lines().count() > PREVIEW_LINES {
+        lines.push("…");
+    }
+    format!(
+        "**{}** · `{}`\n\n---\n\n{}",
+        note.meta.title,
+        note.id,
+        lines.join("\n")
+    )
+}
+
+// The line of a markdown heading with the given text, compared without case
+fn heading_line(content: &str, heading: &str) -> Option<usize> {
+    content.lines().position(|line| {
+        let trimmed = line.trim_start();
+        trimmed.starts_with('#')
+            && trimmed
+                .trim_start_matches('#')
+                .trim()
+                .eq_ignore_ascii_case(heading.trim())
+    })
+}
+
+fn document_uri(params: &JsonValue) -> Result<&str, RpcError> {
+    params["textDocument"]["uri"]
+        .as_str()
+        .ok_or_else(|| RpcError::invalid_params("Missing textDocument.uri"))
+}
+
+fn position(params: &JsonValue) -> Result<Position, RpcError> {
+    let field = |name: &str| {
+        params["position"][name]
+            .as_u64()
+            .map(|value| value as usize)
+            .ok_or_else(|| RpcError::invalid_params(format!("Missing position.{}", name)))
+    };
+    Ok((field("line")?, field("character")?))
+}
+
+fn range(start: Position, end: Position) -> JsonValue {
+    json!({
+        "start": {"line": start.0, "character": start.1},
+        "end": {"line": end.0, "character": end.1},
+    })
+}
+
+// Only complete right after an unclosed [[ on the same line: the positions
+// of what was typed after it, and whether ]] follows the cursor. Both come
+// from the text, as the client's position may be stale or past the line end
+fn typed_link(text: &str, position: Position) -> Option<(Position, Position, bool)> {
+    let offset = offset_at(text, position);
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+    let before = &text[line_start..offset];
+    let open = before.rfind("[[")?;
+    let typed = &before[open + 2..];
+    if typed.contains(']') || typed.contains('|') || typed.contains('#') {
+        return None;
+    }
+    Some((
+        position_at(text, line_start + open + 2),
+        position_at(text, offset),
+        text[offset..].starts_with("]]"),
+    ))
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+// LSP positions count UTF-16 code units within a line. These convert them to
+// and from byte offsets, clamping positions past the end of a line or text
+fn offset_at(text: &str, (line, character): Position) -> usize {
+    let mut offset = 0;
+    for _ in 0..line {
+        match text[offset..].find('\n') {
+            Some(i) => offset += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut units = 0;
+    for (i, c) in text[offset..].char_indices() {
+        if c == '\n' || units >= character {
+            return offset + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn position_at(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count(),
+        utf16_len(&before[line_start..]),
+    )
+}
+
+/// Runs a language server on stdin and stdout for the notes of a vault.
+pub fn execute(vault: Option<&str>) -> Result<()> {
+    let mut server = LanguageServer {
+        cache: VaultCache::new(read_config()?),
+        vault: vault.map(|v| v.to_string()),
+        documents: BTreeMap::new(),
+        shutdown: false,
+    };
+    // Fail early when the vault is not configured
+    server.cache.vault(vault)?;
+
+    let stdin = io::stdin();
+    let mut connection = Connection::new(stdin.lock(), io::stdout());
+
+    loop {
+        let message = match connection.read_message() {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                connection.respond(
+                    JsonValue::Null,
+                    Err(RpcError::new(PARSE_ERROR, format!("{:#}", e))),
+                )?;
+                continue;
+            }
+        };
+
+        let request = match Request::parse(&message) {
+            Ok(request) => request,
+            Err(error) => {
+                let id = message.get("id").cloned().unwrap_or(JsonValue::Null);
+                connection.respond(id, Err(error))?;
+                continue;
+            }
+        };
+
+        if let Some(id) = request.id.clone() {
+            let result = server.handle(&request);
+            connection.respond(id, result)?;
+            continue;
+        }
+        if request.method == "exit" {
+            if server.shutdown {
+                return Ok(());
+            }
+            return Err(anyhow!("Editor exited without shutting down the server"));
+        }
+
+        match server.handle_notification(&request) {
+            Ok(uris) => {
+                for uri in uris {
+                    match server.diagnostics(&uri) {
+                        Ok(diagnostics) => {
+                            connection.notify("textDocument/publishDiagnostics", diagnostics)?
+                        }
+                        Err(error) => eprintln!("Warning: {}", error.message),
+                    }
+                }
+            }
+            Err(error) => eprintln!("Warning: {} failed: {}", request.method, error.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let text = "---\ntitle: A\n---\nCafé 😀 [[B]]\nnext";
+        let offset = text.find("[[").unwrap();
+        assert_eq!(position_at(text, offset), (3, 8));
+        assert_eq!(offset_at(text, (3, 8)), offset);
+        assert_eq!(offset_at(text, (3, 100)), text.find("\nnext").unwrap());
+        assert_eq!(offset_at(text, (9, 0)), text.len());
+
+        let links = body_links(text);
+        assert_eq!(&text[links[0].start..links[0].end], "[[B]]");
+    }
+
+    #[test]
+    fn test_typed_link() {
+        let text = "Café [[Alp\nSee [[Beta]] and [[Ga]]";
+        assert_eq!(typed_link(text, (0, 10)), Some(((0, 7), (0, 10), false)));
+        // A position past the end of the line is clamped to it
+        assert_eq!(typed_link(text, (0, 400)), Some(((0, 7), (0, 10), false)));
+        assert_eq!(typed_link(text, (1, 21)), Some(((1, 19), (1, 21), true)));
+        assert_eq!(typed_link(text, (1, 12)), None);
+        assert_eq!(typed_link(text, (1, 3)), None);
+    }
+
+    #[test]
+    fn test_heading_line() {
+        let content = "# Title\n\nText\n\n## The Plan\n";
+        assert_eq!(heading_line(content, "the plan"), Some(4));
+        assert_eq!(heading_line(content, "Missing"), None);
+    }
+}
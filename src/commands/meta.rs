@@ -0,0 +1,391 @@
+// src/commands/meta.rs
+use crate::commands::ls::{NoteFilter, normalize_project};
+use crate::git;
+use crate::metadata::{
+    find_note, load_vault, parse_frontmatter, relative_path, split_frontmatter, update_frontmatter,
+};
+use crate::utils::{read_config, resolve_vault, yaml_to_json};
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value as JsonValue, json};
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `ncy meta` does with the frontmatter of the selected notes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetaAction {
+    Show,
+    Get(String),
+    Set(String, YamlValue),
+    Unset(String),
+}
+
+impl MetaAction {
+    /// Parses `get <key>`, `set <key> <value>` or `unset <key>`. Without an
+    /// action the whole frontmatter is shown. `set` also accepts `key: value`.
+    pub fn parse(action: Option<&str>, key: Option<&str>, value: &[&str]) -> Result<Self> {
+        let require_key = || {
+            key.map(|k| k.trim().trim_end_matches(':').trim().to_string())
+                .filter(|k| !k.is_empty())
+                .context(format!(
+                    "The '{}' action requires a key",
+                    action.unwrap_or("")
+                ))
+        };
+
+        match action {
+            None => Ok(MetaAction::Show),
+            Some("get") => Ok(MetaAction::Get(require_key()?)),
+            Some("unset") => Ok(MetaAction::Unset(require_key()?)),
+            Some("set") => {
+                // `set "status: archived"` as a single argument
+                if value.is_empty()
+                    && let Some((k, v)) = key.and_then(|k| k.split_once(':'))
+                    && !k.trim().is_empty()
+                {
+                    return Ok(MetaAction::Set(k.trim().to_string(), parse_value(v.trim())));
+                }
+
+                if value.is_empty() {
+                    return Err(anyhow!("The 'set' action requires a key and a value"));
+                }
+                Ok(MetaAction::Set(
+                    require_key()?,
+                    parse_value(&value.join(" ")),
+                ))
+            }
+            Some(other) => Err(anyhow!(
+                "Unknown meta action '{}'. Use get, set or unset",
+                other
+            )),
+        }
+    }
+}
+
+/// Reads a value given on the command line as YAML, so `3`, `true` and
+/// `[a, b]` keep their types. Anything else is a plain string.
+pub fn parse_value(text: &str) -> YamlValue {
+    match serde_yaml::from_str::<YamlValue>(text) {
+        Ok(YamlValue::Null) if !matches!(text.trim(), "null" | "~") => {
+            YamlValue::String(text.to_string())
+        }
+        Ok(YamlValue::Mapping(_)) | Err(_) => YamlValue::String(text.to_string()),
+        Ok(value) => value,
+    }
+}
+
+/// Runs a meta action on one note, or on every note matching the filter
+/// when the selector is '@project' ('@' alone selects the whole vault).
+pub fn execute(
+    selector: &str,
+    action: &MetaAction,
+    vault: Option<&str>,
+    mut filter: NoteFilter,
+    json: bool,
+) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    if !selector.trim_start().starts_with('@') {
+        if filter_is_set(&filter) {
+            return Err(anyhow!(
+                "Filters only apply to '@project' selections, not to a single note"
+            ));
+        }
+        let note_path = find_note(&vault_path, selector)?;
+        return run_single(&vault_path, &note_path, action, json);
+    }
+
+    let project = normalize_project(selector);
+    if !project.is_empty() {
+        if filter.project.is_some() {
+            return Err(anyhow!(
+                "Give the project either as '@project' or with --project, not both"
+            ));
+        }
+        filter.project = Some(project);
+    }
+
+    let mut notes: Vec<PathBuf> = load_vault(&vault_path)?
+        .into_iter()
+        .filter(|note| filter.matches(note))
+        .map(|note| note.path)
+        .collect();
+    notes.sort();
+
+    if notes.is_empty() {
+        return Err(anyhow!("No matching notes found in vault: {}", vault_name));
+    }
+
+    run_bulk(&vault_path, &notes, action, json)
+}
+
+fn run_single(vault_path: &Path, note_path: &Path, action: &MetaAction, json: bool) -> Result<()> {
+    let content = read_note(note_path)?;
+
+    match action {
+        MetaAction::Show if json => {
+            let frontmatter = parse_frontmatter(&content)?;
+            println!("{}", to_pretty_json(&frontmatter)?);
+        }
+        MetaAction::Show => {
+            print!("{}", split_frontmatter(&content).0.unwrap_or_default());
+        }
+        MetaAction::Get(key) => {
+            let frontmatter = parse_frontmatter(&content)?;
+            let value = frontmatter.get(key).context(format!(
+                "{} has no '{}' field",
+                relative_path(vault_path, note_path),
+                key
+            ))?;
+            println!("{}", display_value(value, json)?);
+        }
+        MetaAction::Set(..) | MetaAction::Unset(_) => {
+            if let Some(updated) = apply(&content, action)? {
+                fs::write(note_path, updated).context("Failed to write note")?;
+                println!(
+                    "{}",
+                    describe_change(action, &relative_path(vault_path, note_path))
+                );
+                git::auto_commit_or_warn(&[note_path]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_bulk(vault_path: &Path, notes: &[PathBuf], action: &MetaAction, json: bool) -> Result<()> {
+    match action {
+        MetaAction::Show | MetaAction::Get(_) => {
+            let mut entries = Vec::new();
+            for note_path in notes {
+                let frontmatter = parse_frontmatter(&read_note(note_path)?)?;
+                let value = match action {
+                    MetaAction::Get(key) => match frontmatter.get(key) {
+                        Some(value) => value.clone(),
+                        // Notes without the field are left out
+                        None => continue,
+                    },
+                    _ => frontmatter,
+                };
+                entries.push((relative_path(vault_path, note_path), value));
+            }
+
+            if json {
+                let key = if *action == MetaAction::Show {
+                    "frontmatter"
+                } else {
+                    "value"
+                };
+                let array = entries
+                    .into_iter()
+                    .map(|(path, value)| json!({ "path": path, key: value }))
+                    .collect();
+                println!("{}", to_pretty_json(&JsonValue::Array(array))?);
+            } else {
+                for (path, value) in entries {
+                    println!("{}: {}", path, display_value(&value, false)?);
+                }
+            }
+            Ok(())
+        }
+        MetaAction::Set(..) | MetaAction::Unset(_) => {
+            let mut changed = Vec::new();
+            let mut failures = 0;
+
+            for note_path in notes {
+                let relative = relative_path(vault_path, note_path);
+                let result = read_note(note_path)
+                    .and_then(|content| apply(&content, action))
+                    .and_then(|updated| match updated {
+                        Some(updated) => fs::write(note_path, updated)
+                            .context("Failed to write note")
+                            .map(|_| true),
+                        None => Ok(false),
+                    });
+
+                match result {
+                    Ok(true) => {
+                        println!("{}", describe_change(action, &relative));
+                        changed.push(note_path.as_path());
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        eprintln!("Failed to update {}: {}", relative, e);
+                        failures += 1;
+                    }
+                }
+            }
+
+            println!("Updated {} of {} notes", changed.len(), notes.len());
+            if !changed.is_empty() {
+                git::auto_commit_or_warn(&changed);
+            }
+
+            if failures > 0 {
+                return Err(anyhow!(
+                    "Failed to update {} of {} notes",
+                    failures,
+                    notes.len()
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Applies `set` or `unset` to the frontmatter of a note. Returns the new
+/// content, or `None` if nothing changed. The body is kept byte-for-byte.
+fn apply(content: &str, action: &MetaAction) -> Result<Option<String>> {
+    let mut changed = false;
+
+    let updated = update_frontmatter(content, |mapping| {
+        match action {
+            MetaAction::Set(key, value) => {
+                let key = YamlValue::String(key.clone());
+                if mapping.get(&key) != Some(value) {
+                    mapping.insert(key, value.clone());
+                    changed = true;
+                }
+            }
+            MetaAction::Unset(key) => {
+                let key = YamlValue::String(key.clone());
+                if mapping.contains_key(&key) {
+                    // serde_yaml 0.8.26 backs Mapping with an IndexMap, whose
+                    // remove moves the last key into the gap, so the mapping
+                    // is rebuilt to keep the other keys in place
+                    *mapping = mapping
+                        .iter()
+                        .filter(|(k, _)| **k != key)
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect::<Mapping>();
+                    changed = true;
+                }
+            }
+            MetaAction::Show | MetaAction::Get(_) => {}
+        }
+        Ok(())
+    })?;
+
+    Ok(Some(updated).filter(|_| changed))
+}
+
+fn describe_change(action: &MetaAction, note: &str) -> String {
+    match action {
+        MetaAction::Set(key, value) => {
+            let value = display_value(&yaml_to_json(value.clone()), false).unwrap_or_default();
+            format!("Set {} = {} in {}", key, value, note)
+        }
+        MetaAction::Unset(key) => format!("Removed {} from {}", key, note),
+        MetaAction::Show | MetaAction::Get(_) => String::new(),
+    }
+}
+
+/// Formats a value for printing: strings as they are, anything else as JSON.
+fn display_value(value: &JsonValue, json: bool) -> Result<String> {
+    match value {
+        JsonValue::String(s) if !json => Ok(s.clone()),
+        _ => serde_json::to_string(value).context("Failed to serialize value"),
+    }
+}
+
+fn to_pretty_json(value: &JsonValue) -> Result<String> {
+    serde_json::to_string_pretty(value).context("Failed to serialize frontmatter")
+}
+
+fn read_note(note_path: &Path) -> Result<String> {
+    fs::read_to_string(note_path).context(format!("Failed to read note: {}", note_path.display()))
+}
+
+fn filter_is_set(filter: &NoteFilter) -> bool {
+    filter.project.is_some()
+        || !filter.tags.is_empty()
+        || !filter.fields.is_empty()
+        || filter.created_after.is_some()
+        || filter.created_before.is_some()
+        || filter.modified_after.is_some()
+        || filter.modified_before.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meta_action() {
+        assert_eq!(
+            MetaAction::parse(None, None, &[]).unwrap(),
+            MetaAction::Show
+        );
+        assert_eq!(
+            MetaAction::parse(Some("get"), Some("status"), &[]).unwrap(),
+            MetaAction::Get("status".to_string())
+        );
+        assert_eq!(
+            MetaAction::parse(Some("set"), Some("title"), &["Two", "words"]).unwrap(),
+            MetaAction::Set("title".to_string(), YamlValue::from("Two words"))
+        );
+        assert_eq!(
+            MetaAction::parse(Some("set"), Some("status: archived"), &[]).unwrap(),
+            MetaAction::Set("status".to_string(), YamlValue::from("archived"))
+        );
+        assert_eq!(
+            MetaAction::parse(Some("set"), Some("status:"), &["archived"]).unwrap(),
+            MetaAction::Set("status".to_string(), YamlValue::from("archived"))
+        );
+        assert!(MetaAction::parse(Some("set"), Some("status"), &[]).is_err());
+        assert!(MetaAction::parse(Some("unset"), None, &[]).is_err());
+        assert!(MetaAction::parse(Some("delete"), Some("x"), &[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("3"), YamlValue::from(3));
+        assert_eq!(parse_value("true"), YamlValue::from(true));
+        assert_eq!(parse_value("2024-05-01"), YamlValue::from("2024-05-01"));
+        assert_eq!(parse_value("a: b"), YamlValue::from("a: b"));
+        assert_eq!(parse_value(""), YamlValue::from(""));
+        assert_eq!(
+            parse_value("[a, b]"),
+            YamlValue::Sequence(vec![YamlValue::from("a"), YamlValue::from("b")])
+        );
+    }
+
+    #[test]
+    fn test_apply_keeps_body_and_key_order() {
+        let body = "Body with --- and\r\ntrailing spaces  \n\n";
+        let content = format!(
+            "---\ntitle: Note\nstatus: draft\ndate: 2024-01-01\n---\n{}",
+            body
+        );
+
+        let set = MetaAction::Set("status".to_string(), YamlValue::from("archived"));
+        let updated = apply(&content, &set).unwrap().unwrap();
+        assert_eq!(
+            updated,
+            format!(
+                "---\ntitle: Note\nstatus: archived\ndate: 2024-01-01\n---\n{}",
+                body
+            )
+        );
+        assert_eq!(apply(&updated, &set).unwrap(), None);
+
+        let unset = MetaAction::Unset("title".to_string());
+        let updated = apply(&updated, &unset).unwrap().unwrap();
+        assert_eq!(
+            updated,
+            format!("---\nstatus: archived\ndate: 2024-01-01\n---\n{}", body)
+        );
+        assert_eq!(apply(&updated, &unset).unwrap(), None);
+    }
+
+    #[test]
+    fn test_apply_adds_frontmatter_to_plain_note() {
+        let set = MetaAction::Set("status".to_string(), YamlValue::from("new"));
+        assert_eq!(
+            apply("# Heading\n", &set).unwrap().unwrap(),
+            "---\nstatus: new\n---\n# Heading\n"
+        );
+    }
+}
@@ -1,6 +1,29 @@
+pub mod agenda;
+pub mod ask;
+pub mod attachments;
+pub mod batch;
+pub mod commit;
+pub mod completions;
 pub mod dir;
+pub mod encrypt;
+pub mod export;
+pub mod graph;
+pub mod import;
+pub mod inbox;
 pub mod init;
 pub mod jrnl;
+pub mod ls;
+pub mod lsp;
+pub mod meta;
 pub mod new;
 pub mod open;
+pub mod project;
+pub mod query;
+pub mod recent;
+pub mod related;
+pub mod serve;
 pub mod set;
+pub mod shell_init;
+pub mod validate;
+pub mod watch;
+pub mod workspace;
@@ -1,17 +1,37 @@
 // src/commands/new.rs
-use crate::utils::read_config;
+use crate::commands::encrypt;
+use crate::commands::ls::normalize_project;
+use crate::commands::meta::parse_value;
+use crate::crypto;
+use crate::git;
+use crate::history::{self, Action};
+use crate::metadata::{parse_frontmatter, update_frontmatter};
+use crate::schema::{FieldRule, FieldType, Schema, describe_values, load_schemas, schemas_for};
+use crate::utils::{read_config, yaml_to_json};
 use anyhow::{Context, Result, anyhow};
 use notemancy_core::notes::crud::create_note;
+use serde_json::Value as JsonValue;
 use std::env;
-use std::path::Path;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 // Original execute function now calls execute_with_options with external=false
 pub fn execute(args: &str) -> Result<()> {
-    execute_with_options(args, false)
+    execute_with_options(args, false, false)
 }
 
-pub fn execute_with_options(args: &str, external: bool) -> Result<()> {
+/// Creates a note from 'title @ project/path +vault' and opens it, or prints
+/// its path in external mode. With `encrypt` the note is encrypted with a
+/// new passphrase before anything is written to it.
+pub fn execute_with_options(args: &str, external: bool, encrypt: bool) -> Result<()> {
+    if encrypt && external {
+        return Err(anyhow!(
+            "Encrypted notes cannot be created in external mode, as the editor would get the encrypted text"
+        ));
+    }
+
     // Parse the arguments: "title @ project/path +vault"
     let (title, project, vault) = parse_arguments(args)?;
 
@@ -37,10 +57,29 @@ pub fn execute_with_options(args: &str, external: bool) -> Result<()> {
     let vault_path = Path::new(&vault_directory);
 
     // Create the note
-    let note_path = create_note(&title, vault_path, &project).context(format!(
-        "Failed to create note '{}' in project '{}'",
-        title, project
-    ))?;
+    let interactive = !external && io::stdin().is_terminal();
+    let note_path = create_with_schemas(
+        &config,
+        &vault_name,
+        vault_path,
+        &title,
+        &project,
+        interactive,
+    )?;
+
+    // Ask for the passphrase once the note exists, and open it through a decrypted copy
+    if encrypt {
+        let passphrase = crypto::read_passphrase(true)?;
+        encrypt::encrypt_file(&note_path, &passphrase)?;
+        println!(
+            "Created encrypted note: {} in {}",
+            title,
+            note_path.display()
+        );
+        encrypt::edit(&note_path, Some(&passphrase))?;
+        git::auto_commit_or_warn(&[&note_path]);
+        return Ok(());
+    }
 
     // If in external mode, just print the absolute path and return
     if external {
@@ -49,6 +88,7 @@ pub fn execute_with_options(args: &str, external: bool) -> Result<()> {
             .canonicalize()
             .context("Failed to get absolute path for created note")?;
         println!("{}", canonical_path.display());
+        git::auto_commit_or_warn(&[&note_path]);
         return Ok(());
     }
 
@@ -69,9 +109,132 @@ pub fn execute_with_options(args: &str, external: bool) -> Result<()> {
         return Err(anyhow!("Editor exited with non-zero status"));
     }
 
+    git::auto_commit_or_warn(&[&note_path]);
     Ok(())
 }
 
+/// Creates a note in a project of the vault and records it in the history.
+/// Everything that adds notes to a vault goes through here.
+pub fn create(title: &str, vault_path: &Path, project: &str) -> Result<PathBuf> {
+    let note_path = create_note(title, vault_path, project).context(format!(
+        "Failed to create note '{}' in project '{}'",
+        title, project
+    ))?;
+    history::record_or_warn(&note_path, Action::Create);
+    Ok(note_path)
+}
+
+/// Creates a note and adds the fields required by the schemas of its
+/// project, asking for their values when `interactive` is set.
+pub fn create_with_schemas(
+    config: &JsonValue,
+    vault_name: &str,
+    vault_path: &Path,
+    title: &str,
+    project: &str,
+    interactive: bool,
+) -> Result<PathBuf> {
+    // Load the project's schemas first so a broken schema does not leave a half-made note
+    let schemas = load_schemas(config, vault_name, vault_path)?;
+    let schemas = schemas_for(&schemas, &normalize_project(project));
+
+    let note_path = create(title, vault_path, project)?;
+    if !schemas.is_empty() {
+        fill_required_fields(&note_path, &schemas, interactive)?;
+    }
+    Ok(note_path)
+}
+
+/// Adds the fields required by the schemas to a new note. Values are asked
+/// for when running interactively; otherwise, or when an answer is left
+/// empty, the field gets its default or an empty value to fill in later.
+fn fill_required_fields(note_path: &Path, schemas: &[&Schema], interactive: bool) -> Result<()> {
+    let content = fs::read_to_string(note_path).context("Failed to read created note")?;
+    let frontmatter = parse_frontmatter(&content)?;
+
+    let mut values: Vec<(String, JsonValue)> = Vec::new();
+    for field in schemas.iter().flat_map(|schema| &schema.required) {
+        if frontmatter.get(field).is_some() || values.iter().any(|(f, _)| f == field) {
+            continue;
+        }
+
+        let rule = schemas
+            .iter()
+            .find_map(|schema| schema.rule(field))
+            .cloned()
+            .unwrap_or_default();
+        let answer = if interactive {
+            prompt_field(field, &rule)?
+        } else {
+            None
+        };
+        let value = answer
+            .or(rule.default)
+            .unwrap_or(JsonValue::String(String::new()));
+        values.push((field.clone(), value));
+    }
+
+    if values.is_empty() {
+        return Ok(());
+    }
+
+    let updated = update_frontmatter(&content, |mapping| {
+        for (field, value) in values {
+            let value = serde_yaml::to_value(&value).context("Failed to convert field value")?;
+            mapping.insert(serde_yaml::Value::String(field), value);
+        }
+        Ok(())
+    })?;
+    fs::write(note_path, updated).context("Failed to write created note")
+}
+
+// Asks for a field until the answer follows its rule. Returns None for an empty answer
+fn prompt_field(field: &str, rule: &FieldRule) -> Result<Option<JsonValue>> {
+    let mut hints = Vec::new();
+    if !rule.one_of.is_empty() {
+        hints.push(describe_values(&rule.one_of));
+    }
+    if let Some(default) = &rule.default {
+        hints.push(format!(
+            "default: {}",
+            describe_values(std::slice::from_ref(default))
+        ));
+    }
+
+    loop {
+        if hints.is_empty() {
+            print!("{}: ", field);
+        } else {
+            print!("{} ({}): ", field, hints.join("; "));
+        }
+        io::stdout().flush().context("Failed to write prompt")?;
+
+        let mut line = String::new();
+        if io::stdin()
+            .read_line(&mut line)
+            .context("Failed to read answer")?
+            == 0
+        {
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        // Strings are taken as typed, everything else is read as YAML like `ncy meta set`
+        let value = if rule.kind == Some(FieldType::String) {
+            JsonValue::String(line.to_string())
+        } else {
+            yaml_to_json(parse_value(line))
+        };
+        match rule.check(&value).first() {
+            Some(problem) => println!("  {}", problem),
+            None => return Ok(Some(value)),
+        }
+    }
+}
+
 /// Parses the command arguments in the format: "title @ project/path +vault"
 /// Returns a tuple of (title, project, vault) where vault is an Option<String>
 fn parse_arguments(args: &str) -> Result<(String, String, Option<String>)> {
@@ -0,0 +1,353 @@
+// src/commands/project.rs
+use crate::commands::ls::{normalize_project, project_matches};
+use crate::commands::new;
+use crate::git;
+use crate::links::{
+    LinkIndex, decode_path, relative_url, replace_markdown_links, replace_wikilinks,
+    resolve_relative,
+};
+use crate::metadata::{
+    list_projects, load_vault, parse_frontmatter, relative_path, split_frontmatter,
+};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local};
+use notemancy_core::notes::utils::list_all_notes_alt;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+const ARCHIVE_PROJECT: &str = "archive";
+
+/// Prints the projects of a vault as a tree, with the number of notes in
+/// each (subprojects included) and when the latest of them changed.
+pub fn list(vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let notes = load_vault(&vault_path)?;
+
+    let mut rows = vec![(format!("{}/", vault_name), String::new())];
+    rows.extend(list_projects(&vault_path).into_iter().map(|project| {
+        let depth = project.matches('/').count() + 1;
+        let name = project.rsplit('/').next().unwrap_or(&project);
+        (format!("{}{}/", "  ".repeat(depth), name), project)
+    }));
+
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (label, project) in rows {
+        let matching: Vec<DateTime<Local>> = notes
+            .iter()
+            .filter(|note| project_matches(&note.project, &project))
+            .map(|note| note.modified)
+            .collect();
+        let modified = matching
+            .iter()
+            .max()
+            .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<width$}  {:>5}  {}",
+            label,
+            matching.len(),
+            modified,
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+/// Creates a project directory, with an index note named after it if asked.
+pub fn create(path: &str, vault: Option<&str>, index: bool) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let project = checked_project(path)?;
+
+    let project_path = vault_path.join(&project);
+    if project_path.exists() {
+        return Err(anyhow!(
+            "Project '{}' already exists in vault '{}'",
+            project,
+            vault_name
+        ));
+    }
+    fs::create_dir_all(&project_path).context(format!(
+        "Failed to create project directory: {}",
+        project_path.display()
+    ))?;
+    println!("Created project {}", project);
+
+    if index {
+        let title = index_title(&project);
+        let interactive = io::stdin().is_terminal();
+        let note_path = new::create_with_schemas(
+            &config,
+            &vault_name,
+            &vault_path,
+            &title,
+            &project,
+            interactive,
+        )?;
+        println!(
+            "Created index note: {}",
+            relative_path(&vault_path, &note_path)
+        );
+        git::auto_commit_or_warn(&[&note_path]);
+    }
+
+    Ok(())
+}
+
+/// Moves a project under `archive/`, rewriting links that would otherwise
+/// break: wikilinks by path and relative markdown links, into the project,
+/// out of it and within it.
+pub fn archive(path: &str, vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+    let project = checked_project(path)?;
+
+    if project_matches(&project, ARCHIVE_PROJECT) {
+        return Err(anyhow!("Project '{}' is already archived", project));
+    }
+    let source = vault_path.join(&project);
+    if !source.is_dir() {
+        return Err(anyhow!(
+            "Project '{}' not found in vault '{}'",
+            project,
+            vault_name
+        ));
+    }
+    let archived = format!("{}/{}", ARCHIVE_PROJECT, project);
+    let target = vault_path.join(&archived);
+    if target.exists() {
+        return Err(anyhow!("{} already exists", archived));
+    }
+
+    let mut notes: BTreeMap<String, String> = BTreeMap::new();
+    for note_path in list_all_notes_alt(&vault_path, false)? {
+        let note_path = Path::new(&note_path);
+        let content = fs::read_to_string(note_path)
+            .context(format!("Failed to read note: {}", note_path.display()))?;
+        notes.insert(relative_path(&vault_path, note_path), content);
+    }
+
+    let moved = |id: &str| -> String {
+        match id.strip_prefix(&project) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{}", archived, rest)
+            }
+            _ => id.to_string(),
+        }
+    };
+    let updated = rewrite_links(&notes, &moved);
+
+    // The links are rewritten before the project moves, so the notes inside
+    // it move along already updated. If anything fails, the notes written so
+    // far get their old content back and the project stays where it was
+    let mut written: Vec<&String> = Vec::new();
+    let result = updated
+        .iter()
+        .try_for_each(|(id, content)| {
+            let note_path = vault_path.join(id);
+            fs::write(&note_path, content)
+                .context(format!("Failed to write note: {}", note_path.display()))?;
+            written.push(id);
+            Ok(())
+        })
+        .and_then(|()| {
+            fs::create_dir_all(target.parent().unwrap_or(&vault_path))
+                .context("Failed to create archive directory")?;
+            fs::rename(&source, &target)
+                .context(format!("Failed to move {} to {}", project, archived))
+        });
+    if let Err(e) = result {
+        for id in written {
+            let note_path = vault_path.join(id);
+            if let Err(restore_error) = fs::write(&note_path, &notes[id]) {
+                eprintln!(
+                    "Warning: failed to restore {}: {}",
+                    note_path.display(),
+                    restore_error
+                );
+            }
+        }
+        return Err(e);
+    }
+
+    println!("Archived {} to {}", project, archived);
+    if !updated.is_empty() {
+        println!("Updated links in {} notes", updated.len());
+    }
+    git::auto_commit_or_warn(&[&target]);
+    Ok(())
+}
+
+/// Rewrites the links of notes, given by path relative to the vault, for
+/// notes and files that move as `moved` says. Returns the notes that changed,
+/// under their old paths.
+fn rewrite_links<F>(notes: &BTreeMap<String, String>, moved: &F) -> BTreeMap<String, String>
+where
+    F: Fn(&str) -> String,
+{
+    let titles: Vec<(String, String)> = notes
+        .iter()
+        .map(|(id, content)| (id.clone(), note_title(id, content)))
+        .collect();
+    let before = LinkIndex::new(
+        titles
+            .iter()
+            .map(|(id, title)| (id.as_str(), title.as_str())),
+    );
+    let moved_titles: Vec<(String, &str)> = titles
+        .iter()
+        .map(|(id, title)| (moved(id), title.as_str()))
+        .collect();
+    let after = LinkIndex::new(moved_titles.iter().map(|(id, title)| (id.as_str(), *title)));
+
+    let mut updated = BTreeMap::new();
+    for (id, content) in notes {
+        let new_id = moved(id);
+        let (frontmatter, body) = split_frontmatter(content);
+        let body_start = content.len() - body.len();
+
+        // A wikilink needs a new target when it would resolve to another
+        // note, or to none, once the notes have moved
+        let body = replace_wikilinks(body, |link| {
+            let original = || content[body_start + link.start..body_start + link.end].to_string();
+            let Some(old_target) = before.resolve(&link.target) else {
+                return original();
+            };
+            let expected = moved(old_target);
+            if after.resolve(&link.target) == Some(expected.as_str()) {
+                return original();
+            }
+            link.with_target(expected.strip_suffix(".md").unwrap_or(&expected))
+        });
+
+        // Relative markdown links need a new path when either end moved
+        let body = replace_markdown_links(&body, |url| {
+            if url.contains("://") || url.starts_with("mailto:") || url.starts_with('#') {
+                return None;
+            }
+            let (path, fragment) = match url.split_once('#') {
+                Some((path, fragment)) => (path, Some(fragment)),
+                None => (url, None),
+            };
+            let old_target = resolve_relative(id, &decode_path(path));
+            let new_target = moved(&old_target);
+            if new_id == *id && new_target == old_target {
+                return None;
+            }
+            let mut url = relative_url(&new_id, &new_target);
+            if let Some(fragment) = fragment {
+                url.push('#');
+                url.push_str(fragment);
+            }
+            Some(url)
+        });
+
+        let rewritten = match frontmatter {
+            Some(_) => format!("{}{}", &content[..body_start], body),
+            None => body,
+        };
+        if rewritten != *content {
+            updated.insert(id.clone(), rewritten);
+        }
+    }
+    updated
+}
+
+// Title of a note for resolving links: the frontmatter title, or the file name
+fn note_title(id: &str, content: &str) -> String {
+    let frontmatter = parse_frontmatter(content).unwrap_or_default();
+    let title = frontmatter.get("title").and_then(|t| t.as_str());
+    title.map(|t| t.to_string()).unwrap_or_else(|| {
+        let name = id.rsplit('/').next().unwrap_or(id);
+        name.strip_suffix(".md").unwrap_or(name).to_string()
+    })
+}
+
+// A project path that stays inside the vault and out of hidden directories
+fn checked_project(path: &str) -> Result<String> {
+    let project = normalize_project(path);
+    if project.is_empty() {
+        return Err(anyhow!("A project path is required"));
+    }
+    if project
+        .split('/')
+        .any(|part| part.is_empty() || part == "." || part == ".." || part.starts_with('.'))
+    {
+        return Err(anyhow!("Invalid project path: {}", path));
+    }
+    Ok(project)
+}
+
+/// The title of the note named after a path: "projects/deep-learning" gets
+/// "Deep learning".
+pub fn index_title(project: &str) -> String {
+    let name = project.rsplit('/').next().unwrap_or(project);
+    let words = name.replace(['-', '_'], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::find_wikilinks;
+
+    #[test]
+    fn test_rewrite_links() {
+        let notes: BTreeMap<String, String> = [
+            (
+                "home.md",
+                "See [[projects/research/alpha|Alpha]], [[Alpha]] and [notes](projects/research/alpha.md#plan).\n",
+            ),
+            (
+                "projects/research/alpha.md",
+                "---\ntitle: Alpha\n---\nBack to [home](../../home.md), [beta](beta.md) and [[home]].\n",
+            ),
+            ("projects/research/beta.md", "Nothing here.\n"),
+        ]
+        .into_iter()
+        .map(|(id, content)| (id.to_string(), content.to_string()))
+        .collect();
+
+        let moved = |id: &str| match id.strip_prefix("projects/research/") {
+            Some(rest) => format!("archive/projects/research/{}", rest),
+            None => id.to_string(),
+        };
+        let updated = rewrite_links(&notes, &moved);
+
+        assert_eq!(updated.len(), 2);
+        assert_eq!(
+            updated["home.md"],
+            "See [[archive/projects/research/alpha|Alpha]], [[Alpha]] and [notes](archive/projects/research/alpha.md#plan).\n"
+        );
+        assert_eq!(
+            updated["projects/research/alpha.md"],
+            "---\ntitle: Alpha\n---\nBack to [home](../../../home.md), [beta](beta.md) and [[home]].\n"
+        );
+        assert!(find_wikilinks(&updated["home.md"]).len() == 2);
+    }
+
+    #[test]
+    fn test_checked_project_and_index_title() {
+        assert_eq!(
+            checked_project("@ projects/research/").unwrap(),
+            "projects/research"
+        );
+        assert!(checked_project("../outside").is_err());
+        assert!(checked_project(".git").is_err());
+        assert!(checked_project("").is_err());
+        assert_eq!(index_title("projects/deep-learning"), "Deep learning");
+    }
+}
@@ -0,0 +1,353 @@
+// src/commands/query.rs
+use crate::commands::batch::{open_in_editor, print_paths};
+use crate::metadata::{NoteMeta, load_vault, relative_path};
+use crate::picker::{NoteChoice, PickOptions, Picked, pick_notes};
+use crate::query::{self, field_value};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value as JsonValue;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Folder inside every vault that holds saved queries
+pub const QUERIES_DIR: &str = "queries";
+
+// Longest a table cell gets before it is cut off
+const MAX_CELL_WIDTH: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryFormat {
+    Table,
+    Json,
+    Paths,
+    Titles,
+}
+
+impl QueryFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "table" => Ok(QueryFormat::Table),
+            "json" => Ok(QueryFormat::Json),
+            "paths" => Ok(QueryFormat::Paths),
+            "titles" => Ok(QueryFormat::Titles),
+            _ => Err(anyhow!("Unknown output format '{}'", value)),
+        }
+    }
+}
+
+/// How to show the notes a query returns.
+pub struct QueryOutput {
+    pub format: QueryFormat,
+    /// Pick among the results and open them instead of printing them
+    pub pick: bool,
+    pub external: bool,
+}
+
+/// Runs a query given on the command line, or a saved one by name. With
+/// `save_as`, the query is stored in the vault before it runs.
+pub fn execute(
+    text: Option<&str>,
+    saved: Option<&str>,
+    save_as: Option<&str>,
+    vault: Option<&str>,
+    output: &QueryOutput,
+) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    let text = match (text, saved) {
+        (Some(text), _) => text.to_string(),
+        (None, Some(name)) => load(&vault_path, name)?,
+        (None, None) => return Err(anyhow!("Give a query or the name of a saved query")),
+    };
+    let query = query::parse(&text)?;
+
+    if let Some(name) = save_as {
+        save(&vault_path, name, &text)?;
+        eprintln!("Saved query '{}'", name);
+    }
+
+    let mut notes: Vec<(NoteMeta, String)> = load_vault(&vault_path)?
+        .into_iter()
+        .map(|note| {
+            let path = relative_path(&vault_path, &note.path);
+            (note, path)
+        })
+        .filter(|(note, path)| query.matches(note, path))
+        .collect();
+    query.sort(&mut notes);
+    if let Some(limit) = query.limit {
+        notes.truncate(limit);
+    }
+
+    if notes.is_empty() {
+        if output.pick {
+            return Err(anyhow!(
+                "No notes in vault '{}' match the query",
+                vault_name
+            ));
+        }
+        if output.format == QueryFormat::Table {
+            println!("No notes match the query");
+            return Ok(());
+        }
+    }
+
+    if output.pick {
+        return pick_results(&notes, output.external);
+    }
+
+    match output.format {
+        QueryFormat::Table => print_table(&notes, &query.columns),
+        QueryFormat::Json => {
+            let json = JsonValue::Array(notes.iter().map(|(note, _)| note.to_json()).collect());
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json).context("Failed to serialize notes")?
+            );
+        }
+        QueryFormat::Paths => {
+            for (note, _) in &notes {
+                println!("{}", note.path.display());
+            }
+        }
+        QueryFormat::Titles => {
+            for (note, _) in &notes {
+                println!("{}", note.title);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn list(vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    let saved = load_all(&vault_path)?;
+    if saved.is_empty() {
+        println!("No saved queries in vault: {}", vault_name);
+        return Ok(());
+    }
+
+    let width = saved.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, text) in saved {
+        println!(
+            "{:width$}  {}",
+            name,
+            text.replace('\n', " "),
+            width = width
+        );
+    }
+    Ok(())
+}
+
+pub fn delete(name: &str, vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+
+    let path = query_file(&vault_path, name)?;
+    if !path.exists() {
+        return Err(anyhow!("Saved query '{}' not found", name));
+    }
+    fs::remove_file(&path).context(format!("Failed to delete {}", path.display()))?;
+
+    println!("Deleted query '{}'", name);
+    Ok(())
+}
+
+fn pick_results(notes: &[(NoteMeta, String)], external: bool) -> Result<()> {
+    let choices: Vec<NoteChoice> = notes
+        .iter()
+        .map(|(note, _)| NoteChoice {
+            title: note.title.clone(),
+            path: note.path.to_string_lossy().to_string(),
+        })
+        .collect();
+
+    let options = PickOptions {
+        external,
+        multi: true,
+        allow_create: false,
+    };
+    let picked = match pick_notes(&choices, options)? {
+        Picked::Notes(picked) => picked,
+        Picked::Create(_) => return Err(anyhow!("No note selected")),
+    };
+
+    if external {
+        print_paths(&picked);
+        return Ok(());
+    }
+    open_in_editor(&picked)
+}
+
+/// Prints the results as an aligned table: the title, then either the
+/// columns named in the query or the project and modification date.
+fn print_table(notes: &[(NoteMeta, String)], columns: &[String]) {
+    let default_columns = ["project".to_string(), "modified".to_string()];
+    let columns = if columns.is_empty() {
+        &default_columns[..]
+    } else {
+        columns
+    };
+
+    let mut rows = vec![
+        std::iter::once("title".to_string())
+            .chain(columns.iter().cloned())
+            .collect::<Vec<_>>(),
+    ];
+    for (note, path) in notes {
+        let mut row = vec![note.title.clone()];
+        row.extend(
+            columns
+                .iter()
+                .map(|column| field_value(note, path, column).display()),
+        );
+        rows.push(row);
+    }
+
+    for row in &mut rows {
+        for cell in row.iter_mut() {
+            *cell = truncate(&cell.replace('\n', " "), MAX_CELL_WIDTH);
+        }
+    }
+
+    let widths: Vec<usize> = (0..=columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for (i, row) in rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+
+        if i == 0 {
+            let rule = widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            println!("{}", rule);
+        }
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn query_file(vault_path: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') || name.contains('\\') || name.starts_with('.') {
+        return Err(anyhow!("Invalid query name '{}'", name));
+    }
+
+    Ok(vault_path.join(QUERIES_DIR).join(format!("{}.yaml", name)))
+}
+
+/// Saved queries are stored as `queries/<name>.yaml` with `name` and
+/// `query` keys, so they can be edited by hand.
+fn save(vault_path: &Path, name: &str, text: &str) -> Result<()> {
+    // Make sure only queries that parse are saved
+    query::parse(text)?;
+
+    let path = query_file(vault_path, name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create queries directory")?;
+    }
+
+    let mut mapping = Mapping::new();
+    mapping.insert("name".into(), name.trim().into());
+    mapping.insert("query".into(), text.trim().into());
+    let yaml = serde_yaml::to_string(&mapping).context("Failed to serialize query")?;
+
+    fs::write(&path, yaml).context(format!("Failed to write query file: {}", path.display()))
+}
+
+fn load(vault_path: &Path, name: &str) -> Result<String> {
+    let path = query_file(vault_path, name)?;
+    if !path.exists() {
+        return Err(anyhow!(
+            "Saved query '{}' not found. Save one with 'ncy query --save {} <query>'",
+            name,
+            name
+        ));
+    }
+
+    let content = fs::read_to_string(&path)
+        .context(format!("Failed to read query file: {}", path.display()))?;
+    parse_query_file(&content).context(format!("Invalid query file: {}", path.display()))
+}
+
+fn load_all(vault_path: &Path) -> Result<Vec<(String, String)>> {
+    let dir = vault_path.join(QUERIES_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir).context("Failed to read queries directory")? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "yaml")
+            && let Some(stem) = path.file_stem()
+        {
+            names.push(stem.to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let text = load(vault_path, &name)?;
+            Ok((name, text))
+        })
+        .collect()
+}
+
+fn parse_query_file(content: &str) -> Result<String> {
+    let value = serde_yaml::from_str::<YamlValue>(content).context("Failed to parse query YAML")?;
+    value
+        .get("query")
+        .and_then(|query| query.as_str())
+        .map(|query| query.to_string())
+        .context("Missing 'query' key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_file() {
+        let content = "name: active\nquery: from \"projects\" where status = \"active\"\n";
+        assert_eq!(
+            parse_query_file(content).unwrap(),
+            "from \"projects\" where status = \"active\""
+        );
+        assert!(parse_query_file("name: broken\n").is_err());
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("a long cell value", 8), "a long …");
+    }
+}
@@ -0,0 +1,100 @@
+// src/commands/recent.rs
+use crate::commands::encrypt;
+use crate::commands::ls::OutputFormat;
+use crate::crypto;
+use crate::git;
+use crate::history::{self, Action};
+use anyhow::{Context, Result, anyhow};
+use notemancy_core::notes::utils::get_title;
+use serde_json::{Value as JsonValue, json};
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+pub fn execute(count: usize, format: OutputFormat) -> Result<()> {
+    let entries = history::load()?;
+
+    // Notes that were deleted or moved since they were used are skipped
+    let paths: Vec<String> = history::recent_paths(&entries)
+        .into_iter()
+        .filter(|path| Path::new(path).exists())
+        .take(count)
+        .collect();
+
+    match format {
+        OutputFormat::Titles => {
+            for path in &paths {
+                println!("{}", get_title(Path::new(path))?);
+            }
+        }
+        OutputFormat::Paths => {
+            for path in &paths {
+                println!("{}", path);
+            }
+        }
+        OutputFormat::Json => {
+            let mut notes = Vec::new();
+            for path in &paths {
+                let last_used = entries
+                    .iter()
+                    .rev()
+                    .find(|entry| &entry.path == path)
+                    .map(|entry| entry.time.to_rfc3339());
+
+                notes.push(json!({
+                    "title": get_title(Path::new(path))?,
+                    "path": path,
+                    "last_used": last_used,
+                }));
+            }
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&JsonValue::Array(notes))
+                    .context("Failed to serialize recent notes")?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Reopens the most recently used note that still exists.
+pub fn reopen_last(external: bool) -> Result<()> {
+    let entries = history::load()?;
+
+    let note_path = history::recent_paths(&entries)
+        .into_iter()
+        .find(|path| Path::new(path).exists())
+        .context("No recently used notes found")?;
+
+    history::record_or_warn(Path::new(&note_path), Action::Open);
+
+    // In external mode just print the path for the calling editor
+    if external {
+        println!("{}", note_path);
+        return Ok(());
+    }
+
+    let title = get_title(Path::new(&note_path))?;
+    if crypto::is_encrypted_note(Path::new(&note_path)) {
+        println!("Opening note: {}", title);
+        return encrypt::edit(Path::new(&note_path), None);
+    }
+
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+
+    println!("Opening note: {} with {}", title, editor);
+
+    let status = Command::new(&editor)
+        .arg(&note_path)
+        .status()
+        .context(format!("Failed to open editor '{}' for note", editor))?;
+
+    if !status.success() {
+        return Err(anyhow!("Editor exited with non-zero status"));
+    }
+
+    git::auto_commit_or_warn(&[Path::new(&note_path)]);
+    Ok(())
+}
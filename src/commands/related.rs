@@ -0,0 +1,208 @@
+// src/commands/related.rs
+use crate::crypto;
+use crate::git;
+use crate::metadata::{find_note, load_vault, relative_path, split_frontmatter};
+use crate::picker::{PickOptions, Picked, pick_notes, vault_choices};
+use crate::similarity::TfIdf;
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const RELATED_HEADING: &str = "## Related";
+
+pub struct RelatedOptions {
+    pub limit: usize,
+    /// Write the suggestions to the note as a "Related" section
+    pub append: bool,
+    pub external: bool,
+}
+
+/// Lists the notes whose text is most similar to the given note, or to one
+/// picked interactively.
+pub fn execute(note: Option<&str>, vault: Option<&str>, options: &RelatedOptions) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    let note_path = match note {
+        Some(note) => find_note(&vault_path, note)?,
+        None => pick_note(&vault_path, &vault_name, options.external)?,
+    };
+    let note_id = relative_path(&vault_path, &note_path);
+    if options.append && crypto::is_encrypted_note(&note_path) {
+        return Err(anyhow!(
+            "Cannot add a Related section to an encrypted note: {}",
+            note_id
+        ));
+    }
+
+    let mut notes = load_vault(&vault_path)?;
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    let position = notes
+        .iter()
+        .position(|n| relative_path(&vault_path, &n.path) == note_id)
+        .context(format!(
+            "Note is not part of vault '{}': {}",
+            vault_name, note_id
+        ))?;
+
+    let mut documents = Vec::with_capacity(notes.len());
+    for n in &notes {
+        let content = fs::read_to_string(&n.path)
+            .context(format!("Failed to read note: {}", n.path.display()))?;
+        documents.push(format!("{}\n{}", n.title, document_text(&content)));
+    }
+
+    let related: Vec<(&Path, &str, f64)> = TfIdf::new(&documents)
+        .most_similar(position, options.limit)
+        .into_iter()
+        .map(|(i, score)| (notes[i].path.as_path(), notes[i].title.as_str(), score))
+        .collect();
+
+    let title = &notes[position].title;
+    if related.is_empty() {
+        println!("No notes related to '{}'", title);
+        return Ok(());
+    }
+
+    println!("Notes related to '{}':", title);
+    for (path, title, score) in &related {
+        println!(
+            "  {:.2}  {}  {}",
+            score,
+            title,
+            relative_path(&vault_path, path)
+        );
+    }
+
+    if options.append {
+        let links: Vec<String> = related
+            .iter()
+            .map(|(path, title, _)| {
+                let target = relative_path(&vault_path, path);
+                format!(
+                    "[[{}|{}]]",
+                    target.strip_suffix(".md").unwrap_or(&target),
+                    title
+                )
+            })
+            .collect();
+
+        let content = fs::read_to_string(&note_path)
+            .context(format!("Failed to read note: {}", note_path.display()))?;
+        let updated = with_related_section(&content, &links);
+        if updated != content {
+            fs::write(&note_path, updated)
+                .context(format!("Failed to write note: {}", note_path.display()))?;
+            println!("Updated the Related section of {}", note_id);
+            git::auto_commit_or_warn(&[&note_path]);
+        }
+    }
+
+    Ok(())
+}
+
+fn pick_note(vault_path: &Path, vault_name: &str, external: bool) -> Result<PathBuf> {
+    let choices = vault_choices(vault_path)?;
+    if choices.is_empty() {
+        return Err(anyhow!("No markdown notes found in vault: {}", vault_name));
+    }
+
+    let options = PickOptions {
+        external,
+        ..Default::default()
+    };
+    match pick_notes(&choices, options)? {
+        Picked::Notes(notes) => notes
+            .into_iter()
+            .next()
+            .map(|note| PathBuf::from(note.path))
+            .context("No note selected"),
+        Picked::Create(_) => Err(anyhow!("No note selected")),
+    }
+}
+
+/// The body of a note without its frontmatter or a previous Related section,
+/// so earlier suggestions do not make notes look alike.
+pub fn document_text(content: &str) -> String {
+    let body = split_frontmatter(content).1;
+    match related_section(body) {
+        Some(range) => format!("{}{}", &body[..range.start], &body[range.end..]),
+        None => body.to_string(),
+    }
+}
+
+/// Byte range of the "Related" section: from its heading up to the next
+/// heading of the same or a higher level, or the end of the note.
+fn related_section(content: &str) -> Option<Range<usize>> {
+    let mut start = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        match start {
+            None if trimmed.eq_ignore_ascii_case(RELATED_HEADING) => start = Some(offset),
+            Some(start) if trimmed.starts_with("# ") || trimmed.starts_with("## ") => {
+                return Some(start..offset);
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+
+    start.map(|start| start..content.len())
+}
+
+/// Replaces the note's "Related" section with a list of links, or adds one
+/// at the end of the note.
+fn with_related_section(content: &str, links: &[String]) -> String {
+    let mut section = format!("{}\n\n", RELATED_HEADING);
+    for link in links {
+        section.push_str(&format!("- {}\n", link));
+    }
+
+    match related_section(content) {
+        Some(range) if range.end < content.len() => format!(
+            "{}{}\n{}",
+            &content[..range.start],
+            section,
+            &content[range.end..]
+        ),
+        Some(range) => format!("{}{}", &content[..range.start], section),
+        None if content.trim().is_empty() => section,
+        None => format!("{}\n\n{}", content.trim_end(), section),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_related_section() {
+        let links = vec!["[[a|A]]".to_string(), "[[x/b|B]]".to_string()];
+
+        assert_eq!(
+            with_related_section("# Note\n\nText\n", &links),
+            "# Note\n\nText\n\n## Related\n\n- [[a|A]]\n- [[x/b|B]]\n"
+        );
+        assert_eq!(
+            with_related_section(
+                "Text\n\n## Related\n\n- [[old]]\n\n## Later\nMore\n",
+                &links
+            ),
+            "Text\n\n## Related\n\n- [[a|A]]\n- [[x/b|B]]\n\n## Later\nMore\n"
+        );
+
+        let once = with_related_section("Text\n", &links);
+        assert_eq!(with_related_section(&once, &links), once);
+    }
+
+    #[test]
+    fn test_document_text_skips_related_section() {
+        let content =
+            "---\ntitle: T\n---\nBody\n## Related\n\n- [[other]]\n### Sub\n## Next\nEnd\n";
+        assert_eq!(document_text(content), "Body\n## Next\nEnd\n");
+    }
+}
@@ -0,0 +1,502 @@
+// src/commands/serve.rs
+use crate::commands::graph::{self, Graph, Node, NoteLink};
+use crate::commands::jrnl;
+use crate::commands::ls::NoteFilter;
+use crate::commands::new;
+use crate::commands::related::document_text;
+use crate::git;
+use crate::index::NoteIndex;
+use crate::links::{LinkIndex, resolve_relative};
+use crate::metadata::{NoteMeta, find_note, relative_path, scan_vault, split_frontmatter};
+use crate::rpc::{Connection, METHOD_NOT_FOUND, PARSE_ERROR, Request, RpcError};
+use crate::similarity::TfIdf;
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::{Value as JsonValue, json};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+// Vault directories and the flags that mark them as changed on disk
+type WatchedDirs = Arc<Mutex<Vec<(PathBuf, Arc<AtomicBool>)>>>;
+
+/// A note held in memory with its content.
+pub struct LoadedNote {
+    pub meta: NoteMeta,
+    /// Path relative to the vault
+    pub id: String,
+    pub content: String,
+}
+
+impl LoadedNote {
+    /// The content after the frontmatter.
+    pub fn body(&self) -> &str {
+        split_frontmatter(&self.content).1
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut json = self.meta.to_json();
+        json["id"] = json!(self.id);
+        json
+    }
+}
+
+/// The notes of a vault and what is derived from them. The search index and
+/// link graph are only built when a method needs them.
+pub struct Loaded {
+    pub notes: Vec<LoadedNote>,
+    pub links: LinkIndex,
+    /// Links of each note from the index, when `ncy watch` keeps it current
+    indexed_links: Option<BTreeMap<PathBuf, Vec<NoteLink>>>,
+    search: Option<TfIdf>,
+    graph: Option<Graph>,
+}
+
+impl Loaded {
+    pub fn note(&self, id: &str) -> Option<&LoadedNote> {
+        self.notes.iter().find(|note| note.id == id)
+    }
+
+    pub fn search(&mut self) -> &TfIdf {
+        let notes = &self.notes;
+        self.search.get_or_insert_with(|| {
+            let documents: Vec<String> = notes
+                .iter()
+                .map(|note| format!("{}\n{}", note.meta.title, document_text(note.body())))
+                .collect();
+            TfIdf::new(&documents)
+        })
+    }
+
+    pub fn graph(&mut self) -> &Graph {
+        let notes = &self.notes;
+        let indexed_links = &self.indexed_links;
+        self.graph.get_or_insert_with(|| {
+            let sources: Vec<(Node, Vec<NoteLink>)> = notes
+                .iter()
+                .map(|note| {
+                    let node = Node {
+                        id: note.id.clone(),
+                        title: note.meta.title.clone(),
+                        project: note.meta.project.clone(),
+                        tags: note.meta.tags.clone(),
+                    };
+                    let links = indexed_links
+                        .as_ref()
+                        .and_then(|links| links.get(&note.meta.path).cloned())
+                        .unwrap_or_else(|| graph::note_links(note.body()));
+                    (node, links)
+                })
+                .collect();
+            graph::build(&sources)
+        })
+    }
+}
+
+pub struct VaultState {
+    pub name: String,
+    pub path: PathBuf,
+    /// Set when files in the vault change, so the notes are loaded again
+    changed: Arc<AtomicBool>,
+    /// Whether the watcher covers this vault. If not, every request reloads
+    watched: bool,
+    loaded: Option<Loaded>,
+}
+
+impl VaultState {
+    /// The notes of the vault, loaded again if files changed since the last call.
+    pub fn loaded(&mut self) -> Result<&mut Loaded> {
+        let changed = self.changed.swap(false, Ordering::SeqCst);
+        if changed || !self.watched || self.loaded.is_none() {
+            self.loaded = Some(self.load()?);
+        }
+        Ok(self.loaded.as_mut().expect("notes were just loaded"))
+    }
+
+    /// Makes the next call to `loaded` read the vault again.
+    pub fn mark_changed(&self) {
+        self.changed.store(true, Ordering::SeqCst);
+    }
+
+    fn load(&self) -> Result<Loaded> {
+        let (mut metas, indexed_links) = match NoteIndex::load_live(&self.path) {
+            Some(index) => {
+                let links = index
+                    .notes()
+                    .map(|note| (note.meta.path.clone(), note.links.clone()))
+                    .collect();
+                (index.into_notes(), Some(links))
+            }
+            None => (scan_vault(&self.path)?, None),
+        };
+        metas.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut notes = Vec::with_capacity(metas.len());
+        for meta in metas {
+            let content = fs::read_to_string(&meta.path)
+                .context(format!("Failed to read note: {}", meta.path.display()))?;
+            notes.push(LoadedNote {
+                id: relative_path(&self.path, &meta.path),
+                content,
+                meta,
+            });
+        }
+
+        let links = LinkIndex::new(
+            notes
+                .iter()
+                .map(|note| (note.id.as_str(), note.meta.title.as_str())),
+        );
+        Ok(Loaded {
+            notes,
+            links,
+            indexed_links,
+            search: None,
+            graph: None,
+        })
+    }
+}
+
+/// The vaults a long-running command works with, kept in memory and
+/// reloaded when their files change.
+pub struct VaultCache {
+    config: JsonValue,
+    vaults: BTreeMap<String, VaultState>,
+    watcher: Option<RecommendedWatcher>,
+    watched_dirs: WatchedDirs,
+}
+
+impl VaultCache {
+    pub fn new(config: JsonValue) -> Self {
+        let watched_dirs: WatchedDirs = Arc::default();
+        let dirs = Arc::clone(&watched_dirs);
+        let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            let Ok(dirs) = dirs.lock() else {
+                return;
+            };
+            for (dir, changed) in dirs.iter() {
+                if event.paths.iter().any(|path| path.starts_with(dir)) {
+                    changed.store(true, Ordering::SeqCst);
+                }
+            }
+        });
+
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!(
+                    "Warning: not watching for changes, notes are reloaded on every request: {}",
+                    e
+                );
+                None
+            }
+        };
+
+        VaultCache {
+            config,
+            vaults: BTreeMap::new(),
+            watcher,
+            watched_dirs,
+        }
+    }
+
+    pub fn config(&self) -> &JsonValue {
+        &self.config
+    }
+
+    /// The state of a vault, or of the default vault, set up the first time
+    /// it is asked for.
+    pub fn vault(&mut self, name: Option<&str>) -> Result<&mut VaultState> {
+        let (name, path) = resolve_vault(&self.config, name)?;
+        if !self.vaults.contains_key(&name) {
+            let changed = Arc::new(AtomicBool::new(false));
+            let watched = match self.watcher.as_mut() {
+                Some(watcher) => match watcher.watch(&path, RecursiveMode::Recursive) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("Warning: failed to watch vault '{}': {}", name, e);
+                        false
+                    }
+                },
+                None => false,
+            };
+            if watched && let Ok(mut dirs) = self.watched_dirs.lock() {
+                dirs.push((path.clone(), Arc::clone(&changed)));
+            }
+
+            let state = VaultState {
+                name: name.clone(),
+                path,
+                changed,
+                watched,
+                loaded: None,
+            };
+            self.vaults.insert(name.clone(), state);
+        }
+        Ok(self.vaults.get_mut(&name).expect("vault was just added"))
+    }
+}
+
+struct Server {
+    cache: VaultCache,
+}
+
+impl Server {
+    fn vault(&mut self, request: &Request) -> Result<&mut VaultState, RpcError> {
+        Ok(self.cache.vault(request.optional_string("vault"))?)
+    }
+
+    fn handle(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        match request.method.as_str() {
+            "listNotes" => self.list_notes(request),
+            "createNote" => self.create_note(request),
+            "appendJournal" => self.append_journal(request),
+            "resolveLink" => self.resolve_link(request),
+            "search" => self.search(request),
+            "backlinks" => self.backlinks(request),
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        }
+    }
+
+    fn list_notes(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        let filter = NoteFilter {
+            project: request.optional_string("project").map(|p| p.to_string()),
+            tags: string_list(request, "tags")?,
+            ..NoteFilter::default()
+        };
+
+        let loaded = self.vault(request)?.loaded()?;
+        let notes: Vec<JsonValue> = loaded
+            .notes
+            .iter()
+            .filter(|note| filter.matches(&note.meta))
+            .map(LoadedNote::to_json)
+            .collect();
+        Ok(json!(notes))
+    }
+
+    fn create_note(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        let title = request.string("title")?.trim().to_string();
+        if title.is_empty() {
+            return Err(RpcError::invalid_params("The title must not be empty"));
+        }
+        let project = request.optional_string("project").unwrap_or("");
+
+        let config = self.cache.config().clone();
+        let vault = self.vault(request)?;
+        let note_path =
+            new::create_with_schemas(&config, &vault.name, &vault.path, &title, project, false)?;
+        vault.mark_changed();
+        git::auto_commit_or_warn(&[&note_path]);
+
+        Ok(json!({
+            "path": note_path.to_string_lossy(),
+            "id": relative_path(&vault.path, &note_path),
+            "title": title,
+        }))
+    }
+
+    fn append_journal(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        let text = request.string("text")?;
+        let vault = self.vault(request)?;
+        let (note_path, created) = jrnl::append_today(&vault.path, text)?;
+        vault.mark_changed();
+        git::auto_commit_or_warn(&[&note_path]);
+
+        Ok(json!({
+            "path": note_path.to_string_lossy(),
+            "id": relative_path(&vault.path, &note_path),
+            "created": created,
+        }))
+    }
+
+    // Markdown links are resolved relative to `from`, when given, before
+    // trying the target as a wikilink
+    fn resolve_link(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        let target = link_target(request.string("target")?);
+        let vault = self.vault(request)?;
+        let from = match request.optional_string("from") {
+            Some(from) => Some(relative_path(&vault.path, &find_note(&vault.path, from)?)),
+            None => None,
+        };
+
+        let loaded = vault.loaded()?;
+        let relative = from.map(|from| resolve_relative(&from, target));
+        let id = relative
+            .as_deref()
+            .and_then(|path| loaded.links.resolve(path))
+            .or_else(|| loaded.links.resolve(target));
+
+        Ok(id
+            .and_then(|id| loaded.note(id))
+            .map(LoadedNote::to_json)
+            .unwrap_or(JsonValue::Null))
+    }
+
+    fn search(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        let query = request.string("query")?;
+        let limit = match request.params.get("limit") {
+            None | Some(JsonValue::Null) => DEFAULT_SEARCH_LIMIT,
+            Some(limit) => limit
+                .as_u64()
+                .ok_or_else(|| RpcError::invalid_params("'limit' must be a positive number"))?
+                as usize,
+        };
+
+        let loaded = self.vault(request)?.loaded()?;
+        let results = loaded.search().search(query, limit);
+        let notes: Vec<JsonValue> = results
+            .into_iter()
+            .map(|(i, score)| {
+                let mut json = loaded.notes[i].to_json();
+                json["score"] = json!(score);
+                json
+            })
+            .collect();
+        Ok(json!(notes))
+    }
+
+    fn backlinks(&mut self, request: &Request) -> Result<JsonValue, RpcError> {
+        let note = request.string("note")?;
+        let vault = self.vault(request)?;
+        let id = relative_path(&vault.path, &find_note(&vault.path, note)?);
+
+        let loaded = vault.loaded()?;
+        let edges: Vec<(String, &'static str, usize)> = loaded
+            .graph()
+            .edges
+            .iter()
+            .filter(|edge| edge.target == id)
+            .map(|edge| (edge.source.clone(), edge.kind.name(), edge.count))
+            .collect();
+
+        let notes: Vec<JsonValue> = edges
+            .into_iter()
+            .filter_map(|(source, kind, count)| {
+                let mut json = loaded.note(&source)?.to_json();
+                json["kind"] = json!(kind);
+                json["count"] = json!(count);
+                Some(json)
+            })
+            .collect();
+        Ok(json!(notes))
+    }
+}
+
+// A list of strings, given as an array or a single string
+fn string_list(request: &Request, name: &str) -> Result<Vec<String>, RpcError> {
+    match request.params.get(name) {
+        None | Some(JsonValue::Null) => Ok(Vec::new()),
+        Some(JsonValue::String(value)) => Ok(vec![value.clone()]),
+        Some(JsonValue::Array(values)) => values
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| RpcError::invalid_params(format!("'{}' must be strings", name))),
+        Some(_) => Err(RpcError::invalid_params(format!(
+            "'{}' must be a list of strings",
+            name
+        ))),
+    }
+}
+
+// The note part of a link target: without [[ ]], a label or a heading
+fn link_target(target: &str) -> &str {
+    let target = target.trim();
+    let target = target
+        .strip_prefix("![[")
+        .or_else(|| target.strip_prefix("[["))
+        .unwrap_or(target);
+    let target = target.strip_suffix("]]").unwrap_or(target);
+    let target = target.split('|').next().unwrap_or(target);
+    target.split('#').next().unwrap_or(target).trim()
+}
+
+/// Answers JSON-RPC requests on stdin until it is closed, keeping the notes
+/// of each vault in memory and reloading them when files change.
+pub fn execute() -> Result<()> {
+    let mut server = Server {
+        cache: VaultCache::new(read_config()?),
+    };
+    let stdin = io::stdin();
+    let mut connection = Connection::new(stdin.lock(), io::stdout());
+
+    loop {
+        let message = match connection.read_message() {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                connection.respond(
+                    JsonValue::Null,
+                    Err(RpcError::new(PARSE_ERROR, format!("{:#}", e))),
+                )?;
+                continue;
+            }
+        };
+
+        let request = match Request::parse(&message) {
+            Ok(request) => request,
+            Err(error) => {
+                let id = message.get("id").cloned().unwrap_or(JsonValue::Null);
+                connection.respond(id, Err(error))?;
+                continue;
+            }
+        };
+
+        let result = server.handle(&request);
+        match request.id {
+            Some(id) => connection.respond(id, result)?,
+            None => {
+                if let Err(error) = result {
+                    eprintln!("Warning: {} failed: {}", request.method, error.message);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_target() {
+        assert_eq!(link_target("[[projects/plan|The plan]]"), "projects/plan");
+        assert_eq!(link_target("![[diagram#Overview]]"), "diagram");
+        assert_eq!(link_target(" ../notes/b.md "), "../notes/b.md");
+    }
+
+    #[test]
+    fn test_string_list() {
+        let request = |params: JsonValue| Request {
+            id: None,
+            method: "listNotes".to_string(),
+            params,
+        };
+        assert_eq!(
+            string_list(&request(json!({"tags": ["a", "b"]})), "tags").unwrap(),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            string_list(&request(json!({"tags": "a"})), "tags").unwrap(),
+            vec!["a"]
+        );
+        assert!(string_list(&request(json!({})), "tags").unwrap().is_empty());
+        assert!(string_list(&request(json!({"tags": 3})), "tags").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+// src/commands/shell_init.rs
+use anyhow::{Result, anyhow};
+
+pub fn execute(shell: &str, function_name: &str) -> Result<()> {
+    let script = shell_function(shell, function_name)?;
+    print!("{}", script);
+    Ok(())
+}
+
+/// Builds a shell function that picks a note with `ncy dir -e` and changes
+/// the current shell into that note's directory.
+fn shell_function(shell: &str, function_name: &str) -> Result<String> {
+    // The name goes into a script that is eval'd, so only plain names pass
+    let mut chars = function_name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(anyhow!(
+            "Invalid function name '{}': use letters, digits, '_' and '-', starting with a letter or '_'",
+            function_name
+        ));
+    }
+
+    match shell {
+        "bash" | "zsh" | "sh" => Ok(format!(
+            "{name}() {{\n    local dir\n    dir=\"$(ncy dir -e)\" && [ -n \"$dir\" ] && cd \"$dir\"\n}}\n",
+            name = function_name
+        )),
+        "fish" => Ok(format!(
+            "function {name}\n    set -l dir (ncy dir -e)\n    and test -n \"$dir\"\n    and cd \"$dir\"\nend\n",
+            name = function_name
+        )),
+        _ => Err(anyhow!(
+            "Unsupported shell '{}'. Supported shells are: bash, zsh, sh, fish",
+            shell
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_function_for_posix_shells() {
+        let script = shell_function("bash", "ncd").unwrap();
+        assert!(script.starts_with("ncd() {"));
+        assert!(script.contains("dir=\"$(ncy dir -e)\""));
+        assert_eq!(script, shell_function("zsh", "ncd").unwrap());
+    }
+
+    #[test]
+    fn test_shell_function_for_fish() {
+        let script = shell_function("fish", "ncd").unwrap();
+        assert!(script.starts_with("function ncd\n"));
+        assert!(script.ends_with("end\n"));
+    }
+
+    #[test]
+    fn test_shell_function_with_unknown_shell() {
+        assert!(shell_function("powershell", "ncd").is_err());
+    }
+
+    #[test]
+    fn test_shell_function_with_invalid_name() {
+        assert!(shell_function("bash", "n cd").is_err());
+        assert!(shell_function("bash", "").is_err());
+        assert!(shell_function("bash", "x;rm").is_err());
+        assert!(shell_function("fish", "$(id)").is_err());
+        assert!(shell_function("bash", "-ncd").is_err());
+        assert!(shell_function("bash", "_n-cd2").is_ok());
+    }
+}
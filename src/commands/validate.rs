@@ -0,0 +1,91 @@
+// src/commands/validate.rs
+use crate::commands::ls::{normalize_project, project_matches};
+use crate::metadata::{load_vault, parse_frontmatter, relative_path};
+use crate::schema::{SCHEMA_FILE, load_schemas, schemas_for};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value as JsonValue, json};
+use std::fs;
+
+/// Checks every note that has a schema and reports each field that does
+/// not follow it. Fails when there are violations, so it can run in hooks.
+pub fn execute(vault: Option<&str>, project: Option<&str>, json: bool) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    let schemas = load_schemas(&config, &vault_name, &vault_path)?;
+    if schemas.is_empty() {
+        return Err(anyhow!(
+            "No schemas defined for vault '{}'. Add them to {} or to the vault in config.yaml",
+            vault_name,
+            vault_path.join(SCHEMA_FILE).display()
+        ));
+    }
+
+    let mut notes = load_vault(&vault_path)?;
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut checked = 0;
+    let mut violations = Vec::new();
+    for note in &notes {
+        if let Some(project) = project
+            && !project_matches(&note.project, &normalize_project(project))
+        {
+            continue;
+        }
+        let applicable = schemas_for(&schemas, &note.project);
+        if applicable.is_empty() {
+            continue;
+        }
+        checked += 1;
+
+        let path = relative_path(&vault_path, &note.path);
+        // Notes with broken frontmatter are loaded without any metadata
+        let content = fs::read_to_string(&note.path)
+            .context(format!("Failed to read note: {}", note.path.display()))?;
+        if let Err(e) = parse_frontmatter(&content) {
+            violations.push((path, "frontmatter".to_string(), e.to_string()));
+            continue;
+        }
+
+        for schema in applicable {
+            for violation in schema.check(&note.frontmatter) {
+                violations.push((path.clone(), violation.field, violation.message));
+            }
+        }
+    }
+
+    if json {
+        let array: Vec<JsonValue> = violations
+            .iter()
+            .map(|(path, field, message)| json!({"path": path, "field": field, "message": message}))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&array).context("Failed to serialize violations")?
+        );
+    } else {
+        for (path, field, message) in &violations {
+            println!("{}: {}: {}", path, field, message);
+        }
+    }
+
+    if violations.is_empty() {
+        if !json {
+            println!("All {} notes with a schema are valid", checked);
+        }
+        return Ok(());
+    }
+
+    let invalid_notes = {
+        let mut paths: Vec<&String> = violations.iter().map(|(path, _, _)| path).collect();
+        paths.dedup();
+        paths.len()
+    };
+    Err(anyhow!(
+        "{} violation(s) in {} of {} notes",
+        violations.len(),
+        invalid_notes,
+        checked
+    ))
+}
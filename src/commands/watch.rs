@@ -0,0 +1,212 @@
+// src/commands/watch.rs
+use crate::index::{Change, NoteIndex, WatchGuard, claim_watch};
+use crate::metadata::relative_path;
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use chrono::Local;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::time::Duration;
+
+// How long to wait for more events before updating the index, so an editor
+// saving through a temporary file causes a single update
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+struct WatchedVault {
+    name: String,
+    path: PathBuf,
+    index: NoteIndex,
+    _guard: WatchGuard,
+}
+
+/// Shell commands to run when notes change, from `hooks` in config.yaml.
+/// They run with `NCY_EVENT`, `NCY_NOTE` and `NCY_VAULT` set.
+#[derive(Debug, Default, PartialEq)]
+pub struct Hooks {
+    created: Option<String>,
+    changed: Option<String>,
+    deleted: Option<String>,
+}
+
+impl Hooks {
+    pub fn from_config(config: &JsonValue) -> Result<Self> {
+        let Some(hooks) = config.get("hooks").filter(|h| !h.is_null()) else {
+            return Ok(Hooks::default());
+        };
+        let hooks = hooks
+            .as_object()
+            .context("'hooks' in config.yaml must map events to commands")?;
+
+        let mut result = Hooks::default();
+        for (event, command) in hooks {
+            let command = command
+                .as_str()
+                .map(|c| c.to_string())
+                .context(format!("The '{}' hook must be a shell command", event))?;
+            match event.as_str() {
+                "created" => result.created = Some(command),
+                "changed" => result.changed = Some(command),
+                "deleted" => result.deleted = Some(command),
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown hook '{}'. Use created, changed or deleted",
+                        event
+                    ));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn command(&self, change: Change) -> Option<&str> {
+        match change {
+            Change::Created => self.created.as_deref(),
+            Change::Changed => self.changed.as_deref(),
+            Change::Deleted => self.deleted.as_deref(),
+        }
+    }
+
+    fn run(&self, change: Change, note_path: &Path, vault: &WatchedVault) {
+        let Some(command) = self.command(change) else {
+            return;
+        };
+
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(&vault.path)
+            .env("NCY_EVENT", change.name())
+            .env("NCY_NOTE", note_path)
+            .env("NCY_VAULT", &vault.name)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => eprintln!("Warning: {} hook exited with {}", change.name(), status),
+            Err(e) => eprintln!("Warning: failed to run {} hook: {}", change.name(), e),
+        }
+    }
+}
+
+/// Watches vaults for changes and keeps their note index current until the
+/// process is stopped. Without vault names, every configured vault is
+/// watched.
+pub fn execute(vault_names: &[&str], run_hooks: bool) -> Result<()> {
+    let config = read_config()?;
+    let hooks = if run_hooks {
+        Hooks::from_config(&config)?
+    } else {
+        Hooks::default()
+    };
+
+    let names: Vec<String> = if vault_names.is_empty() {
+        config
+            .get("vaults")
+            .and_then(|v| v.as_array())
+            .context("No vaults defined in configuration")?
+            .iter()
+            .filter_map(|vault| vault.get("name").and_then(|n| n.as_str()))
+            .map(|name| name.to_string())
+            .collect()
+    } else {
+        vault_names.iter().map(|name| name.to_string()).collect()
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(sender).context("Failed to start watching files")?;
+
+    let mut vaults = Vec::new();
+    for name in names {
+        let (name, path) = resolve_vault(&config, Some(&name))?;
+        let guard = claim_watch(&path)?;
+        // Watch before indexing so no change falls between the two
+        watcher
+            .watch(&path, RecursiveMode::Recursive)
+            .context(format!(
+                "Failed to watch vault directory: {}",
+                path.display()
+            ))?;
+        let index = NoteIndex::build(&path)?;
+        index.save()?;
+
+        println!(
+            "Watching vault '{}' ({} notes)",
+            name,
+            index.notes().count()
+        );
+        vaults.push(WatchedVault {
+            _guard: guard,
+            name,
+            path,
+            index,
+        });
+    }
+    if vaults.is_empty() {
+        return Err(anyhow!("No vaults to watch"));
+    }
+
+    loop {
+        let mut paths = BTreeSet::new();
+        let first = receiver.recv().context("File watcher stopped")?;
+        collect_paths(first, &mut paths);
+        while let Ok(event) = receiver.recv_timeout(SETTLE_TIME) {
+            collect_paths(event, &mut paths);
+        }
+
+        for vault in &mut vaults {
+            let mut changes = Vec::new();
+            for path in paths.iter().filter(|path| path.starts_with(&vault.path)) {
+                match vault.index.refresh(path) {
+                    Ok(refreshed) => changes.extend(refreshed),
+                    Err(e) => eprintln!("Warning: failed to index {}: {}", path.display(), e),
+                }
+            }
+            if changes.is_empty() {
+                continue;
+            }
+
+            if let Err(e) = vault.index.save() {
+                eprintln!("Warning: {}", e);
+            }
+            for (change, note_path) in &changes {
+                println!(
+                    "{} {} {}:{}",
+                    Local::now().format("%H:%M:%S"),
+                    change.name(),
+                    vault.name,
+                    relative_path(&vault.path, note_path)
+                );
+                hooks.run(*change, note_path, vault);
+            }
+        }
+    }
+}
+
+fn collect_paths(event: notify::Result<Event>, paths: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+        Ok(event) => paths.extend(event.paths),
+        Err(e) => eprintln!("Warning: file watcher error: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_hooks_from_config() {
+        let hooks = Hooks::from_config(&json!({"hooks": {"changed": "ncy validate"}})).unwrap();
+        assert_eq!(hooks.command(Change::Changed), Some("ncy validate"));
+        assert_eq!(hooks.command(Change::Deleted), None);
+
+        assert_eq!(Hooks::from_config(&json!({})).unwrap(), Hooks::default());
+        assert!(Hooks::from_config(&json!({"hooks": {"renamed": "x"}})).is_err());
+        assert!(Hooks::from_config(&json!({"hooks": {"created": 3}})).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+// src/commands/workspace.rs
+use crate::commands::batch::{open_in_editor, print_paths};
+use crate::commands::ls::{NoteFilter, parse_field_filter};
+use crate::metadata::{load_vault, parse_date, relative_path, resolve_note_path};
+use crate::picker::{NoteChoice, PickOptions, Picked, pick_notes, vault_choices};
+use crate::utils::{read_config, resolve_vault};
+use anyhow::{Context, Result, anyhow};
+use notemancy_core::notes::utils::get_title;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Folder inside every vault that holds the workspace files (created by `ncy init`)
+const WORKSPACES_DIR: &str = "workspaces";
+
+/// A named set of notes, stored as `workspaces/<name>.yaml` in the vault.
+///
+/// Members are kept as paths relative to the vault. The optional saved query
+/// adds every note matching it at the time the workspace is opened.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pub name: String,
+    pub notes: Vec<String>,
+    pub query: Option<NoteFilter>,
+}
+
+/// Creates an empty workspace. A non-empty filter is saved as its query.
+pub fn new(name: &str, vault: Option<&str>, filter: NoteFilter) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+
+    let path = workspace_file(&vault_path, name)?;
+    if path.exists() {
+        return Err(anyhow!("Workspace '{}' already exists", name));
+    }
+
+    let workspace = Workspace {
+        name: name.to_string(),
+        notes: Vec::new(),
+        query: Some(filter).filter(|f| !filter_to_yaml(f).is_empty()),
+    };
+    save(&vault_path, &workspace)?;
+
+    println!("Created workspace '{}'", name);
+    Ok(())
+}
+
+/// Adds notes to a workspace. Without note arguments the notes are picked
+/// interactively (Tab marks several).
+pub fn add(name: &str, notes: &[&str], vault: Option<&str>, use_external: bool) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+    let mut workspace = load(&vault_path, name)?;
+
+    let paths = if notes.is_empty() {
+        let options = PickOptions {
+            external: use_external,
+            multi: true,
+            allow_create: false,
+        };
+        match pick_notes(&vault_choices(&vault_path)?, options)? {
+            Picked::Notes(picked) => picked
+                .iter()
+                .map(|note| relative_path(&vault_path, Path::new(&note.path)))
+                .collect(),
+            Picked::Create(_) => return Err(anyhow!("No note selected")),
+        }
+    } else {
+        notes
+            .iter()
+            .map(|note| resolve_note_path(&vault_path, note))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    for path in paths {
+        if workspace.notes.contains(&path) {
+            println!("Already in '{}': {}", name, path);
+            continue;
+        }
+        println!("Added to '{}': {}", name, path);
+        workspace.notes.push(path);
+    }
+
+    save(&vault_path, &workspace)
+}
+
+/// Removes notes from a workspace, or deletes the whole workspace when no
+/// notes are given.
+pub fn rm(name: &str, notes: &[&str], vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+    let mut workspace = load(&vault_path, name)?;
+
+    if notes.is_empty() {
+        let path = workspace_file(&vault_path, name)?;
+        fs::remove_file(&path).context(format!(
+            "Failed to delete workspace file: {}",
+            path.display()
+        ))?;
+        println!("Deleted workspace '{}'", name);
+        return Ok(());
+    }
+
+    for note in notes {
+        // Members may point to notes that no longer exist, so match the stored
+        // path first and only then try to resolve the argument
+        let path = if workspace.notes.iter().any(|n| n == note) {
+            note.to_string()
+        } else {
+            resolve_note_path(&vault_path, note)?
+        };
+
+        let before = workspace.notes.len();
+        workspace.notes.retain(|n| *n != path);
+        if workspace.notes.len() == before {
+            return Err(anyhow!("'{}' is not in workspace '{}'", note, name));
+        }
+        println!("Removed from '{}': {}", name, path);
+    }
+
+    save(&vault_path, &workspace)
+}
+
+pub fn list(vault: Option<&str>) -> Result<()> {
+    let config = read_config()?;
+    let (vault_name, vault_path) = resolve_vault(&config, vault)?;
+
+    let workspaces = load_all(&vault_path)?;
+    if workspaces.is_empty() {
+        println!("No workspaces in vault: {}", vault_name);
+        return Ok(());
+    }
+
+    for workspace in workspaces {
+        let count = workspace.notes.len();
+        let mut line = format!(
+            "{} ({} note{})",
+            workspace.name,
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+        if let Some(query) = &workspace.query {
+            line.push_str(&format!(" + query: {}", describe_filter(query)));
+        }
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+/// Opens every note of a workspace in the editor, or lets the user pick among
+/// them first. In external mode the paths are printed instead.
+pub fn open(name: &str, vault: Option<&str>, pick: bool, use_external: bool) -> Result<()> {
+    let config = read_config()?;
+    let (_, vault_path) = resolve_vault(&config, vault)?;
+    let workspace = load(&vault_path, name)?;
+
+    let mut notes = member_choices(&vault_path, &workspace)?;
+    if notes.is_empty() {
+        return Err(anyhow!("Workspace '{}' has no notes", name));
+    }
+
+    if pick {
+        let options = PickOptions {
+            external: use_external,
+            multi: true,
+            allow_create: false,
+        };
+        notes = match pick_notes(&notes, options)? {
+            Picked::Notes(picked) => picked,
+            Picked::Create(_) => return Err(anyhow!("No note selected")),
+        };
+    }
+
+    if use_external {
+        print_paths(&notes);
+        return Ok(());
+    }
+
+    open_in_editor(&notes)
+}
+
+/// Resolves the notes of a workspace: explicit members first, in the order
+/// they were added, then the notes matching the saved query.
+fn member_choices(vault_path: &Path, workspace: &Workspace) -> Result<Vec<NoteChoice>> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    for note in &workspace.notes {
+        let path = vault_path.join(note);
+        if !path.is_file() {
+            eprintln!(
+                "Warning: '{}' in workspace '{}' no longer exists",
+                note, workspace.name
+            );
+            continue;
+        }
+        paths.push(path);
+    }
+
+    if let Some(query) = &workspace.query {
+        for note in load_vault(vault_path)? {
+            let relative = relative_path(vault_path, &note.path);
+            if query.matches(&note) && !workspace.notes.contains(&relative) {
+                paths.push(note.path);
+            }
+        }
+    }
+
+    paths
+        .into_iter()
+        .map(|path| {
+            Ok(NoteChoice {
+                title: get_title(&path)?,
+                path: path.to_string_lossy().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn workspace_file(vault_path: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') || name.contains('\\') || name.starts_with('.') {
+        return Err(anyhow!("Invalid workspace name '{}'", name));
+    }
+
+    Ok(vault_path
+        .join(WORKSPACES_DIR)
+        .join(format!("{}.yaml", name)))
+}
+
+pub fn load(vault_path: &Path, name: &str) -> Result<Workspace> {
+    let path = workspace_file(vault_path, name)?;
+    if !path.exists() {
+        return Err(anyhow!(
+            "Workspace '{}' not found. Create it with 'ncy ws new {}'",
+            name,
+            name
+        ));
+    }
+
+    let content = fs::read_to_string(&path)
+        .context(format!("Failed to read workspace file: {}", path.display()))?;
+    parse_workspace(name, &content).context(format!("Invalid workspace file: {}", path.display()))
+}
+
+pub fn load_all(vault_path: &Path) -> Result<Vec<Workspace>> {
+    let dir = vault_path.join(WORKSPACES_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir).context("Failed to read workspaces directory")? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "yaml")
+            && let Some(stem) = path.file_stem()
+        {
+            names.push(stem.to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+
+    names.iter().map(|name| load(vault_path, name)).collect()
+}
+
+fn save(vault_path: &Path, workspace: &Workspace) -> Result<()> {
+    let path = workspace_file(vault_path, &workspace.name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create workspaces directory")?;
+    }
+
+    fs::write(&path, serialize_workspace(workspace)?).context(format!(
+        "Failed to write workspace file: {}",
+        path.display()
+    ))
+}
+
+fn parse_workspace(name: &str, content: &str) -> Result<Workspace> {
+    let mapping = match serde_yaml::from_str::<YamlValue>(content)
+        .context("Failed to parse workspace YAML")?
+    {
+        YamlValue::Mapping(mapping) => mapping,
+        YamlValue::Null => Mapping::new(),
+        _ => return Err(anyhow!("Expected a mapping")),
+    };
+
+    let notes = match mapping.get(&YamlValue::from("notes")) {
+        Some(YamlValue::Sequence(notes)) => notes
+            .iter()
+            .filter_map(|note| note.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let query = match mapping.get(&YamlValue::from("query")) {
+        Some(YamlValue::Mapping(query)) => Some(filter_from_yaml(query)?),
+        _ => None,
+    };
+
+    Ok(Workspace {
+        name: name.to_string(),
+        notes,
+        query,
+    })
+}
+
+fn serialize_workspace(workspace: &Workspace) -> Result<String> {
+    let mut mapping = Mapping::new();
+    mapping.insert("name".into(), workspace.name.as_str().into());
+    if let Some(query) = &workspace.query {
+        mapping.insert("query".into(), YamlValue::Mapping(filter_to_yaml(query)));
+    }
+    mapping.insert(
+        "notes".into(),
+        YamlValue::Sequence(
+            workspace
+                .notes
+                .iter()
+                .map(|note| note.as_str().into())
+                .collect(),
+        ),
+    );
+
+    serde_yaml::to_string(&mapping).context("Failed to serialize workspace")
+}
+
+// The saved query uses the same keys as the filter options on the command line
+const DATE_KEYS: [&str; 4] = [
+    "created-after",
+    "created-before",
+    "modified-after",
+    "modified-before",
+];
+
+fn filter_to_yaml(filter: &NoteFilter) -> Mapping {
+    let mut mapping = Mapping::new();
+
+    if let Some(project) = &filter.project {
+        mapping.insert("project".into(), project.as_str().into());
+    }
+    if !filter.tags.is_empty() {
+        let tags = filter.tags.iter().map(|t| t.as_str().into()).collect();
+        mapping.insert("tags".into(), YamlValue::Sequence(tags));
+    }
+    if !filter.fields.is_empty() {
+        let fields = filter
+            .fields
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("{}={}", key, value).into(),
+                None => key.as_str().into(),
+            })
+            .collect();
+        mapping.insert("fields".into(), YamlValue::Sequence(fields));
+    }
+
+    let dates = [
+        filter.created_after,
+        filter.created_before,
+        filter.modified_after,
+        filter.modified_before,
+    ];
+    for (key, date) in DATE_KEYS.iter().zip(dates) {
+        if let Some(date) = date {
+            mapping.insert((*key).into(), date.format("%Y-%m-%d").to_string().into());
+        }
+    }
+
+    mapping
+}
+
+fn filter_from_yaml(mapping: &Mapping) -> Result<NoteFilter> {
+    let string_list = |key: &str| match mapping.get(&YamlValue::from(key)) {
+        Some(YamlValue::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str().map(|s| s.to_string()))
+            .collect(),
+        Some(YamlValue::String(item)) => vec![item.clone()],
+        _ => Vec::new(),
+    };
+
+    let date = |key: &str| match mapping.get(&YamlValue::from(key)).and_then(|v| v.as_str()) {
+        Some(value) => parse_date(value)
+            .map(Some)
+            .context(format!("Invalid date '{}' for '{}'", value, key)),
+        None => Ok(None),
+    };
+
+    Ok(NoteFilter {
+        project: mapping
+            .get(&YamlValue::from("project"))
+            .and_then(|v| v.as_str())
+            .map(|p| p.to_string()),
+        tags: string_list("tags"),
+        fields: string_list("fields")
+            .iter()
+            .map(|field| parse_field_filter(field))
+            .collect::<Result<Vec<_>>>()?,
+        created_after: date(DATE_KEYS[0])?,
+        created_before: date(DATE_KEYS[1])?,
+        modified_after: date(DATE_KEYS[2])?,
+        modified_before: date(DATE_KEYS[3])?,
+    })
+}
+
+/// One-line summary of a saved query, e.g. "project=work, tags=q4,urgent".
+fn describe_filter(filter: &NoteFilter) -> String {
+    filter_to_yaml(filter)
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                YamlValue::Sequence(items) => items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                value => value.as_str().unwrap_or_default().to_string(),
+            };
+            format!("{}={}", key.as_str().unwrap_or_default(), value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_round_trip() {
+        let workspace = Workspace {
+            name: "thesis".to_string(),
+            notes: vec!["projects/research/alpha.md".to_string()],
+            query: Some(NoteFilter {
+                project: Some("projects/research".to_string()),
+                tags: vec!["ml".to_string()],
+                fields: vec![("status".to_string(), Some("active".to_string()))],
+                created_after: parse_date("2024-03-01"),
+                ..Default::default()
+            }),
+        };
+
+        let content = serialize_workspace(&workspace).unwrap();
+        let parsed = parse_workspace("thesis", &content).unwrap();
+
+        assert_eq!(parsed.notes, workspace.notes);
+        let query = parsed.query.unwrap();
+        assert_eq!(query.project.as_deref(), Some("projects/research"));
+        assert_eq!(query.tags, vec!["ml".to_string()]);
+        assert_eq!(
+            query.fields,
+            vec![("status".to_string(), Some("active".to_string()))]
+        );
+        assert_eq!(query.created_after, parse_date("2024-03-01"));
+    }
+
+    #[test]
+    fn test_parse_workspace_without_query() {
+        let parsed = parse_workspace("empty", "name: empty\nnotes: []\n").unwrap();
+        assert!(parsed.notes.is_empty());
+        assert!(parsed.query.is_none());
+    }
+
+    #[test]
+    fn test_workspace_name_must_be_a_file_name() {
+        let vault = Path::new("/vault");
+        assert!(workspace_file(vault, "research").is_ok());
+        assert!(workspace_file(vault, "../research").is_err());
+        assert!(workspace_file(vault, "").is_err());
+    }
+}
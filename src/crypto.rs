@@ -0,0 +1,407 @@
+// src/crypto.rs
+//! Passphrase encryption of note bodies. The key is derived with Argon2id
+//! from the passphrase and a random salt, and the body is sealed with
+//! XChaCha20-Poly1305. The frontmatter stays readable, so titles, tags and
+//! the `encrypted: true` marker keep working in pickers and listings.
+//!
+//! The body of an encrypted note is an armored block:
+//!
+//! ```text
+//! -----BEGIN NCY ENCRYPTED NOTE-----
+//! <base64 of: "NCY1" | m_cost | t_cost | p_cost | salt | nonce | ciphertext>
+//! -----END NCY ENCRYPTED NOTE-----
+//! ```
+//!
+//! Everything before the ciphertext is authenticated along with it, so the
+//! key derivation parameters cannot be tampered with either.
+use crate::metadata::{split_frontmatter, update_frontmatter};
+use anyhow::{Context, Result, anyhow};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+const BEGIN_MARKER: &str = "-----BEGIN NCY ENCRYPTED NOTE-----";
+const END_MARKER: &str = "-----END NCY ENCRYPTED NOTE-----";
+const MAGIC: &[u8; 4] = b"NCY1";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+const HEADER_LENGTH: usize = MAGIC.len() + 12 + SALT_LENGTH + NONCE_LENGTH;
+const KEY_LENGTH: usize = 32;
+// Width of the base64 lines in the armored block
+const LINE_WIDTH: usize = 64;
+// Refuse parameters that would make opening a note take gigabytes of memory,
+// or so many passes that it never seems to finish
+const MAX_MEMORY_COST: u32 = 1024 * 1024;
+const MAX_TIME_COST: u32 = 64;
+
+/// Frontmatter key marking an encrypted note.
+pub const ENCRYPTED_KEY: &str = "encrypted";
+
+/// Argon2id cost parameters: memory in KiB, iterations and lanes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Checks whether the body of a note is an encrypted block.
+pub fn is_encrypted(content: &str) -> bool {
+    split_frontmatter(content)
+        .1
+        .trim_start()
+        .starts_with(BEGIN_MARKER)
+}
+
+/// Like `is_encrypted`, for a note on disk. Unreadable notes are not encrypted.
+pub fn is_encrypted_note(note_path: &Path) -> bool {
+    fs::read_to_string(note_path).is_ok_and(|content| is_encrypted(&content))
+}
+
+/// Encrypts the body of a note and marks its frontmatter, adding `title`
+/// when the frontmatter has none so the note can still be found by title.
+pub fn encrypt_note(content: &str, title: &str, passphrase: &str) -> Result<String> {
+    encrypt_note_with(content, title, passphrase, KdfParams::default())
+}
+
+fn encrypt_note_with(
+    content: &str,
+    title: &str,
+    passphrase: &str,
+    params: KdfParams,
+) -> Result<String> {
+    if is_encrypted(content) {
+        return Err(anyhow!("The note is already encrypted"));
+    }
+
+    let body = split_frontmatter(content).1;
+    let armored = encrypt_text(body, passphrase, params)?;
+    let marked = update_frontmatter(content, |mapping| {
+        let title_key = serde_yaml::Value::String("title".to_string());
+        if !mapping.contains_key(&title_key) {
+            mapping.insert(title_key, serde_yaml::Value::String(title.to_string()));
+        }
+        mapping.insert(
+            serde_yaml::Value::String(ENCRYPTED_KEY.to_string()),
+            serde_yaml::Value::Bool(true),
+        );
+        Ok(())
+    })?;
+
+    // update_frontmatter keeps the body, so swap the plain one for the block
+    let frontmatter_end = marked.len() - body.len();
+    Ok(format!("{}{}", &marked[..frontmatter_end], armored))
+}
+
+/// Decrypts the body of a note and removes the `encrypted` marker. Fails
+/// for a wrong passphrase or a damaged block.
+pub fn decrypt_note(content: &str, passphrase: &str) -> Result<Zeroizing<String>> {
+    let (_, body) = split_frontmatter(content);
+    if !is_encrypted(content) {
+        return Err(anyhow!("The note is not encrypted"));
+    }
+
+    let plain = decrypt_text(body, passphrase)?;
+    let unmarked = update_frontmatter(content, |mapping| {
+        mapping.remove(&serde_yaml::Value::String(ENCRYPTED_KEY.to_string()));
+        Ok(())
+    })?;
+    let frontmatter_end = unmarked.len() - body.len();
+    Ok(Zeroizing::new(format!(
+        "{}{}",
+        &unmarked[..frontmatter_end],
+        plain.as_str()
+    )))
+}
+
+fn encrypt_text(plain: &str, passphrase: &str, params: KdfParams) -> Result<String> {
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&params.m_cost.to_le_bytes());
+    header.extend_from_slice(&params.t_cost.to_le_bytes());
+    header.extend_from_slice(&params.p_cost.to_le_bytes());
+    let mut random = [0u8; SALT_LENGTH + NONCE_LENGTH];
+    getrandom::getrandom(&mut random)
+        .map_err(|e| anyhow!("Failed to get random bytes from the system: {}", e))?;
+    header.extend_from_slice(&random);
+
+    let (salt, nonce) = random.split_at(SALT_LENGTH);
+    let key = derive_key(passphrase, salt, params)?;
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: plain.as_bytes(),
+                aad: &header,
+            },
+        )
+        .map_err(|_| anyhow!("Failed to encrypt the note"))?;
+
+    header.extend_from_slice(&ciphertext);
+    let encoded = BASE64.encode(&header);
+
+    let mut armored = format!("{}\n", BEGIN_MARKER);
+    for chunk in encoded.as_bytes().chunks(LINE_WIDTH) {
+        // base64 is ASCII, so every chunk is valid UTF-8
+        armored.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        armored.push('\n');
+    }
+    armored.push_str(END_MARKER);
+    armored.push('\n');
+    Ok(armored)
+}
+
+fn decrypt_text(armored: &str, passphrase: &str) -> Result<Zeroizing<String>> {
+    let inner = armored
+        .trim()
+        .strip_prefix(BEGIN_MARKER)
+        .and_then(|rest| rest.strip_suffix(END_MARKER))
+        .context("The encrypted block of the note is damaged")?;
+    let encoded: String = inner.split_whitespace().collect();
+    let data = BASE64
+        .decode(encoded)
+        .context("The encrypted block of the note is damaged")?;
+
+    if data.len() < HEADER_LENGTH || &data[..MAGIC.len()] != MAGIC {
+        return Err(anyhow!(
+            "The encrypted block of the note is damaged or from a newer version of ncy"
+        ));
+    }
+    let number = |i: usize| {
+        let start = MAGIC.len() + i * 4;
+        u32::from_le_bytes([
+            data[start],
+            data[start + 1],
+            data[start + 2],
+            data[start + 3],
+        ])
+    };
+    let params = KdfParams {
+        m_cost: number(0),
+        t_cost: number(1),
+        p_cost: number(2),
+    };
+    if params.m_cost > MAX_MEMORY_COST || params.t_cost > MAX_TIME_COST {
+        return Err(anyhow!("The encrypted block of the note is damaged"));
+    }
+
+    let (header, ciphertext) = data.split_at(HEADER_LENGTH);
+    let salt = &header[HEADER_LENGTH - NONCE_LENGTH - SALT_LENGTH..HEADER_LENGTH - NONCE_LENGTH];
+    let nonce = &header[HEADER_LENGTH - NONCE_LENGTH..];
+    let key = derive_key(passphrase, salt, params)?;
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+    let plain = cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow!("Wrong passphrase, or the note has been tampered with"))?;
+
+    String::from_utf8(plain.to_vec())
+        .map(Zeroizing::new)
+        .context("The decrypted note is not valid UTF-8")
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    params: KdfParams,
+) -> Result<Zeroizing<[u8; KEY_LENGTH]>> {
+    let params = Params::new(
+        params.m_cost,
+        params.t_cost,
+        params.p_cost,
+        Some(KEY_LENGTH),
+    )
+    .map_err(|e| anyhow!("Invalid key derivation parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| anyhow!("Failed to derive the key: {}", e))?;
+    Ok(key)
+}
+
+/// Asks for a passphrase on the terminal without echoing it. With `confirm`
+/// it is asked twice, for passphrases that are about to be used for the
+/// first time.
+pub fn read_passphrase(confirm: bool) -> Result<Zeroizing<String>> {
+    let passphrase = Zeroizing::new(
+        rpassword::prompt_password("Passphrase: ").context("Failed to read the passphrase")?,
+    );
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase cannot be empty"));
+    }
+
+    if confirm {
+        let again = Zeroizing::new(
+            rpassword::prompt_password("Repeat the passphrase: ")
+                .context("Failed to read the passphrase")?,
+        );
+        if *again != *passphrase {
+            return Err(anyhow!("The passphrases do not match"));
+        }
+    }
+    Ok(passphrase)
+}
+
+/// A directory only the current user can read, for decrypted copies of
+/// notes. It lives in $XDG_RUNTIME_DIR when set, which is usually kept in
+/// memory, and is wiped when dropped: every file left in it, including
+/// swap and backup files of the editor, is overwritten before removal.
+pub struct PrivateDir {
+    path: PathBuf,
+}
+
+impl PrivateDir {
+    pub fn new() -> Result<Self> {
+        let base = env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(env::temp_dir);
+
+        let mut random = [0u8; 8];
+        getrandom::getrandom(&mut random)
+            .map_err(|e| anyhow!("Failed to get random bytes from the system: {}", e))?;
+        let name: String = random.iter().map(|b| format!("{:02x}", b)).collect();
+        let path = base.join(format!("ncy-{}-{}", std::process::id(), name));
+
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&path).context(format!(
+            "Failed to create private directory: {}",
+            path.display()
+        ))?;
+        Ok(PrivateDir { path })
+    }
+
+    /// Writes a new file in the directory, readable only by the current user.
+    pub fn write(&self, name: &str, content: &str) -> Result<PathBuf> {
+        let path = self.path.join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .context(format!("Failed to create file: {}", path.display()))?;
+        file.write_all(content.as_bytes())
+            .context(format!("Failed to write file: {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        if let Ok(entries) = fs::read_dir(&self.path) {
+            for entry in entries.flatten() {
+                wipe_file(&entry.path());
+            }
+        }
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            eprintln!("Warning: failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+// Overwrites a file with zeros and flushes it to disk before removing it
+fn wipe_file(path: &Path) {
+    if !path.is_file() {
+        return;
+    }
+    if let Ok(length) = fs::metadata(path).map(|meta| meta.len())
+        && let Ok(mut file) = OpenOptions::new().write(true).open(path)
+    {
+        let zeros = vec![0u8; length as usize];
+        let _ = file.write_all(&zeros).and_then(|_| File::sync_all(&file));
+    }
+    let _ = fs::remove_file(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap parameters so the tests do not spend seconds deriving keys
+    const TEST_PARAMS: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_encrypt_and_decrypt_note() {
+        let note = "---\ntitle: Salaries\nproject: hr\n---\n\nAlice: 1234\n";
+        let encrypted = encrypt_note_with(note, "ignored", "hunter2", TEST_PARAMS).unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("Alice"));
+        assert!(encrypted.starts_with("---\ntitle: Salaries\nproject: hr\nencrypted: true\n---\n"));
+        assert!(encrypt_note_with(&encrypted, "", "hunter2", TEST_PARAMS).is_err());
+
+        let decrypted = decrypt_note(&encrypted, "hunter2").unwrap();
+        assert_eq!(
+            decrypted.as_str(),
+            "---\ntitle: Salaries\nproject: hr\n---\n\nAlice: 1234\n"
+        );
+        assert!(decrypt_note(&encrypted, "hunter3").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_block_is_authenticated() {
+        let encrypted = encrypt_note_with("Plain text", "Keys", "pass", TEST_PARAMS).unwrap();
+        assert!(encrypted.starts_with("---\ntitle: Keys\nencrypted: true\n---\n"));
+
+        // Raising the stored iteration count must break decryption
+        let body = split_frontmatter(&encrypted).1;
+        let encoded: String = body.lines().filter(|l| !l.starts_with("-----")).collect();
+        let mut data = BASE64.decode(encoded).unwrap();
+        data[MAGIC.len() + 4] += 1;
+        let tampered = format!(
+            "{}\n{}\n{}\n",
+            BEGIN_MARKER,
+            BASE64.encode(&data),
+            END_MARKER
+        );
+        assert!(decrypt_text(&tampered, "pass").is_err());
+
+        // An absurd iteration count is refused before deriving a key
+        data[MAGIC.len() + 4..MAGIC.len() + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let hostile = format!(
+            "{}\n{}\n{}\n",
+            BEGIN_MARKER,
+            BASE64.encode(&data),
+            END_MARKER
+        );
+        assert!(decrypt_text(&hostile, "pass").is_err());
+        assert_eq!(decrypt_text(body, "pass").unwrap().as_str(), "Plain text");
+    }
+}
@@ -0,0 +1,196 @@
+// src/embeddings.rs
+//! Sentence embeddings of notes for semantic search. The model is loaded from
+//! a local directory named by `embedding_model` in config.yaml and runs on the
+//! CPU; nothing is ever downloaded. The directory must hold a sentence
+//! embeddings model converted for rust-bert (`config.json`, `modules.json`,
+//! `rust_model.ot` and the tokenizer files).
+//!
+//! Embeddings are cached per vault in the config directory, keyed by the hash
+//! of the text they were computed from, so only changed notes are embedded
+//! again.
+use crate::utils::config_dir;
+use anyhow::{Context, Result, anyhow};
+use rust_bert::pipelines::sentence_embeddings::{
+    SentenceEmbeddingsBuilder, SentenceEmbeddingsModel,
+};
+use serde_json::{Map, Value as JsonValue, json};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tch::Device;
+
+// Notes embedded per call to the model, to keep memory use flat
+const BATCH_SIZE: usize = 32;
+
+/// Returns the model directory configured in config.yaml. Relative paths are
+/// taken relative to the config directory.
+pub fn model_directory(config: &JsonValue) -> Result<PathBuf> {
+    let configured = config
+        .get("embedding_model")
+        .and_then(|m| m.as_str())
+        .context(
+            "No embedding model configured. Set 'embedding_model' in config.yaml to a local sentence embeddings model directory",
+        )?;
+
+    let path = config_dir()?.join(configured);
+    if !path.is_dir() {
+        return Err(anyhow!(
+            "Embedding model directory not found: {}",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+pub struct Embedder {
+    model: SentenceEmbeddingsModel,
+}
+
+impl Embedder {
+    pub fn load(model_dir: &Path) -> Result<Self> {
+        let model = SentenceEmbeddingsBuilder::local(model_dir)
+            .with_device(Device::Cpu)
+            .create_model()
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to load embedding model from {}: {}",
+                    model_dir.display(),
+                    e
+                )
+            })?;
+        Ok(Embedder { model })
+    }
+
+    pub fn embed<S: AsRef<str> + Sync>(&self, texts: &[S]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let batch = self
+                .model
+                .encode(batch)
+                .map_err(|e| anyhow!("Failed to compute embeddings: {}", e))?;
+            embeddings.extend(batch);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Embeddings computed with one model, keyed by content hash.
+#[derive(Debug, Default, PartialEq)]
+pub struct EmbeddingCache {
+    model: String,
+    embeddings: HashMap<String, Vec<f32>>,
+}
+
+impl EmbeddingCache {
+    fn path(vault_name: &str) -> Result<PathBuf> {
+        Ok(config_dir()?
+            .join("embeddings")
+            .join(format!("{}.json", vault_name)))
+    }
+
+    /// Loads the cache of a vault. A cache made with another model, or one
+    /// that cannot be read, is ignored.
+    pub fn load(vault_name: &str, model_dir: &Path) -> Result<Self> {
+        let model = model_dir.to_string_lossy().to_string();
+        let empty = EmbeddingCache {
+            model: model.clone(),
+            embeddings: HashMap::new(),
+        };
+
+        let path = Self::path(vault_name)?;
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(empty);
+        };
+        match parse_cache(&content) {
+            Some(cache) if cache.model == model => Ok(cache),
+            _ => Ok(empty),
+        }
+    }
+
+    pub fn save(&self, vault_name: &str) -> Result<()> {
+        let path = Self::path(vault_name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create embeddings directory")?;
+        }
+
+        let embeddings: Map<String, JsonValue> = self
+            .embeddings
+            .iter()
+            .map(|(hash, embedding)| (hash.clone(), json!(embedding)))
+            .collect();
+        let content = json!({"model": self.model, "embeddings": embeddings});
+
+        fs::write(&path, content.to_string()).context(format!(
+            "Failed to write embeddings cache: {}",
+            path.display()
+        ))
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Vec<f32>> {
+        self.embeddings.get(hash)
+    }
+
+    pub fn insert(&mut self, hash: String, embedding: Vec<f32>) {
+        self.embeddings.insert(hash, embedding);
+    }
+
+    /// Drops the embeddings of text that no longer exists. Returns whether
+    /// anything was dropped.
+    pub fn retain_hashes(&mut self, hashes: &HashSet<&str>) -> bool {
+        let before = self.embeddings.len();
+        self.embeddings
+            .retain(|hash, _| hashes.contains(hash.as_str()));
+        self.embeddings.len() != before
+    }
+}
+
+fn parse_cache(content: &str) -> Option<EmbeddingCache> {
+    let value: JsonValue = serde_json::from_str(content).ok()?;
+    let model = value.get("model")?.as_str()?.to_string();
+
+    let mut embeddings = HashMap::new();
+    for (hash, embedding) in value.get("embeddings")?.as_object()? {
+        let embedding = embedding
+            .as_array()?
+            .iter()
+            .map(|x| x.as_f64().map(|x| x as f32))
+            .collect::<Option<Vec<f32>>>()?;
+        embeddings.insert(hash.clone(), embedding);
+    }
+
+    Some(EmbeddingCache { model, embeddings })
+}
+
+pub fn content_hash(text: &str) -> String {
+    format!("{:x}", md5::compute(text))
+}
+
+/// Cosine similarity of two embeddings.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-6);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        let cache = parse_cache(r#"{"model": "/m", "embeddings": {"abc": [0.5, 1]}}"#).unwrap();
+        assert_eq!(cache.model, "/m");
+        assert_eq!(cache.get("abc"), Some(&vec![0.5, 1.0]));
+
+        assert!(parse_cache(r#"{"model": "/m", "embeddings": {"abc": ["x"]}}"#).is_none());
+        assert!(parse_cache("not json").is_none());
+    }
+}
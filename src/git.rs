@@ -0,0 +1,364 @@
+// src/git.rs
+use crate::utils::read_config;
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// A pending change in the working tree, with its path relative to the vault.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: String,
+}
+
+/// A commit that touched a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    pub hash: String,
+    pub date: String,
+    pub subject: String,
+}
+
+/// Runs git inside `dir` and returns its stdout, failing with git's own
+/// error message when it exits with a non-zero status.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git. Is git installed?")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    String::from_utf8(output.stdout).context("Failed to parse git output")
+}
+
+pub fn is_repository(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out.trim() == "true")
+}
+
+/// Lists the uncommitted changes below `dir`, with paths relative to `dir`.
+pub fn pending_changes(dir: &Path) -> Result<Vec<Change>> {
+    // --no-renames reports a moved note as a deletion and a creation, which is
+    // also how the commit message describes it
+    let output = git(
+        dir,
+        &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=all",
+            "--no-renames",
+            "--",
+            ".",
+        ],
+    )?;
+
+    // Porcelain paths are relative to the repository root, which may be above the vault
+    let prefix = git(dir, &["rev-parse", "--show-prefix"])?;
+    let prefix = prefix.trim();
+
+    Ok(parse_status(&output)
+        .into_iter()
+        .map(|change| Change {
+            path: change
+                .path
+                .strip_prefix(prefix)
+                .unwrap_or(&change.path)
+                .to_string(),
+            ..change
+        })
+        .collect())
+}
+
+fn parse_status(output: &str) -> Vec<Change> {
+    output
+        .split('\0')
+        .filter(|entry| entry.len() > 3)
+        .map(|entry| {
+            let (status, path) = entry.split_at(3);
+            let kind = match status.trim() {
+                "??" | "A" | "AM" => ChangeKind::Created,
+                s if s.contains('D') => ChangeKind::Deleted,
+                _ => ChangeKind::Modified,
+            };
+            Change {
+                kind,
+                path: path.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Builds a commit message from the pending changes. A single change gets a
+/// one-line message; several changes get a summary line and a list per kind.
+pub fn commit_message(changes: &[Change]) -> String {
+    if let [change] = changes {
+        let verb = match change.kind {
+            ChangeKind::Created => "Create",
+            ChangeKind::Modified => "Update",
+            ChangeKind::Deleted => "Delete",
+        };
+        return format!("{} {}", verb, change.path);
+    }
+
+    let groups = [
+        (ChangeKind::Created, "created", "Created"),
+        (ChangeKind::Modified, "modified", "Modified"),
+        (ChangeKind::Deleted, "deleted", "Deleted"),
+    ];
+
+    let mut counts = Vec::new();
+    let mut body = String::new();
+    for (kind, lower, heading) in groups {
+        let paths: Vec<&str> = changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .map(|change| change.path.as_str())
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+
+        counts.push(format!("{} {}", paths.len(), lower));
+        body.push_str(&format!("\n{}:\n", heading));
+        for path in paths {
+            body.push_str(&format!("- {}\n", path));
+        }
+    }
+
+    format!("Update notes: {}\n{}", counts.join(", "), body)
+}
+
+/// Stages everything below `dir` and commits it. Returns the changes that
+/// were committed; an empty list means there was nothing to commit.
+pub fn commit_all(dir: &Path, message: Option<&str>) -> Result<Vec<Change>> {
+    let changes = pending_changes(dir)?;
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    let message = match message {
+        Some(message) => message.to_string(),
+        None => commit_message(&changes),
+    };
+
+    git(dir, &["add", "--all", "--", "."])?;
+    git(
+        dir,
+        &["commit", "--quiet", "--message", &message, "--", "."],
+    )?;
+
+    Ok(changes)
+}
+
+/// Lists the commits that touched a note, newest first. The path is relative
+/// to `dir` and the note may have been deleted since.
+pub fn file_history(dir: &Path, note: &str) -> Result<Vec<Revision>> {
+    let output = git(
+        dir,
+        &[
+            // No --follow: fresh notes share their frontmatter and would be
+            // mistaken for copies of older ones
+            "log",
+            "--date=format:%Y-%m-%d %H:%M",
+            "--format=%h%x1f%ad%x1f%s",
+            "--",
+            note,
+        ],
+    )?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\x1f');
+            Some(Revision {
+                hash: parts.next()?.to_string(),
+                date: parts.next()?.to_string(),
+                subject: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Returns the content of a note at the given revision.
+pub fn show_revision(dir: &Path, revision: &str, note: &str) -> Result<String> {
+    // "./" makes the path relative to `dir` instead of the repository root
+    git(dir, &["show", &format!("{}:./{}", revision, note)]).context(format!(
+        "'{}' does not exist at revision {}",
+        note, revision
+    ))
+}
+
+/// Returns the changes made to a note by the given revision as a patch.
+pub fn show_diff(dir: &Path, revision: &str, note: &str) -> Result<String> {
+    git(
+        dir,
+        &[
+            "show",
+            "--format=%h %ad %s",
+            "--date=short",
+            revision,
+            "--",
+            note,
+        ],
+    )
+}
+
+/// Commits the vaults containing the given notes if they have `auto_commit: true`.
+pub fn auto_commit(note_paths: &[&Path]) -> Result<()> {
+    let config = read_config()?;
+
+    for vault_path in auto_commit_vaults(&config) {
+        if !note_paths.iter().any(|path| path.starts_with(&vault_path)) {
+            continue;
+        }
+
+        if !is_repository(&vault_path) {
+            return Err(anyhow!(
+                "auto_commit is enabled but {} is not a git repository",
+                vault_path.display()
+            ));
+        }
+        commit_all(&vault_path, None)?;
+    }
+
+    Ok(())
+}
+
+/// Same as `auto_commit`, but only warns on failure so the note itself is
+/// never lost because of a git problem.
+pub fn auto_commit_or_warn(note_paths: &[&Path]) {
+    if let Err(e) = auto_commit(note_paths) {
+        eprintln!("Warning: failed to auto-commit vault: {}", e);
+    }
+}
+
+fn auto_commit_vaults(config: &JsonValue) -> Vec<PathBuf> {
+    config
+        .get("vaults")
+        .and_then(|v| v.as_array())
+        .map(|vaults| {
+            vaults
+                .iter()
+                .filter(|vault| vault.get("auto_commit").and_then(|v| v.as_bool()) == Some(true))
+                .filter_map(|vault| vault.get("vault_directory").and_then(|d| d.as_str()))
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    // Creates an empty repository in a fresh temp directory
+    fn temp_repo(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ncy-git-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        git(&dir, &["init", "--quiet"]).unwrap();
+        git(&dir, &["config", "user.name", "ncy"]).unwrap();
+        git(&dir, &["config", "user.email", "ncy@example.com"]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_commit_message() {
+        let single = [Change {
+            kind: ChangeKind::Created,
+            path: "ideas/zeta.md".to_string(),
+        }];
+        assert_eq!(commit_message(&single), "Create ideas/zeta.md");
+
+        let several = [
+            Change {
+                kind: ChangeKind::Modified,
+                path: "journal/03-01-2024.md".to_string(),
+            },
+            Change {
+                kind: ChangeKind::Created,
+                path: "a.md".to_string(),
+            },
+        ];
+        assert_eq!(
+            commit_message(&several),
+            "Update notes: 1 created, 1 modified\n\nCreated:\n- a.md\n\nModified:\n- journal/03-01-2024.md\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let changes = parse_status("?? new.md\0 M dir/old.md\0 D gone.md\0");
+        assert_eq!(
+            changes.iter().map(|c| c.kind).collect::<Vec<_>>(),
+            vec![
+                ChangeKind::Created,
+                ChangeKind::Modified,
+                ChangeKind::Deleted
+            ]
+        );
+        assert_eq!(changes[1].path, "dir/old.md");
+    }
+
+    #[test]
+    fn test_commit_and_history_in_temp_repo() {
+        let dir = temp_repo("history");
+        let note = "projects/note.md";
+        fs::create_dir_all(dir.join("projects")).unwrap();
+
+        fs::write(dir.join(note), "first\n").unwrap();
+        let changes = commit_all(&dir, None).unwrap();
+        assert_eq!(changes[0].kind, ChangeKind::Created);
+
+        fs::write(dir.join(note), "second\n").unwrap();
+        commit_all(&dir, None).unwrap();
+
+        // Nothing left to commit
+        assert!(commit_all(&dir, None).unwrap().is_empty());
+
+        let revisions = file_history(&dir, note).unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].subject, "Update projects/note.md");
+        assert_eq!(revisions[1].subject, "Create projects/note.md");
+        assert_eq!(
+            show_revision(&dir, &revisions[1].hash, note).unwrap(),
+            "first\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_auto_commit_vaults() {
+        let config = json!({
+            "vaults": [
+                {"name": "main", "vault_directory": "/notes/main", "auto_commit": true},
+                {"name": "work", "vault_directory": "/notes/work"},
+            ]
+        });
+        assert_eq!(
+            auto_commit_vaults(&config),
+            vec![PathBuf::from("/notes/main")]
+        );
+    }
+}
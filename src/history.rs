@@ -0,0 +1,209 @@
+// src/history.rs
+use crate::utils::config_dir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Number of entries kept in the history file; older ones are dropped
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Open,
+    Create,
+}
+
+impl Action {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Action::Open => "open",
+            Action::Create => "create",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: String,
+    pub action: Action,
+    pub time: DateTime<Local>,
+}
+
+fn history_file() -> Result<PathBuf> {
+    Ok(config_dir()?.join("history.jsonl"))
+}
+
+/// Appends an open or create event for a note to the history file.
+pub fn record(note_path: &Path, action: Action) -> Result<()> {
+    let history_path = history_file()?;
+
+    let mut entries = load_from(&history_path)?;
+    entries.push(Entry {
+        path: note_path.to_string_lossy().to_string(),
+        action,
+        time: Local::now(),
+    });
+
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+
+    let mut content = String::new();
+    for entry in &entries {
+        let line = json!({
+            "path": entry.path,
+            "action": entry.action.as_str(),
+            "time": entry.time.to_rfc3339(),
+        });
+        content.push_str(&line.to_string());
+        content.push('\n');
+    }
+
+    fs::write(&history_path, content).context("Failed to write note history")?;
+    Ok(())
+}
+
+/// Same as `record`, but only warns on failure. History is a convenience and
+/// must never make the command itself fail.
+pub fn record_or_warn(note_path: &Path, action: Action) {
+    if let Err(e) = record(note_path, action) {
+        eprintln!("Warning: failed to record note history: {}", e);
+    }
+}
+
+pub fn load() -> Result<Vec<Entry>> {
+    load_from(&history_file()?)
+}
+
+fn load_from(history_path: &Path) -> Result<Vec<Entry>> {
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(history_path).context("Failed to read note history")?;
+    Ok(parse_entries(&content))
+}
+
+fn parse_entries(content: &str) -> Vec<Entry> {
+    // Malformed lines are skipped rather than invalidating the whole history
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|value| {
+            let path = value.get("path")?.as_str()?.to_string();
+            let action = match value.get("action")?.as_str()? {
+                "create" => Action::Create,
+                _ => Action::Open,
+            };
+            let time = DateTime::parse_from_rfc3339(value.get("time")?.as_str()?)
+                .ok()?
+                .with_timezone(&Local);
+            Some(Entry { path, action, time })
+        })
+        .collect()
+}
+
+/// Returns the paths of the most recently used notes, newest first and
+/// without duplicates.
+pub fn recent_paths(entries: &[Entry]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    entries
+        .iter()
+        .rev()
+        .filter(|entry| seen.insert(entry.path.clone()))
+        .map(|entry| entry.path.clone())
+        .collect()
+}
+
+/// Scores every note in the history by frequency and recency of use.
+/// Each visit is weighted by its age, so a note opened often last week beats
+/// one opened once yesterday, and both beat one used heavily months ago.
+pub fn frecency_scores(entries: &[Entry], now: DateTime<Local>) -> HashMap<String, f64> {
+    let mut scores = HashMap::new();
+
+    for entry in entries {
+        let age = now - entry.time;
+        let weight = if age < Duration::days(4) {
+            100.0
+        } else if age < Duration::days(14) {
+            70.0
+        } else if age < Duration::days(31) {
+            50.0
+        } else if age < Duration::days(90) {
+            30.0
+        } else {
+            10.0
+        };
+
+        *scores.entry(entry.path.clone()).or_insert(0.0) += weight;
+    }
+
+    scores
+}
+
+/// Reorders note paths so the highest frecency comes first. Notes without
+/// history keep their relative order at the end.
+pub fn sort_by_frecency(note_paths: &mut [String]) {
+    // Missing history just means no ranking
+    let entries = load().unwrap_or_default();
+    if entries.is_empty() {
+        return;
+    }
+
+    let scores = frecency_scores(&entries, Local::now());
+    note_paths.sort_by(|a, b| {
+        let score_a = scores.get(a).copied().unwrap_or(0.0);
+        let score_b = scores.get(b).copied().unwrap_or(0.0);
+        score_b.total_cmp(&score_a)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, days_ago: i64) -> Entry {
+        Entry {
+            path: path.to_string(),
+            action: Action::Open,
+            time: Local::now() - Duration::days(days_ago),
+        }
+    }
+
+    #[test]
+    fn test_parse_entries_skips_malformed_lines() {
+        let content = concat!(
+            "{\"path\":\"/v/a.md\",\"action\":\"create\",\"time\":\"2024-03-01T10:00:00+00:00\"}\n",
+            "not json\n",
+            "{\"path\":\"/v/b.md\",\"action\":\"open\"}\n",
+        );
+        let entries = parse_entries(content);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "/v/a.md");
+        assert_eq!(entries[0].action, Action::Create);
+    }
+
+    #[test]
+    fn test_recent_paths_are_unique_and_newest_first() {
+        let entries = vec![entry("a", 3), entry("b", 2), entry("a", 1)];
+        assert_eq!(recent_paths(&entries), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_frecency_prefers_frequent_recent_notes() {
+        let entries = vec![
+            entry("old", 200),
+            entry("old", 150),
+            entry("old", 120),
+            entry("weekly", 8),
+            entry("weekly", 6),
+            entry("yesterday", 1),
+        ];
+        let scores = frecency_scores(&entries, Local::now());
+        assert!(scores["weekly"] > scores["yesterday"]);
+        assert!(scores["yesterday"] > scores["old"]);
+    }
+}
@@ -0,0 +1,770 @@
+// src/html_to_md.rs
+//! A small HTML to Markdown converter for imported notes. It understands the
+//! common formatting tags and is forgiving about malformed markup; anything it
+//! does not know is reduced to its text.
+use std::collections::HashMap;
+
+pub type Attributes = HashMap<String, String>;
+
+/// Converts HTML to Markdown. Tags that are not HTML, such as Evernote's
+/// `<en-media>`, are passed to `custom`, which may return Markdown for them.
+pub fn convert<F>(html: &str, mut custom: F) -> String
+where
+    F: FnMut(&str, &Attributes) -> Option<String>,
+{
+    let mut converter = Converter::default();
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => converter.text(&text),
+            Token::Start(name, attributes, self_closing) => {
+                if let Some(markdown) = custom(&name, &attributes) {
+                    converter.raw(&markdown);
+                } else {
+                    converter.start(&name, &attributes);
+                    if self_closing || is_void(&name) {
+                        converter.end(&name);
+                    }
+                }
+            }
+            Token::End(name) => converter.end(&name),
+        }
+    }
+
+    converter.finish()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text(String),
+    Start(String, Attributes, bool),
+    End(String),
+}
+
+fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "hr" | "img" | "input" | "meta" | "link" | "col" | "area" | "base" | "wbr"
+    )
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    // Text inside these tags is never shown
+    let mut skip_until: Option<String> = None;
+
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            if skip_until.is_none() {
+                tokens.push(Token::Text(decode_entities(rest)));
+            }
+            break;
+        };
+
+        if open > 0 && skip_until.is_none() {
+            tokens.push(Token::Text(decode_entities(&rest[..open])));
+        }
+        rest = &rest[open..];
+
+        // Comments, doctypes, processing instructions and CDATA markers
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let Some(close) = rest.find('>') else {
+            if skip_until.is_none() {
+                tokens.push(Token::Text(decode_entities(rest)));
+            }
+            break;
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim().to_lowercase();
+            if skip_until.as_deref() == Some(name.as_str()) {
+                skip_until = None;
+            } else if skip_until.is_none() {
+                tokens.push(Token::End(name));
+            }
+            continue;
+        }
+
+        let self_closing = tag.trim_end().ends_with('/');
+        let tag = tag.trim_end().trim_end_matches('/');
+        let (name, attributes) = match tag.find(char::is_whitespace) {
+            Some(space) => (&tag[..space], parse_attributes(&tag[space..])),
+            None => (tag, Attributes::new()),
+        };
+        let name = name.to_lowercase();
+        if name.is_empty() || !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            // A lone '<' in text
+            if skip_until.is_none() {
+                tokens.push(Token::Text(decode_entities(&format!("<{}>", tag))));
+            }
+            continue;
+        }
+
+        if skip_until.is_some() {
+            continue;
+        }
+        if matches!(name.as_str(), "script" | "style" | "head" | "title") && !self_closing {
+            skip_until = Some(name);
+            continue;
+        }
+
+        tokens.push(Token::Start(name, attributes, self_closing));
+    }
+
+    tokens
+}
+
+fn parse_attributes(text: &str) -> Attributes {
+    let mut attributes = Attributes::new();
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let value = if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (value, remaining) = match after_eq.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after_eq[1..];
+                    match inner.find(quote) {
+                        Some(end) => (&inner[..end], &inner[end + 1..]),
+                        None => (inner, ""),
+                    }
+                }
+                _ => {
+                    let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                    (&after_eq[..end], &after_eq[end..])
+                }
+            };
+            rest = remaining.trim_start();
+            decode_entities(value)
+        } else {
+            String::new()
+        };
+
+        if !name.is_empty() {
+            attributes.insert(name, value);
+        }
+    }
+
+    attributes
+}
+
+/// Decodes HTML character references such as `&amp;` and `&#39;`.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let entity_end = rest[1..]
+            .find(|c: char| c == ';' || c == '&' || c.is_whitespace())
+            .map(|i| i + 1);
+        let Some(end) = entity_end.filter(|end| rest[*end..].starts_with(';')) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('\u{2013}'),
+            "mdash" => Some('\u{2014}'),
+            "hellip" => Some('\u{2026}'),
+            "lsquo" => Some('\u{2018}'),
+            "rsquo" => Some('\u{2019}'),
+            "ldquo" => Some('\u{201c}'),
+            "rdquo" => Some('\u{201d}'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+
+        match character {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// An element whose content is collected separately and rewritten when it closes.
+#[derive(Debug)]
+enum FrameKind {
+    Root,
+    Emphasis(&'static str),
+    Code,
+    Link(String),
+    Heading(usize),
+    Blockquote,
+    ListItem,
+    Pre,
+    Cell,
+    Table(Vec<Vec<String>>),
+}
+
+#[derive(Debug)]
+struct Frame {
+    kind: FrameKind,
+    tag: String,
+    out: String,
+}
+
+#[derive(Debug)]
+struct Converter {
+    frames: Vec<Frame>,
+    // One entry per open list: whether it is ordered and the next number
+    lists: Vec<(bool, usize)>,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Converter {
+            frames: vec![Frame {
+                kind: FrameKind::Root,
+                tag: String::new(),
+                out: String::new(),
+            }],
+            lists: Vec::new(),
+        }
+    }
+}
+
+impl Converter {
+    fn out(&mut self) -> &mut String {
+        &mut self.frames.last_mut().expect("root frame").out
+    }
+
+    fn in_pre(&self) -> bool {
+        self.frames
+            .iter()
+            .any(|frame| matches!(frame.kind, FrameKind::Pre))
+    }
+
+    fn in_code(&self) -> bool {
+        self.frames
+            .iter()
+            .any(|frame| matches!(frame.kind, FrameKind::Code))
+    }
+
+    fn push(&mut self, kind: FrameKind, tag: &str) {
+        self.frames.push(Frame {
+            kind,
+            tag: tag.to_string(),
+            out: String::new(),
+        });
+    }
+
+    fn raw(&mut self, markdown: &str) {
+        self.out().push_str(markdown);
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_pre() {
+            self.out().push_str(text);
+            return;
+        }
+
+        let mut collapsed = String::new();
+        let mut last_space = false;
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !last_space {
+                    collapsed.push(' ');
+                }
+                last_space = true;
+            } else {
+                collapsed.push(c);
+                last_space = false;
+            }
+        }
+
+        let out = self.out();
+        if out.is_empty() || out.ends_with('\n') || out.ends_with(' ') {
+            collapsed = collapsed.trim_start().to_string();
+        }
+
+        // Code is written as is, since backslashes do not escape anything in it
+        if self.in_code() {
+            self.out().push_str(&collapsed);
+        } else {
+            self.out().push_str(&escape_markdown(&collapsed));
+        }
+    }
+
+    fn newline(&mut self) {
+        let out = self.out();
+        let trimmed_len = out.trim_end_matches([' ', '\t']).len();
+        out.truncate(trimmed_len);
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        let out = self.out();
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+
+    fn start(&mut self, name: &str, attributes: &Attributes) {
+        match name {
+            "p" | "table" | "hr" => self.blank_line(),
+            // Nested lists continue the item they are in
+            "ul" | "ol" if !self.lists.is_empty() => self.newline(),
+            "ul" | "ol" => self.blank_line(),
+            "div" | "tr" | "section" | "article" | "header" | "footer" => self.newline(),
+            _ => {}
+        }
+
+        match name {
+            "br" => {
+                if self.in_pre() {
+                    self.out().push('\n');
+                } else {
+                    self.out().push_str("  \n");
+                }
+            }
+            "hr" => {
+                self.out().push_str("---");
+                self.blank_line();
+            }
+            "b" | "strong" => self.push(FrameKind::Emphasis("**"), name),
+            "i" | "em" => self.push(FrameKind::Emphasis("*"), name),
+            "s" | "del" | "strike" => self.push(FrameKind::Emphasis("~~"), name),
+            "code" if !self.in_pre() => self.push(FrameKind::Code, name),
+            "a" => {
+                let href = attributes.get("href").cloned().unwrap_or_default();
+                self.push(FrameKind::Link(href), name);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.blank_line();
+                let level = name[1..].parse().unwrap_or(1);
+                self.push(FrameKind::Heading(level), name);
+            }
+            "blockquote" => {
+                self.blank_line();
+                self.push(FrameKind::Blockquote, name);
+            }
+            "ul" => self.lists.push((false, 1)),
+            "ol" => {
+                let start = attributes
+                    .get("start")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1);
+                self.lists.push((true, start));
+            }
+            "li" => {
+                self.newline();
+                self.push(FrameKind::ListItem, name);
+            }
+            "pre" => {
+                self.blank_line();
+                self.push(FrameKind::Pre, name);
+            }
+            "table" => self.push(FrameKind::Table(Vec::new()), name),
+            "tr" => {
+                if let Some(FrameKind::Table(rows)) = self.frames.last_mut().map(|f| &mut f.kind) {
+                    rows.push(Vec::new());
+                }
+            }
+            "td" | "th" => self.push(FrameKind::Cell, name),
+            "img" => {
+                let src = attributes.get("src").cloned().unwrap_or_default();
+                if !src.is_empty() {
+                    let alt = attributes.get("alt").cloned().unwrap_or_default();
+                    let markdown = format!("![{}]({})", escape_markdown(&alt), link_target(&src));
+                    self.out().push_str(&markdown);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                } else {
+                    self.newline();
+                }
+                return;
+            }
+            "p" => {
+                self.blank_line();
+                return;
+            }
+            "div" | "tr" | "section" | "article" | "header" | "footer" => {
+                self.newline();
+                return;
+            }
+            _ => {}
+        }
+
+        // Close the innermost frame opened by this tag, along with any frames
+        // left open inside it by malformed markup
+        let Some(position) = self.frames.iter().rposition(|frame| frame.tag == name) else {
+            return;
+        };
+        if position == 0 {
+            return;
+        }
+        while self.frames.len() > position {
+            let frame = self.frames.pop().expect("frame");
+            self.close(frame);
+        }
+    }
+
+    fn close(&mut self, frame: Frame) {
+        let content = frame.out;
+
+        match frame.kind {
+            FrameKind::Root => {}
+            FrameKind::Emphasis(marker) => {
+                let markdown = wrap_inline(&content, marker);
+                self.out().push_str(&markdown);
+            }
+            FrameKind::Code => {
+                let markdown = wrap_code(&content);
+                self.out().push_str(&markdown);
+            }
+            FrameKind::Link(href) => {
+                let text = content.trim();
+                let markdown = if href.is_empty()
+                    || href.starts_with('#')
+                    || href.starts_with("javascript:")
+                {
+                    text.to_string()
+                } else if text.is_empty() {
+                    format!("<{}>", href)
+                } else {
+                    format!("[{}]({})", text, link_target(&href))
+                };
+                self.out().push_str(&markdown);
+            }
+            FrameKind::Heading(level) => {
+                let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    let markdown = format!("{} {}", "#".repeat(level), text);
+                    self.out().push_str(&markdown);
+                }
+                self.blank_line();
+            }
+            FrameKind::Blockquote => {
+                let quoted: Vec<String> = content
+                    .trim()
+                    .lines()
+                    .map(|line| {
+                        if line.is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {}", line)
+                        }
+                    })
+                    .collect();
+                self.out().push_str(&quoted.join("\n"));
+                self.blank_line();
+            }
+            FrameKind::ListItem => {
+                let marker = match self.lists.last_mut() {
+                    Some((true, number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                let indent = " ".repeat(marker.len());
+
+                let mut item = String::new();
+                for (i, line) in content.trim().lines().enumerate() {
+                    if i == 0 {
+                        item.push_str(&marker);
+                        item.push_str(line);
+                    } else if line.trim().is_empty() {
+                        item.push('\n');
+                        continue;
+                    } else {
+                        item.push('\n');
+                        item.push_str(&indent);
+                        item.push_str(line);
+                    }
+                }
+                if item.is_empty() {
+                    item = marker.trim_end().to_string();
+                }
+
+                self.newline();
+                self.out().push_str(&item);
+                self.newline();
+            }
+            FrameKind::Pre => {
+                let code = content.trim_matches('\n');
+                let fence = "`".repeat(longest_backtick_run(code).max(2) + 1);
+                let markdown = format!("{}\n{}\n{}", fence, code, fence);
+                self.out().push_str(&markdown);
+                self.blank_line();
+            }
+            FrameKind::Cell => {
+                let cell = content
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .replace('|', "\\|");
+                if let Some(FrameKind::Table(rows)) = self.frames.last_mut().map(|f| &mut f.kind) {
+                    match rows.last_mut() {
+                        Some(row) => row.push(cell),
+                        None => rows.push(vec![cell]),
+                    }
+                }
+            }
+            FrameKind::Table(rows) => {
+                let markdown = render_table(&rows);
+                self.out().push_str(&markdown);
+                self.blank_line();
+            }
+        }
+    }
+
+    fn finish(mut self) -> String {
+        while self.frames.len() > 1 {
+            let frame = self.frames.pop().expect("frame");
+            self.close(frame);
+        }
+
+        // Drop whitespace-only lines left by things like <div><br/></div>
+        // and never leave more than one blank line in a row
+        let mut result = String::new();
+        let mut blank = false;
+        for line in self.frames[0].out.trim().lines() {
+            if line.trim().is_empty() {
+                blank = true;
+                continue;
+            }
+            if !result.is_empty() {
+                result.push_str(if blank { "\n\n" } else { "\n" });
+            }
+            result.push_str(line);
+            blank = false;
+        }
+
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// Wraps inline content in a marker, keeping surrounding spaces outside of it.
+fn wrap_inline(content: &str, marker: &str) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return content.to_string();
+    }
+
+    let (leading, trailing) = outer_spaces(content);
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+// A space for each end of the content that has whitespace
+fn outer_spaces(content: &str) -> (&'static str, &'static str) {
+    let space = |has: bool| if has { " " } else { "" };
+    (
+        space(content.starts_with(char::is_whitespace)),
+        space(content.ends_with(char::is_whitespace)),
+    )
+}
+
+/// Wraps inline code in more backticks than it contains, with spaces when it
+/// starts or ends with one.
+fn wrap_code(content: &str) -> String {
+    let code = content.trim();
+    if code.is_empty() {
+        return content.to_string();
+    }
+
+    let fence = "`".repeat(longest_backtick_run(code) + 1);
+    let padding = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    let (leading, trailing) = outer_spaces(content);
+    format!(
+        "{}{}{}{}{}{}{}",
+        leading, fence, padding, code, padding, fence, trailing
+    )
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0)
+}
+
+fn render_table(rows: &[Vec<String>]) -> String {
+    let rows: Vec<&Vec<String>> = rows.iter().filter(|row| !row.is_empty()).collect();
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let line = |row: &Vec<String>| {
+        let mut cells = row.clone();
+        cells.resize(columns, String::new());
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut table = vec![line(rows[0]), format!("|{}", " --- |".repeat(columns))];
+    table.extend(rows[1..].iter().map(|row| line(row)));
+    table.join("\n")
+}
+
+/// Wraps link targets with spaces or parentheses in angle brackets.
+fn link_target(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::with_capacity(text.len());
+
+    for (i, c) in chars.iter().enumerate() {
+        let needs_escape = match c {
+            '\\' | '*' | '`' | '[' | ']' => true,
+            // Underscores inside words never start emphasis
+            '_' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1);
+                !(before.is_some_and(char::is_alphanumeric)
+                    && after.is_some_and(|c| c.is_alphanumeric()))
+            }
+            _ => false,
+        };
+        if needs_escape {
+            escaped.push('\\');
+        }
+        escaped.push(*c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md(html: &str) -> String {
+        convert(html, |_, _| None)
+    }
+
+    #[test]
+    fn test_inline_formatting_and_links() {
+        assert_eq!(
+            md(
+                "<p>Some <b>bold</b>, <em>italic </em>and <a href=\"https://x.org\">a link</a>.</p>"
+            ),
+            "Some **bold**, *italic* and [a link](https://x.org).\n"
+        );
+        assert_eq!(
+            md("<p>snake_case * star &amp; &lt;tag&gt;</p>"),
+            "snake_case \\* star & <tag>\n"
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let html = "<h1>Title</h1><div>line one</div><div>line two</div><div><br/></div><hr/>\
+                    <blockquote><p>quoted</p></blockquote><pre>fn main() {\n    x();\n}</pre>";
+        assert_eq!(
+            md(html),
+            "# Title\n\nline one\nline two\n\n---\n\n> quoted\n\n```\nfn main() {\n    x();\n}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_code_keeps_backslashes() {
+        assert_eq!(
+            md("<pre>C:\\tmp\\x\n```</pre>"),
+            "````\nC:\\tmp\\x\n```\n````\n"
+        );
+        assert_eq!(
+            md("<p>Run <code>C:\\tmp\\x</code> or <code>`a`*</code></p>"),
+            "Run `C:\\tmp\\x` or `` `a`* ``\n"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let html = "<ul><li>one</li><li>two<ol><li>a</li><li>b</li></ol></li></ul><p>after</p>";
+        assert_eq!(md(html), "- one\n- two\n  1. a\n  2. b\n\nafter\n");
+    }
+
+    #[test]
+    fn test_table() {
+        let html = "<table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>2 | 3</td></tr></table>";
+        assert_eq!(md(html), "| A | B |\n| --- | --- |\n| 1 | 2 \\| 3 |\n");
+    }
+
+    #[test]
+    fn test_skips_head_and_comments_and_uses_custom_tags() {
+        let html = "<?xml version=\"1.0\"?><!DOCTYPE en-note><html><head><title>T</title>\
+                    <style>p{}</style></head><body><!-- hidden --><en-note>\
+                    <div><en-todo checked=\"true\"/>done</div>\
+                    <en-media hash=\"abc\" type=\"image/png\"/></en-note></body></html>";
+        let result = convert(html, |name, attributes| match name {
+            "en-todo" => Some(
+                if attributes.get("checked").map(|c| c.as_str()) == Some("true") {
+                    "[x] ".to_string()
+                } else {
+                    "[ ] ".to_string()
+                },
+            ),
+            "en-media" => Some(format!("![](attachments/{}.png)", attributes["hash"])),
+            _ => None,
+        });
+        assert_eq!(result, "[x] done\n![](attachments/abc.png)\n");
+    }
+}
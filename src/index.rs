@@ -0,0 +1,351 @@
+// src/index.rs
+//! A saved index of the notes of a vault: their metadata and the links they
+//! contain. `ncy watch` keeps it current as files change; while a watcher is
+//! running for a vault, `load_vault`, the pickers and the link graph read the
+//! index instead of scanning and parsing every note again.
+use crate::commands::graph::{LinkKind, NoteLink, note_links};
+use crate::metadata::{NoteMeta, load_note, split_frontmatter};
+use crate::utils::config_dir;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local};
+use notemancy_core::notes::utils::list_all_notes_alt;
+use serde_json::{Value as JsonValue, json};
+use std::collections::BTreeMap;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A note in the index, with the links in its body.
+#[derive(Debug, Clone)]
+pub struct IndexedNote {
+    pub meta: NoteMeta,
+    pub links: Vec<NoteLink>,
+}
+
+impl IndexedNote {
+    fn load(vault_path: &Path, note_path: &Path) -> Result<Self> {
+        let meta = load_note(vault_path, note_path)?;
+        let content = fs::read_to_string(note_path)
+            .context(format!("Failed to read note: {}", note_path.display()))?;
+        let links = note_links(split_frontmatter(&content).1);
+        Ok(IndexedNote { meta, links })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut json = self.meta.to_json();
+        json["links"] = self
+            .links
+            .iter()
+            .map(|link| json!({"target": link.target, "kind": link.kind.name()}))
+            .collect();
+        json
+    }
+
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(|v| v.as_str());
+        let strings = |key: &str| -> Option<Vec<String>> {
+            value
+                .get(key)?
+                .as_array()?
+                .iter()
+                .map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        };
+        let date = |key: &str| {
+            DateTime::parse_from_rfc3339(text(key)?)
+                .ok()
+                .map(|date| date.with_timezone(&Local))
+        };
+
+        Some(IndexedNote {
+            meta: NoteMeta {
+                path: PathBuf::from(text("path")?),
+                title: text("title")?.to_string(),
+                project: text("project")?.to_string(),
+                frontmatter: value.get("frontmatter")?.clone(),
+                tags: strings("tags")?,
+                created: date("created")?,
+                modified: date("modified")?,
+            },
+            links: value
+                .get("links")?
+                .as_array()?
+                .iter()
+                .map(|link| {
+                    Some(NoteLink {
+                        target: link.get("target")?.as_str()?.to_string(),
+                        kind: LinkKind::parse(link.get("kind")?.as_str()?)?,
+                    })
+                })
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+/// What happened to a note when the index was brought up to date.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Created,
+    Changed,
+    Deleted,
+}
+
+impl Change {
+    pub fn name(self) -> &'static str {
+        match self {
+            Change::Created => "created",
+            Change::Changed => "changed",
+            Change::Deleted => "deleted",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NoteIndex {
+    vault_path: PathBuf,
+    notes: BTreeMap<PathBuf, IndexedNote>,
+}
+
+impl NoteIndex {
+    /// Indexes every note of a vault.
+    pub fn build(vault_path: &Path) -> Result<Self> {
+        let mut notes = BTreeMap::new();
+        for note_path in list_notes(vault_path, vault_path)? {
+            let note = IndexedNote::load(vault_path, &note_path)?;
+            notes.insert(note_path, note);
+        }
+
+        Ok(NoteIndex {
+            vault_path: vault_path.to_path_buf(),
+            notes,
+        })
+    }
+
+    /// Loads the saved index of a vault, but only while `ncy watch` is
+    /// running for it. Otherwise the index may be out of date.
+    pub fn load_live(vault_path: &Path) -> Option<Self> {
+        if !watcher_running(vault_path) {
+            return None;
+        }
+
+        let content = fs::read_to_string(index_file(vault_path).ok()?).ok()?;
+        let value: JsonValue = serde_json::from_str(&content).ok()?;
+        let notes = value
+            .get("notes")?
+            .as_array()?
+            .iter()
+            .map(|note| IndexedNote::from_json(note).map(|n| (n.meta.path.clone(), n)))
+            .collect::<Option<BTreeMap<_, _>>>()?;
+
+        Some(NoteIndex {
+            vault_path: vault_path.to_path_buf(),
+            notes,
+        })
+    }
+
+    /// The live index of a vault, or a new one built by scanning the vault
+    /// when no watcher keeps it current.
+    pub fn load_or_build(vault_path: &Path) -> Result<Self> {
+        match NoteIndex::load_live(vault_path) {
+            Some(index) => Ok(index),
+            None => NoteIndex::build(vault_path),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = index_file(&self.vault_path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create index directory")?;
+        }
+
+        let notes: Vec<JsonValue> = self.notes.values().map(IndexedNote::to_json).collect();
+        let content = json!({
+            "vault": self.vault_path.to_string_lossy(),
+            "notes": notes,
+        });
+
+        // Write to a temporary file first so readers never see half an index
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, content.to_string()).context("Failed to write note index")?;
+        fs::rename(&temporary, &path).context("Failed to write note index")
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = &IndexedNote> {
+        self.notes.values()
+    }
+
+    pub fn into_notes(self) -> Vec<NoteMeta> {
+        self.notes.into_values().map(|note| note.meta).collect()
+    }
+
+    /// Brings the index up to date for a path that changed on disk: a note,
+    /// or a directory that was created, moved or deleted. Returns the notes
+    /// that changed.
+    pub fn refresh(&mut self, path: &Path) -> Result<Vec<(Change, PathBuf)>> {
+        let mut changes = Vec::new();
+
+        if path.is_dir() {
+            for note_path in list_notes(&self.vault_path, path)? {
+                if let Some(change) = self.refresh_note(&note_path)? {
+                    changes.push((change, note_path));
+                }
+            }
+        } else if is_note(&self.vault_path, path) {
+            if let Some(change) = self.refresh_note(path)? {
+                changes.push((change, path.to_path_buf()));
+            }
+        } else if !path.exists() {
+            // A deleted or moved-away directory takes its notes with it
+            let gone: Vec<PathBuf> = self
+                .notes
+                .keys()
+                .filter(|note| note.starts_with(path))
+                .cloned()
+                .collect();
+            for note in gone {
+                self.notes.remove(&note);
+                changes.push((Change::Deleted, note));
+            }
+        }
+
+        Ok(changes)
+    }
+
+    fn refresh_note(&mut self, note_path: &Path) -> Result<Option<Change>> {
+        if !note_path.is_file() {
+            return Ok(self.notes.remove(note_path).map(|_| Change::Deleted));
+        }
+
+        let note = IndexedNote::load(&self.vault_path, note_path)?;
+        let change = match self.notes.get(note_path) {
+            None => Some(Change::Created),
+            Some(old) if old.to_json() != note.to_json() => Some(Change::Changed),
+            Some(_) => None,
+        };
+        self.notes.insert(note_path.to_path_buf(), note);
+        Ok(change)
+    }
+}
+
+/// Whether a path is a note the index should hold: a markdown file in the
+/// vault, outside hidden directories such as `.git`.
+pub fn is_note(vault_path: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(vault_path) else {
+        return false;
+    };
+    relative.extension().is_some_and(|ext| ext == "md")
+        && !relative
+            .components()
+            .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+// Lists the notes below a directory of the vault
+fn list_notes(vault_path: &Path, dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(list_all_notes_alt(dir, false)?
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|path| is_note(vault_path, path))
+        .collect())
+}
+
+// Index files live in the config directory, named after a hash of the vault
+// path so that renaming a vault in config.yaml does not confuse them
+fn index_file(vault_path: &Path) -> Result<PathBuf> {
+    Ok(index_dir()?.join(format!("{}.json", vault_key(vault_path))))
+}
+
+fn lock_file(vault_path: &Path) -> Result<PathBuf> {
+    Ok(index_dir()?.join(format!("{}.lock", vault_key(vault_path))))
+}
+
+fn index_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("index"))
+}
+
+fn vault_key(vault_path: &Path) -> String {
+    let path = vault_path
+        .canonicalize()
+        .unwrap_or_else(|_| vault_path.to_path_buf());
+    format!("{:x}", md5::compute(path.to_string_lossy().as_bytes()))
+}
+
+/// Records that this process is watching a vault, by holding a lock on a
+/// file until the returned guard is dropped. The lock goes away with the
+/// process, so a killed watcher leaves nothing behind. The index left by an
+/// earlier watcher is removed, so readers scan the vault until a new one is
+/// saved.
+pub fn claim_watch(vault_path: &Path) -> Result<WatchGuard> {
+    let path = lock_file(vault_path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create index directory")?;
+    }
+    let file = File::create(&path).context("Failed to record the running watcher")?;
+    match file.try_lock() {
+        Ok(()) => {
+            // The index of an earlier watcher is out of date, and readers
+            // trust any index while the lock is held
+            match fs::remove_file(index_file(vault_path)?) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(e).context("Failed to remove the old note index");
+                }
+                _ => {}
+            }
+            Ok(WatchGuard { _file: file })
+        }
+        Err(TryLockError::WouldBlock) => Err(anyhow!(
+            "Another ncy watch is already running for {}",
+            vault_path.display()
+        )),
+        Err(TryLockError::Error(e)) => Err(e).context("Failed to record the running watcher"),
+    }
+}
+
+pub struct WatchGuard {
+    _file: File,
+}
+
+/// Whether a watcher is running for a vault: some process holds the lock
+/// that `claim_watch` takes.
+pub fn watcher_running(vault_path: &Path) -> bool {
+    let Some(file) = lock_file(vault_path)
+        .ok()
+        .and_then(|path| File::open(path).ok())
+    else {
+        return false;
+    };
+    matches!(file.try_lock_shared(), Err(TryLockError::WouldBlock))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_note() {
+        let vault = Path::new("/home/me/.notes");
+        assert!(is_note(vault, Path::new("/home/me/.notes/projects/a.md")));
+        assert!(!is_note(vault, Path::new("/home/me/.notes/.git/a.md")));
+        assert!(!is_note(vault, Path::new("/home/me/.notes/image.png")));
+        assert!(!is_note(vault, Path::new("/elsewhere/a.md")));
+    }
+
+    #[test]
+    fn test_indexed_note_round_trip() {
+        let note = IndexedNote {
+            meta: NoteMeta {
+                path: PathBuf::from("/vault/p/a.md"),
+                title: "A".to_string(),
+                project: "p".to_string(),
+                frontmatter: json!({"title": "A", "tags": ["x"]}),
+                tags: vec!["x".to_string()],
+                created: Local::now(),
+                modified: Local::now(),
+            },
+            links: note_links("[[b]] ![[pic.png]] [c](c.md)"),
+        };
+
+        let parsed = IndexedNote::from_json(&note.to_json()).unwrap();
+        assert_eq!(parsed.to_json(), note.to_json());
+        assert!(IndexedNote::from_json(&json!({"title": "A"})).is_none());
+    }
+}
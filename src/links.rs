@@ -0,0 +1,499 @@
+// src/links.rs
+use pulldown_cmark::{CowStr, Event, Parser, Tag};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A `[[target#heading|alias]]` link (or `![[...]]` embed) found in a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiLink {
+    pub target: String,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+    pub embed: bool,
+    /// Byte range of the whole link, including the brackets and the `!`
+    pub start: usize,
+    pub end: usize,
+}
+
+impl WikiLink {
+    /// Text shown for the link: the alias, or the target as written.
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.target)
+    }
+
+    /// The link written out again, pointing to another target.
+    pub fn with_target(&self, target: &str) -> String {
+        let mut link = String::new();
+        if self.embed {
+            link.push('!');
+        }
+        link.push_str("[[");
+        link.push_str(target);
+        if let Some(heading) = &self.heading {
+            link.push('#');
+            link.push_str(heading);
+        }
+        if let Some(alias) = &self.alias {
+            link.push('|');
+            link.push_str(alias);
+        }
+        link.push_str("]]");
+        link
+    }
+}
+
+/// Finds every wikilink in markdown text, skipping fenced code blocks and
+/// inline code spans.
+pub fn find_wikilinks(text: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+
+        match (fence, marker) {
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                continue;
+            }
+            (Some(open), Some(marker)) if open == marker => {
+                fence = None;
+                continue;
+            }
+            (Some(_), _) => continue,
+            (None, None) => {}
+        }
+
+        find_in_line(line, line_start, &mut links);
+    }
+
+    links
+}
+
+fn find_in_line(line: &str, line_start: usize, links: &mut Vec<WikiLink>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        // Skip inline code spans, which end at a run of the same number of backticks
+        if bytes[i] == b'`' {
+            let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+            let ticks = &line[i..i + run];
+            match line[i + run..].find(ticks) {
+                Some(close) => i += run + close + run,
+                None => i += run,
+            }
+            continue;
+        }
+
+        // Compare bytes, as i may fall inside a multi-byte character
+        if bytes[i..].starts_with(b"[[")
+            && let Some(close) = line[i + 2..].find("]]")
+        {
+            let inner = &line[i + 2..i + 2 + close];
+            let embed = i > 0 && bytes[i - 1] == b'!';
+            let start = if embed { i - 1 } else { i };
+            let end = i + 2 + close + 2;
+
+            if let Some(link) = parse_inner(inner, embed, line_start + start, line_start + end) {
+                links.push(link);
+            }
+            i = end;
+            continue;
+        }
+
+        i += 1;
+    }
+}
+
+fn parse_inner(inner: &str, embed: bool, start: usize, end: usize) -> Option<WikiLink> {
+    if inner.contains('[') {
+        return None;
+    }
+
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target, Some(alias.trim().to_string())),
+        None => (inner, None),
+    };
+    let (target, heading) = match target.split_once('#') {
+        Some((target, heading)) => (target, Some(heading.trim().to_string())),
+        None => (target, None),
+    };
+
+    let target = target.trim();
+    if target.is_empty() && heading.is_none() {
+        return None;
+    }
+
+    Some(WikiLink {
+        target: target.to_string(),
+        heading,
+        alias: alias.filter(|alias| !alias.is_empty()),
+        embed,
+        start,
+        end,
+    })
+}
+
+/// Replaces every wikilink in the text with the string returned for it.
+pub fn replace_wikilinks<F>(text: &str, mut replace: F) -> String
+where
+    F: FnMut(&WikiLink) -> String,
+{
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for link in find_wikilinks(text) {
+        result.push_str(&text[last..link.start]);
+        result.push_str(&replace(&link));
+        last = link.end;
+    }
+    result.push_str(&text[last..]);
+
+    result
+}
+
+/// Finds the targets of markdown links that point inside the vault, such as
+/// `[text](../notes/other.md#plan)`. URLs, mail links and links to a heading
+/// of the same note are skipped; fragments are dropped and `%20`-style escapes
+/// decoded, so `docs/my%20note.md#top` comes back as `docs/my note.md`.
+pub fn find_markdown_links(text: &str) -> Vec<String> {
+    local_targets(Parser::new(text).filter_map(|event| match event {
+        Event::Start(Tag::Link { dest_url, .. }) => Some(dest_url),
+        _ => None,
+    }))
+}
+
+/// Like `find_markdown_links`, for the sources of markdown images such as
+/// `![diagram](attachments/diagram.png)`.
+pub fn find_markdown_images(text: &str) -> Vec<String> {
+    local_targets(Parser::new(text).filter_map(|event| match event {
+        Event::Start(Tag::Image { dest_url, .. }) => Some(dest_url),
+        _ => None,
+    }))
+}
+
+fn local_targets<'a>(urls: impl Iterator<Item = CowStr<'a>>) -> Vec<String> {
+    urls.filter(|url| !url.contains("://") && !url.starts_with("mailto:"))
+        .filter_map(|url| {
+            let path = url.split('#').next().unwrap_or_default();
+            (!path.is_empty()).then(|| decode_path(path))
+        })
+        .collect()
+}
+
+/// Replaces the destination of inline markdown links and images with the
+/// string returned for it, leaving the destination alone on None. Reference
+/// definitions are not touched.
+pub fn replace_markdown_links<F>(text: &str, mut replace: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    for (event, range) in Parser::new(text).into_offset_iter() {
+        let (Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. })) =
+            event
+        else {
+            continue;
+        };
+
+        // The destination follows the last "](" of an inline link, possibly in <>
+        let source = &text[range.clone()];
+        let Some(open) = source.rfind("](") else {
+            continue;
+        };
+        let after = &source[open + 2..];
+        let skipped = after.len() - after.trim_start().trim_start_matches('<').len();
+        let start = range.start + open + 2 + skipped;
+        if dest_url.is_empty() || !text[start..].starts_with(dest_url.as_ref()) {
+            continue;
+        }
+        if let Some(replacement) = replace(&dest_url) {
+            edits.push((start, start + dest_url.len(), replacement));
+        }
+    }
+    edits.sort_by_key(|(start, _, _)| *start);
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end, replacement) in edits {
+        result.push_str(&text[last..start]);
+        result.push_str(&replacement);
+        last = end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// Joins a link target found in a note to the note's directory, giving a path
+/// relative to the vault. Targets starting with '/' are taken from the vault
+/// root; `..` never climbs above it.
+pub fn resolve_relative(from: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        let mut dirs: Vec<&str> = from.split('/').collect();
+        dirs.pop();
+        dirs
+    };
+
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Decodes `%20`-style escapes in a link destination.
+pub fn decode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = path
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Resolves wikilink targets to notes. A target may be a path relative to the
+/// vault (with or without `.md`), a file name or a title, compared without
+/// case. When several notes share a name, the first path in sort order wins
+/// so the result never depends on directory listing order.
+#[derive(Debug, Default)]
+pub struct LinkIndex {
+    by_path: BTreeMap<String, String>,
+    by_name: BTreeMap<String, String>,
+    by_title: BTreeMap<String, String>,
+}
+
+impl LinkIndex {
+    /// Builds the index from `(relative path, title)` pairs.
+    pub fn new<'a, I>(notes: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut notes: Vec<(&str, &str)> = notes.into_iter().collect();
+        notes.sort();
+
+        let mut index = LinkIndex::default();
+        for (path, title) in notes {
+            let key = normalize_target(path);
+            index.by_path.entry(key.clone()).or_insert(path.to_string());
+
+            let name = key.rsplit('/').next().unwrap_or(&key).to_string();
+            index.by_name.entry(name).or_insert(path.to_string());
+            index
+                .by_title
+                .entry(title.trim().to_lowercase())
+                .or_insert(path.to_string());
+        }
+
+        index
+    }
+
+    /// Returns the relative path of the note a target points to.
+    pub fn resolve(&self, target: &str) -> Option<&str> {
+        let key = normalize_target(target);
+        if key.is_empty() {
+            return None;
+        }
+
+        self.by_path
+            .get(&key)
+            .or_else(|| self.by_name.get(&key))
+            .or_else(|| self.by_title.get(&target.trim().to_lowercase()))
+            .map(|path| path.as_str())
+    }
+}
+
+// Lowercases a target and strips "./", leading slashes and the .md extension
+fn normalize_target(target: &str) -> String {
+    let target = target
+        .trim()
+        .trim_start_matches("./")
+        .trim_start_matches('/');
+    let target = target.strip_suffix(".md").unwrap_or(target);
+    target.to_lowercase()
+}
+
+/// Relative URL from one file to another, both given relative to the same
+/// root with '/' separators. Path segments are percent-encoded.
+pub fn relative_url(from: &str, to: &str) -> String {
+    let from_dirs: Vec<&str> = from.split('/').collect();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_parts: Vec<&str> = to.split('/').collect();
+
+    let common = from_dirs
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(to_parts.len() - 1);
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from_dirs.len() - common];
+    parts.extend(
+        to_parts[common..]
+            .iter()
+            .map(|part| encode_path_segment(part)),
+    );
+    parts.join("/")
+}
+
+/// `file://` URI of an absolute path, as used by editors.
+pub fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let segments: Vec<String> = path.split('/').map(encode_path_segment).collect();
+    format!("file://{}", segments.join("/"))
+}
+
+/// Path of a `file://` URI. Other schemes give None.
+pub fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // A host may precede the path, as in file://localhost/home
+    let path = &path[path.find('/')?..];
+    Some(PathBuf::from(decode_path(path)))
+}
+
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::new();
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_wikilinks() {
+        let text = "See [[Alpha]] and ![[img.png]], [[projects/beta#Plan|the plan]].";
+        let links = find_wikilinks(text);
+
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].target, "Alpha");
+        assert_eq!(&text[links[0].start..links[0].end], "[[Alpha]]");
+        assert!(links[1].embed);
+        assert_eq!(&text[links[1].start..links[1].end], "![[img.png]]");
+        assert_eq!(links[2].target, "projects/beta");
+        assert_eq!(links[2].heading.as_deref(), Some("Plan"));
+        assert_eq!(links[2].label(), "the plan");
+
+        let text = "Crème brûlée → [[Désserts]]";
+        assert_eq!(find_wikilinks(text)[0].target, "Désserts");
+    }
+
+    #[test]
+    fn test_find_wikilinks_skips_code() {
+        let text =
+            "`[[inline]]` [[real]]\n```\n[[fenced]]\n```\n~~~md\n[[tilde]]\n~~~\n[[after]]\n";
+        let targets: Vec<String> = find_wikilinks(text)
+            .into_iter()
+            .map(|link| link.target)
+            .collect();
+        assert_eq!(targets, vec!["real", "after"]);
+    }
+
+    #[test]
+    fn test_replace_wikilinks() {
+        let replaced = replace_wikilinks("a [[b]] c [[d|e]]", |link| link.label().to_uppercase());
+        assert_eq!(replaced, "a B c E");
+    }
+
+    #[test]
+    fn test_link_index_resolution() {
+        let index = LinkIndex::new([
+            ("projects/research/alpha.md", "Alpha Project"),
+            ("projects/beta.md", "Beta"),
+            ("archive/beta.md", "Old Beta"),
+        ]);
+
+        assert_eq!(index.resolve("alpha"), Some("projects/research/alpha.md"));
+        assert_eq!(
+            index.resolve("Alpha Project"),
+            Some("projects/research/alpha.md")
+        );
+        assert_eq!(index.resolve("projects/beta.md"), Some("projects/beta.md"));
+        // Ambiguous names resolve to the first path in sort order
+        assert_eq!(index.resolve("BETA"), Some("archive/beta.md"));
+        assert_eq!(index.resolve("missing"), None);
+    }
+
+    #[test]
+    fn test_find_markdown_links() {
+        let text = "[a](other.md) [b](https://x.org/c.md) [c](#top) [d](../my%20note.md#plan)\n\
+                    ![img](pic.png) `[e](code.md)`";
+        assert_eq!(find_markdown_links(text), vec!["other.md", "../my note.md"]);
+        assert_eq!(find_markdown_images(text), vec!["pic.png"]);
+
+        assert_eq!(resolve_relative("a/b/n.md", "../c.md"), "a/c.md");
+        assert_eq!(resolve_relative("a/n.md", "./x/y.md"), "a/x/y.md");
+        assert_eq!(resolve_relative("a/n.md", "/top.md"), "top.md");
+        assert_eq!(resolve_relative("n.md", "../../up.md"), "up.md");
+    }
+
+    #[test]
+    fn test_replace_markdown_links() {
+        let text = "[a](other.md) [![i](pic.png)](<b c.md#x>) [r][ref] `[e](code.md)`\n\n[ref]: other.md\n";
+        let replaced = replace_markdown_links(text, |url| {
+            (!url.starts_with("other")).then(|| format!("new/{}", url))
+        });
+        assert_eq!(
+            replaced,
+            "[a](other.md) [![i](new/pic.png)](<new/b c.md#x>) [r][ref] `[e](code.md)`\n\n[ref]: other.md\n"
+        );
+
+        let link = &find_wikilinks("![[old#Plan|see]]")[0];
+        assert_eq!(link.with_target("archive/old"), "![[archive/old#Plan|see]]");
+    }
+
+    #[test]
+    fn test_relative_url() {
+        assert_eq!(relative_url("a.html", "b.html"), "b.html");
+        assert_eq!(relative_url("x/y/a.html", "x/b.html"), "../b.html");
+        assert_eq!(relative_url("a.html", "x/y/b c.html"), "x/y/b%20c.html");
+        assert_eq!(relative_url("x/a.html", "x/index.html"), "index.html");
+    }
+
+    #[test]
+    fn test_file_uri() {
+        let path = Path::new("/home/me/notes/my note.md");
+        assert_eq!(file_uri(path), "file:///home/me/notes/my%20note.md");
+        assert_eq!(path_from_uri(&file_uri(path)).as_deref(), Some(path));
+        assert_eq!(
+            path_from_uri("file://localhost/tmp/a.md"),
+            Some(PathBuf::from("/tmp/a.md"))
+        );
+        assert_eq!(path_from_uri("untitled:Untitled-1"), None);
+    }
+}
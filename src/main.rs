@@ -1,14 +1,54 @@
 mod commands;
+mod crypto;
+mod embeddings;
+mod git;
+mod history;
+mod html_to_md;
+mod index;
+mod links;
+mod metadata;
+mod picker;
+mod query;
+mod rpc;
+mod schema;
+mod similarity;
 mod utils;
 
-use clap::{App, Arg, SubCommand};
+use anyhow::Result;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use commands::batch::BatchAction;
+use commands::ls::{
+    ListOptions, NoteFilter, parse_date_arg, parse_field_filter, parse_output_format,
+    parse_sort_key,
+};
+use std::env;
+use std::path::Path;
 use std::process;
 
 fn main() {
-    let matches = App::new("ncy")
+    // `ncy @<project>` is not a subcommand clap knows, so it is handled first
+    let args: Vec<String> = env::args().skip(1).collect();
+    match project_shortcut(&args) {
+        Ok(Some((project, use_external))) => {
+            if let Err(e) = commands::open::execute_with_options(use_external, Some(project)) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Application error: {}", e);
+            process::exit(1);
+        }
+    }
+
+    // Kept around so `ncy completions` can generate scripts from it
+    let app = App::new("ncy")
         .version("0.1.0")
         .author("Your Name <your.email@example.com>")
         .about("A CLI PKM (Personal Knowledge Management) tool")
+        .after_help("Run 'ncy @<project>' to pick notes within a project, as in 'ncy @projects/research'.")
         .arg(
             Arg::with_name("external")
                 .short("e")
@@ -37,6 +77,13 @@ fn main() {
                         .help("Create file and print absolute path to stdout (useful for integration with text editors)")
                         .takes_value(false),
                 )
+                .arg(
+                    Arg::with_name("encrypt")
+                        .long("encrypt")
+                        .help("Encrypt the body of the note with a passphrase")
+                        .conflicts_with("external")
+                        .takes_value(false),
+                )
                 .arg(
                     Arg::with_name("args")
                         .help("Note title, project path, and vault in format: 'title @ project/path +vault'")
@@ -70,8 +117,773 @@ fn main() {
                     Arg::with_name("external")
                         .short("e")
                         .long("external")
-                        .help("Print directory path to stdout rather than opening in file explorer")
+                        .help("Use fzf and print directory path to stdout rather than opening in file explorer")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("ls")
+                .about("List notes non-interactively, with optional filters and sorting")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to list (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .args(&note_filter_args())
+                .arg(
+                    Arg::with_name("sort")
+                        .short("s")
+                        .long("sort")
+                        .help("Sort notes by title, modification time or creation date")
+                        .possible_values(&["title", "modified", "mtime", "created"])
+                        .default_value("title"),
+                )
+                .arg(
+                    Arg::with_name("reverse")
+                        .short("r")
+                        .long("reverse")
+                        .help("Reverse the sort order")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("o")
+                        .long("format")
+                        .help("Output titles, absolute paths or JSON")
+                        .possible_values(&["titles", "paths", "json"])
+                        .default_value("titles"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("batch")
+                .visible_alias("b")
+                .about("Pick several notes (Tab to mark) and apply an action to all of them")
+                .arg(
+                    Arg::with_name("external")
+                        .short("e")
+                        .long("external")
+                        .help("Use fzf for picking notes instead of nucleo_picker")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to pick notes from (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .args(&note_filter_args())
+                .arg(
+                    Arg::with_name("action")
+                        .help("Action to apply to the picked notes")
+                        .possible_values(&["open", "paths", "move", "tag", "export"])
+                        .default_value("open"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .help("Project path for 'move', tag for 'tag' or directory for 'export'"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("meta")
+                .about("Read or change the frontmatter of a note, or of every note in a project")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to use (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print frontmatter and values as JSON")
+                        .takes_value(false),
+                )
+                .args(&note_filter_args())
+                .arg(
+                    Arg::with_name("note")
+                        .help("Note path or title, or '@project' for every note under a project ('@' for the whole vault)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("action")
+                        .help("What to do with the frontmatter (shows all of it if omitted)")
+                        .possible_values(&["get", "set", "unset"]),
+                )
+                .arg(Arg::with_name("key").help("Frontmatter key"))
+                .arg(
+                    Arg::with_name("value")
+                        .help("Value for 'set', read as YAML (e.g. 3, true, [a, b])")
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("query")
+                .visible_alias("q")
+                .about("Query notes by frontmatter, tags, path, title and dates")
+                .after_help(
+                    "EXAMPLE:\n    ncy query 'from \"projects\" where status = \"active\" and tags contains \"q4\" sort modified desc'",
+                )
+                .arg(
+                    Arg::with_name("external")
+                        .short("e")
+                        .long("external")
+                        .help("Use fzf for picking notes instead of nucleo_picker")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to query (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("o")
+                        .long("format")
+                        .help("Output a table, titles, absolute paths or JSON")
+                        .possible_values(&["table", "titles", "paths", "json"])
+                        .default_value("table"),
+                )
+                .arg(
+                    Arg::with_name("pick")
+                        .short("p")
+                        .long("pick")
+                        .help("Pick among the results and open them")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .short("n")
+                        .long("name")
+                        .help("Run the saved query with this name")
+                        .takes_value(true)
+                        .conflicts_with("query"),
+                )
+                .arg(
+                    Arg::with_name("save")
+                        .short("s")
+                        .long("save")
+                        .help("Save the query in the vault under this name")
+                        .takes_value(true)
+                        .requires("query"),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .short("l")
+                        .long("list")
+                        .help("List the saved queries")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("delete")
+                        .long("delete")
+                        .help("Delete the saved query with this name")
+                        .takes_value(true),
+                )
+                .arg(Arg::with_name("query").help("The query to run")),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Check notes against the frontmatter schemas of their projects")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to validate (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("project")
+                        .short("p")
+                        .long("project")
+                        .help("Only validate notes under this project path")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print violations as JSON")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("graph")
+                .about("Print the wikilink and markdown-link graph of a vault")
+                .after_help("EXAMPLE:\n    ncy graph --note 'Alpha' --depth 2 | dot -Tsvg > alpha.svg")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to graph (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .args(&note_filter_args())
+                .arg(
+                    Arg::with_name("format")
+                        .short("o")
+                        .long("format")
+                        .help("Output Graphviz DOT, GraphML or JSON")
+                        .possible_values(&["dot", "graphml", "json"])
+                        .default_value("dot"),
+                )
+                .arg(
+                    Arg::with_name("note")
+                        .short("n")
+                        .long("note")
+                        .help("Only include notes linked to or from this note (path or title)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .short("d")
+                        .long("depth")
+                        .help("How many links away from --note to go (default: 1)")
+                        .takes_value(true)
+                        .requires("note"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("related")
+                .about("List the notes most similar in text to a note (works offline)")
+                .arg(
+                    Arg::with_name("external")
+                        .short("e")
+                        .long("external")
+                        .help("Use fzf for picking notes instead of nucleo_picker")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to search (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .short("n")
+                        .long("count")
+                        .help("Number of related notes to list")
+                        .takes_value(true)
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::with_name("append")
+                        .short("a")
+                        .long("append")
+                        .help("Write the suggestions to the note as a 'Related' section of links")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("note")
+                        .help("Note path or title (picks a note if omitted)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("ask")
+                .about("Rank notes by meaning with a local embedding model (set 'embedding_model' in config.yaml)")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to search (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .short("n")
+                        .long("count")
+                        .help("Number of notes to list")
+                        .takes_value(true)
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::with_name("question")
+                        .help("What you are looking for, in plain language")
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Keep the note index of your vaults current as files change, and run hooks")
+                .after_help(
+                    "HOOKS:\n    Commands under 'hooks' in config.yaml run when a note is created, changed or deleted:\n\n        hooks:\n          changed: ncy validate\n\n    They run in the vault directory with NCY_EVENT, NCY_NOTE and NCY_VAULT set.",
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to watch (can be repeated; defaults to every configured vault)")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("no-hooks")
+                        .long("no-hooks")
+                        .help("Do not run the hooks configured in config.yaml")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Answer JSON-RPC requests from editor plugins, keeping notes in memory")
+                .after_help(
+                    "METHODS:\n    listNotes {project?, tags?}, createNote {title, project?}, appendJournal {text},\n    resolveLink {target, from?}, search {query, limit?} and backlinks {note}.\n    Every method takes an optional vault; the default vault is used otherwise.",
+                )
+                .arg(
+                    Arg::with_name("stdio")
+                        .long("stdio")
+                        .help("Read requests from stdin and write replies to stdout")
+                        .required(true)
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lsp")
+                .about("Run a language server for wikilinks: completion, go to definition, backlinks, previews and broken links")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault the edited notes belong to (defaults to the default vault)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("ws")
+                .alias("workspace")
+                .about("Manage workspaces: named sets of notes stored in the vault's workspaces/ folder")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault of the workspace (defaults to the default vault)")
+                        .takes_value(true)
+                        .global(true),
+                )
+                .subcommand(
+                    SubCommand::with_name("new")
+                        .about("Create a workspace, optionally with a saved query from the filter options")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the workspace")
+                                .required(true),
+                        )
+                        .args(&note_filter_args()),
+                )
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Add notes to a workspace (picks them interactively if none are given)")
+                        .arg(
+                            Arg::with_name("external")
+                                .short("e")
+                                .long("external")
+                                .help("Use fzf for picking notes instead of nucleo_picker")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the workspace")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("notes")
+                                .help("Note paths, absolute or relative to the vault")
+                                .multiple(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("rm")
+                        .about("Remove notes from a workspace, or delete the workspace if no notes are given")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the workspace")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("notes")
+                                .help("Note paths, absolute or relative to the vault")
+                                .multiple(true),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").about("List the workspaces of the vault"))
+                .subcommand(
+                    SubCommand::with_name("open")
+                        .about("Open all notes of a workspace in the editor")
+                        .arg(
+                            Arg::with_name("external")
+                                .short("e")
+                                .long("external")
+                                .help("Print the note paths to stdout instead (uses fzf with --pick)")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("pick")
+                                .short("p")
+                                .long("pick")
+                                .help("Pick among the workspace notes instead of opening all of them")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the workspace")
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("commit")
+                .about("Commit all changes in a git-versioned vault")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to commit (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .short("m")
+                        .long("message")
+                        .help("Commit message (generated from the changed notes by default)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("List the committed revisions of a note, or show one of them")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault of the note (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("diff")
+                        .short("d")
+                        .long("diff")
+                        .help("Show the changes made by the revision instead of the note content")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("note")
+                        .help("Note path, absolute or relative to the vault")
+                        .required(true),
+                )
+                .arg(Arg::with_name("revision").help("Revision to show")),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Export the notes of a vault or project as a static site")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to export (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("project")
+                        .short("p")
+                        .long("project")
+                        .help("Only export this project subtree (e.g. 'projects/research')")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Export format")
+                        .possible_values(&["html"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("outdir")
+                        .help("Output directory (must be empty or hold a previous export)")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Import notes from other tools into a vault")
+                .subcommand(import_subcommand(
+                    "obsidian",
+                    "Import an Obsidian vault, converting frontmatter, links and attachments",
+                    "Directory of the Obsidian vault",
+                ))
+                .subcommand(import_subcommand(
+                    "enex",
+                    "Import an Evernote export, keeping dates, tags and attachments",
+                    "The .enex file exported from Evernote",
+                ))
+                .subcommand(import_subcommand(
+                    "html",
+                    "Import HTML files as notes, copying local images",
+                    "An HTML file or a directory of them",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("recent")
+                .about("List the most recently opened or created notes")
+                .arg(
+                    Arg::with_name("count")
+                        .short("n")
+                        .long("count")
+                        .help("Number of notes to list")
+                        .takes_value(true)
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("o")
+                        .long("format")
+                        .help("Output titles, absolute paths or JSON")
+                        .possible_values(&["titles", "paths", "json"])
+                        .default_value("titles"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("-")
+                .about("Reopen the most recently used note")
+                .arg(
+                    Arg::with_name("external")
+                        .short("e")
+                        .long("external")
+                        .help("Print the absolute path of the note instead of opening it")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("shell-init")
+                .about("Print a shell function that changes into the directory of a picked note")
+                .arg(
+                    Arg::with_name("shell")
+                        .help("Shell to generate the function for")
+                        .possible_values(&["bash", "zsh", "sh", "fish"])
+                        .default_value("bash"),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .short("n")
+                        .long("name")
+                        .help("Name of the generated shell function")
+                        .takes_value(true)
+                        .default_value("ncd"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("capture")
+                .about("Append a timestamped item to the vault's inbox note")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to capture to (defaults to the default vault)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("text")
+                        .help("Text to capture, or '-' to read it from stdin")
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("inbox")
+                .about("Go through captured items: turn them into notes, move them to notes or the journal, or discard them")
+                .after_help(
+                    "The inbox is inbox.md at the root of the vault. Set 'inbox' on a vault in config.yaml to use another note:\n\n    vaults:\n      - name: main\n        vault_directory: ~/notes\n        inbox: gtd/inbox.md",
+                )
+                .arg(
+                    Arg::with_name("external")
+                        .short("e")
+                        .long("external")
+                        .help("Use fzf for picking notes")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault whose inbox to go through (defaults to the default vault)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("project")
+                .about("List, create and archive projects (directories of a vault). Run 'ncy @<project>' to pick notes within one")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault of the project (defaults to the default vault)")
+                        .takes_value(true)
+                        .global(true),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("Show the projects as a tree with note counts and when they last changed"),
+                )
+                .subcommand(
+                    SubCommand::with_name("new")
+                        .about("Create a project directory")
+                        .arg(
+                            Arg::with_name("path")
+                                .help("Path of the project, such as projects/research")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("index")
+                                .short("i")
+                                .long("index")
+                                .help("Also create an index note named after the project")
+                                .takes_value(false),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("archive")
+                        .about("Move a project under archive/, updating links to and from its notes")
+                        .arg(
+                            Arg::with_name("path")
+                                .help("Path of the project to archive")
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(encryption_subcommand(
+            "encrypt",
+            "Encrypt the body of a note with a passphrase. The frontmatter stays readable",
+        ))
+        .subcommand(encryption_subcommand(
+            "decrypt",
+            "Decrypt an encrypted note back to plain text",
+        ))
+        .subcommand(
+            SubCommand::with_name("attach")
+                .about("Copy a file into the vault's attachments folder and link it from a note")
+                .after_help(
+                    "Files go to attachments/ at the root of the vault. Set 'attachments' on a vault in config.yaml to use another folder:\n\n    vaults:\n      - name: main\n        vault_directory: ~/notes\n        attachments: assets",
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("File to attach")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("note")
+                        .short("n")
+                        .long("note")
+                        .help("Path or title of the note to add the link to (prints the link if not given)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("embed")
+                        .long("embed")
+                        .help("Embed the file with '![...]' even if it is not an image")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to attach the file to (defaults to the default vault)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("attachments")
+                .about("Find and clean up attachments that no note uses")
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to check (defaults to the default vault)")
+                        .takes_value(true)
+                        .global(true),
+                )
+                .subcommand(
+                    SubCommand::with_name("orphans")
+                        .about("List attachments that no note links to or embeds"),
+                )
+                .subcommand(
+                    SubCommand::with_name("gc")
+                        .about("Move attachments that no note uses to the vault's .trash folder")
+                        .arg(
+                            Arg::with_name("include-encrypted")
+                                .long("include-encrypted")
+                                .help("Collect even though encrypted notes cannot be checked, which may trash attachments only they use"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("agenda")
+                .about("Show open tasks with due or scheduled dates and notes with a due or date field, by day")
+                .after_help(
+                    "Tasks are open checklist items with a date written as 'due:2024-05-01', 'scheduled:2024-05-01', '@due(2024-05-01)', '[due:: 2024-05-01]' or with the Tasks plugin emoji '📅 2024-05-01' and '⏳ 2024-05-01'. Overdue items are those due before today.",
+                )
+                .arg(
+                    Arg::with_name("days")
+                        .short("d")
+                        .long("days")
+                        .help("Number of days to show, starting today")
+                        .takes_value(true)
+                        .default_value("7"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("o")
+                        .long("format")
+                        .help("Output for the terminal or JSON")
+                        .possible_values(&["text", "json"])
+                        .default_value("text"),
+                )
+                .arg(
+                    Arg::with_name("journal")
+                        .short("j")
+                        .long("journal")
+                        .help("Also append today's agenda to today's journal entry")
                         .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("vault")
+                        .short("v")
+                        .long("vault")
+                        .help("Vault to gather the agenda from (defaults to the default vault)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("completions")
+                .about("Print a shell completion script, with vault and project names completed from your config")
+                .after_help(
+                    "SETUP:\n    bash: add 'source <(ncy completions bash)' to ~/.bashrc\n    zsh:  add 'source <(ncy completions zsh)' to ~/.zshrc, after compinit\n    fish: ncy completions fish > ~/.config/fish/completions/ncy.fish",
+                )
+                .arg(
+                    Arg::with_name("shell")
+                        .help("Shell to generate the script for")
+                        .required(true)
+                        .possible_values(&["bash", "zsh", "fish"]),
+                ),
+        );
+    // Added after `app` is kept, so the completion scripts do not offer it
+    let matches = app
+        .clone()
+        .subcommand(
+            SubCommand::with_name("__complete")
+                .setting(AppSettings::Hidden)
+                .setting(AppSettings::TrailingVarArg)
+                .about("Print completion candidates for a command line (used by the completion scripts)")
+                .arg(
+                    Arg::with_name("words")
+                        .help("Words of the command line, from 'ncy' up to the word being completed")
+                        .multiple(true)
+                        .allow_hyphen_values(true),
                 ),
         )
         .get_matches();
@@ -98,7 +910,11 @@ fn main() {
             // Check if external flag is set
             let use_external = new_matches.is_present("external");
 
-            if let Err(e) = commands::new::execute_with_options(&combined_args, use_external) {
+            if let Err(e) = commands::new::execute_with_options(
+                &combined_args,
+                use_external,
+                new_matches.is_present("encrypt"),
+            ) {
                 eprintln!("Application error: {}", e);
                 process::exit(1);
             }
@@ -129,13 +945,601 @@ fn main() {
                 process::exit(1);
             }
         }
+        ("ls", Some(ls_matches)) => {
+            let result = list_options_from_matches(ls_matches)
+                .and_then(|options| commands::ls::execute(&options));
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("batch", Some(batch_matches)) | ("b", Some(batch_matches)) => {
+            let result = BatchAction::parse(
+                batch_matches.value_of("action").unwrap(),
+                batch_matches.value_of("target"),
+            )
+            .and_then(|action| {
+                let filter = note_filter_from_matches(batch_matches)?;
+                commands::batch::execute(
+                    &action,
+                    batch_matches.value_of("vault"),
+                    &filter,
+                    batch_matches.is_present("external"),
+                )
+            });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("meta", Some(meta_matches)) => {
+            let value: Vec<&str> = meta_matches
+                .values_of("value")
+                .map(|values| values.collect())
+                .unwrap_or_default();
+            let result = commands::meta::MetaAction::parse(
+                meta_matches.value_of("action"),
+                meta_matches.value_of("key"),
+                &value,
+            )
+            .and_then(|action| {
+                commands::meta::execute(
+                    meta_matches.value_of("note").unwrap(),
+                    &action,
+                    meta_matches.value_of("vault"),
+                    note_filter_from_matches(meta_matches)?,
+                    meta_matches.is_present("json"),
+                )
+            });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("query", Some(query_matches)) | ("q", Some(query_matches)) => {
+            let result = if query_matches.is_present("list") {
+                commands::query::list(query_matches.value_of("vault"))
+            } else if let Some(name) = query_matches.value_of("delete") {
+                commands::query::delete(name, query_matches.value_of("vault"))
+            } else {
+                commands::query::QueryFormat::parse(query_matches.value_of("format").unwrap())
+                    .and_then(|format| {
+                        let output = commands::query::QueryOutput {
+                            format,
+                            pick: query_matches.is_present("pick"),
+                            external: query_matches.is_present("external"),
+                        };
+                        commands::query::execute(
+                            query_matches.value_of("query"),
+                            query_matches.value_of("name"),
+                            query_matches.value_of("save"),
+                            query_matches.value_of("vault"),
+                            &output,
+                        )
+                    })
+            };
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("validate", Some(validate_matches)) => {
+            if let Err(e) = commands::validate::execute(
+                validate_matches.value_of("vault"),
+                validate_matches.value_of("project"),
+                validate_matches.is_present("json"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("graph", Some(graph_matches)) => {
+            let result =
+                commands::graph::GraphFormat::parse(graph_matches.value_of("format").unwrap())
+                    .and_then(|format| {
+                        let depth = graph_matches
+                            .value_of("depth")
+                            .map(|depth| depth.parse::<usize>())
+                            .transpose()
+                            .map_err(|_| anyhow::anyhow!("--depth must be a whole number"))?
+                            .unwrap_or(1);
+                        let options = commands::graph::GraphOptions {
+                            format,
+                            filter: note_filter_from_matches(graph_matches)?,
+                            note: graph_matches.value_of("note"),
+                            depth,
+                        };
+                        commands::graph::execute(graph_matches.value_of("vault"), &options)
+                    });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("related", Some(related_matches)) => {
+            let result = related_matches
+                .value_of("count")
+                .unwrap()
+                .parse::<usize>()
+                .map_err(|_| anyhow::anyhow!("Count must be a positive number"))
+                .and_then(|limit| {
+                    let options = commands::related::RelatedOptions {
+                        limit,
+                        append: related_matches.is_present("append"),
+                        external: related_matches.is_present("external")
+                            || matches.is_present("external"),
+                    };
+                    commands::related::execute(
+                        related_matches.value_of("note"),
+                        related_matches.value_of("vault"),
+                        &options,
+                    )
+                });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("ask", Some(ask_matches)) => {
+            let question = ask_matches
+                .values_of("question")
+                .unwrap()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let result = ask_matches
+                .value_of("count")
+                .unwrap()
+                .parse::<usize>()
+                .map_err(|_| anyhow::anyhow!("Count must be a positive number"))
+                .and_then(|limit| {
+                    commands::ask::execute(&question, ask_matches.value_of("vault"), limit)
+                });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("watch", Some(watch_matches)) => {
+            let vaults: Vec<&str> = watch_matches
+                .values_of("vault")
+                .map(|values| values.collect())
+                .unwrap_or_default();
+
+            if let Err(e) = commands::watch::execute(&vaults, !watch_matches.is_present("no-hooks"))
+            {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("serve", Some(_)) => {
+            if let Err(e) = commands::serve::execute() {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("lsp", Some(lsp_matches)) => {
+            if let Err(e) = commands::lsp::execute(lsp_matches.value_of("vault")) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("capture", Some(capture_matches)) => {
+            let text = values_joined(capture_matches, "text");
+            if let Err(e) = commands::inbox::capture(&text, capture_matches.value_of("vault")) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("inbox", Some(inbox_matches)) => {
+            let external = inbox_matches.is_present("external") || matches.is_present("external");
+            if let Err(e) = commands::inbox::execute(inbox_matches.value_of("vault"), external) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("completions", Some(completions_matches)) => {
+            let shell = completions_matches.value_of("shell").unwrap();
+            if let Err(e) = commands::completions::execute(shell, app) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("__complete", Some(complete_matches)) => {
+            let words: Vec<&str> = complete_matches
+                .values_of("words")
+                .map(|values| values.collect())
+                .unwrap_or_default();
+            // Completion must never print errors into the shell
+            let _ = commands::completions::complete(&words);
+        }
+        ("encrypt", Some(encrypt_matches)) => {
+            if let Err(e) = commands::encrypt::encrypt(
+                encrypt_matches.value_of("note").unwrap(),
+                encrypt_matches.value_of("vault"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("decrypt", Some(decrypt_matches)) => {
+            if let Err(e) = commands::encrypt::decrypt(
+                decrypt_matches.value_of("note").unwrap(),
+                decrypt_matches.value_of("vault"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("agenda", Some(agenda_matches)) => {
+            let result = agenda_matches
+                .value_of("days")
+                .unwrap()
+                .parse::<i64>()
+                .map_err(|_| anyhow::anyhow!("Days must be a positive number"))
+                .and_then(|days| {
+                    commands::agenda::execute(
+                        days,
+                        agenda_matches.value_of("vault"),
+                        agenda_matches.value_of("format") == Some("json"),
+                        agenda_matches.is_present("journal"),
+                    )
+                });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("attach", Some(attach_matches)) => {
+            if let Err(e) = commands::attachments::attach(
+                Path::new(attach_matches.value_of("file").unwrap()),
+                attach_matches.value_of("note"),
+                attach_matches.value_of("vault"),
+                attach_matches.is_present("embed"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("attachments", Some(attachments_matches)) => {
+            let vault = attachments_matches.value_of("vault");
+            let result = match attachments_matches.subcommand() {
+                ("gc", Some(m)) => commands::attachments::gc(
+                    m.value_of("vault").or(vault),
+                    m.is_present("include-encrypted"),
+                ),
+                ("orphans", Some(m)) => {
+                    commands::attachments::orphans(m.value_of("vault").or(vault))
+                }
+                _ => commands::attachments::orphans(vault),
+            };
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("project", Some(project_matches)) => {
+            if let Err(e) = run_project_command(project_matches) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("ws", Some(ws_matches)) => {
+            if let Err(e) = run_workspace_command(ws_matches) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("commit", Some(commit_matches)) => {
+            if let Err(e) = commands::commit::execute(
+                commit_matches.value_of("vault"),
+                commit_matches.value_of("message"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("history", Some(history_matches)) => {
+            if let Err(e) = commands::commit::history(
+                history_matches.value_of("note").unwrap(),
+                history_matches.value_of("revision"),
+                history_matches.value_of("vault"),
+                history_matches.is_present("diff"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("export", Some(export_matches)) => {
+            if let Err(e) = commands::export::execute(
+                export_matches.value_of("format").unwrap(),
+                Path::new(export_matches.value_of("outdir").unwrap()),
+                export_matches.value_of("vault"),
+                export_matches.value_of("project"),
+            ) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("import", Some(import_matches)) => {
+            let result = match import_matches.subcommand() {
+                ("obsidian", Some(m)) => commands::import::obsidian(
+                    Path::new(m.value_of("source").unwrap()),
+                    &values_joined(m, "target"),
+                    m.is_present("dry-run"),
+                ),
+                ("enex", Some(m)) => commands::import::enex(
+                    Path::new(m.value_of("source").unwrap()),
+                    &values_joined(m, "target"),
+                    m.is_present("dry-run"),
+                ),
+                ("html", Some(m)) => commands::import::html(
+                    Path::new(m.value_of("source").unwrap()),
+                    &values_joined(m, "target"),
+                    m.is_present("dry-run"),
+                ),
+                _ => Err(anyhow::anyhow!(
+                    "Specify what to import from. See 'ncy import --help'"
+                )),
+            };
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("recent", Some(recent_matches)) => {
+            let result = recent_matches
+                .value_of("count")
+                .unwrap()
+                .parse::<usize>()
+                .map_err(|_| anyhow::anyhow!("Count must be a positive number"))
+                .and_then(|count| {
+                    let format = parse_output_format(recent_matches.value_of("format").unwrap())?;
+                    commands::recent::execute(count, format)
+                });
+
+            if let Err(e) = result {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("-", Some(last_matches)) => {
+            // Either the subcommand flag or the global one selects external mode
+            let external = last_matches.is_present("external") || matches.is_present("external");
+
+            if let Err(e) = commands::recent::reopen_last(external) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
+        ("shell-init", Some(shell_init_matches)) => {
+            let shell = shell_init_matches.value_of("shell").unwrap();
+            let function_name = shell_init_matches.value_of("name").unwrap();
+
+            if let Err(e) = commands::shell_init::execute(shell, function_name) {
+                eprintln!("Application error: {}", e);
+                process::exit(1);
+            }
+        }
         // Default action when no subcommand is specified
         _ => {
             let use_external = matches.is_present("external");
-            if let Err(e) = commands::open::execute_with_options(use_external) {
+            if let Err(e) = commands::open::execute_with_options(use_external, None) {
                 eprintln!("Application error: {}", e);
                 process::exit(1);
             }
         }
     }
 }
+
+// The project of `ncy [-e] @<project> [-e]`, and whether fzf is used. Other
+// command lines are left to clap
+fn project_shortcut(args: &[String]) -> Result<Option<(&str, bool)>> {
+    let is_external = |arg: &String| arg == "-e" || arg == "--external";
+    let Some(position) = args.iter().position(|arg| !is_external(arg)) else {
+        return Ok(None);
+    };
+    let Some(project) = args[position].strip_prefix('@') else {
+        return Ok(None);
+    };
+
+    if project.trim().is_empty() {
+        return Err(anyhow::anyhow!(
+            "A project name is required after '@', as in 'ncy @projects/research'"
+        ));
+    }
+    if let Some(extra) = args[position + 1..].iter().find(|arg| !is_external(arg)) {
+        return Err(anyhow::anyhow!(
+            "Unexpected argument '{}' after '@{}'",
+            extra,
+            project
+        ));
+    }
+    Ok(Some((&args[position], args.iter().any(is_external))))
+}
+
+// `encrypt` and `decrypt` both take a note and a vault
+fn encryption_subcommand<'a, 'b>(name: &'a str, about: &'b str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .about(about)
+        .after_help(
+            "Opening an encrypted note asks for its passphrase, decrypts it into a private temporary file for $EDITOR and encrypts it again afterwards. Only the body is encrypted: keep secrets out of the frontmatter.",
+        )
+        .arg(
+            Arg::with_name("note")
+                .help("Path or title of the note")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("vault")
+                .short("v")
+                .long("vault")
+                .help("Vault of the note (defaults to the default vault)")
+                .takes_value(true),
+        )
+}
+
+// Every importer takes a source, a destination and --dry-run
+fn import_subcommand<'a, 'b>(name: &'a str, about: &'b str, source: &'b str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .about(about)
+        .arg(
+            Arg::with_name("dry-run")
+                .short("n")
+                .long("dry-run")
+                .help("Only print the report, without writing anything")
+                .takes_value(false),
+        )
+        .arg(Arg::with_name("source").help(source).required(true))
+        .arg(
+            Arg::with_name("target")
+                .help("Destination in format: '@ project/path +vault' (both optional)")
+                .multiple(true),
+        )
+}
+
+// Arguments shared by every command that works on a filtered set of notes
+fn note_filter_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("project")
+            .short("p")
+            .long("project")
+            .help("Only include notes under this project path (e.g. 'projects/research')")
+            .takes_value(true),
+        Arg::with_name("tag")
+            .short("t")
+            .long("tag")
+            .help("Only include notes with this tag (can be repeated)")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("field")
+            .short("f")
+            .long("field")
+            .help("Only include notes whose frontmatter has 'key' or 'key=value' (can be repeated)")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("created-after")
+            .long("created-after")
+            .help("Only include notes created on or after this date (YYYY-MM-DD)")
+            .takes_value(true),
+        Arg::with_name("created-before")
+            .long("created-before")
+            .help("Only include notes created before this date (YYYY-MM-DD)")
+            .takes_value(true),
+        Arg::with_name("modified-after")
+            .long("modified-after")
+            .help("Only include notes modified on or after this date (YYYY-MM-DD)")
+            .takes_value(true),
+        Arg::with_name("modified-before")
+            .long("modified-before")
+            .help("Only include notes modified before this date (YYYY-MM-DD)")
+            .takes_value(true),
+    ]
+}
+
+fn note_filter_from_matches(matches: &ArgMatches) -> Result<NoteFilter> {
+    let fields = matches
+        .values_of("field")
+        .map(|values| values.map(parse_field_filter).collect::<Result<Vec<_>>>())
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(NoteFilter {
+        project: matches.value_of("project").map(|p| p.to_string()),
+        tags: matches
+            .values_of("tag")
+            .map(|values| {
+                values
+                    .map(|t| t.trim_start_matches('#').to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        fields,
+        created_after: parse_date_arg(matches.value_of("created-after"))?,
+        created_before: parse_date_arg(matches.value_of("created-before"))?,
+        modified_after: parse_date_arg(matches.value_of("modified-after"))?,
+        modified_before: parse_date_arg(matches.value_of("modified-before"))?,
+    })
+}
+
+// Joins the values of a multi-value argument with spaces, like `new` does
+fn values_joined(matches: &ArgMatches, name: &str) -> String {
+    matches
+        .values_of(name)
+        .map(|values| values.collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
+
+fn run_project_command(matches: &ArgMatches) -> Result<()> {
+    let vault = matches.value_of("vault");
+
+    match matches.subcommand() {
+        ("new", Some(m)) => commands::project::create(
+            m.value_of("path").unwrap(),
+            m.value_of("vault").or(vault),
+            m.is_present("index"),
+        ),
+        ("archive", Some(m)) => {
+            commands::project::archive(m.value_of("path").unwrap(), m.value_of("vault").or(vault))
+        }
+        ("list", Some(m)) => commands::project::list(m.value_of("vault").or(vault)),
+        _ => commands::project::list(vault),
+    }
+}
+
+fn run_workspace_command<'a>(matches: &'a ArgMatches<'a>) -> Result<()> {
+    let vault = matches.value_of("vault");
+    let notes = |m: &'a ArgMatches| -> Vec<&'a str> {
+        m.values_of("notes")
+            .map(|values| values.collect())
+            .unwrap_or_default()
+    };
+
+    match matches.subcommand() {
+        ("new", Some(m)) => commands::workspace::new(
+            m.value_of("name").unwrap(),
+            m.value_of("vault").or(vault),
+            note_filter_from_matches(m)?,
+        ),
+        ("add", Some(m)) => commands::workspace::add(
+            m.value_of("name").unwrap(),
+            &notes(m),
+            m.value_of("vault").or(vault),
+            m.is_present("external"),
+        ),
+        ("rm", Some(m)) => commands::workspace::rm(
+            m.value_of("name").unwrap(),
+            &notes(m),
+            m.value_of("vault").or(vault),
+        ),
+        ("list", Some(m)) => commands::workspace::list(m.value_of("vault").or(vault)),
+        ("open", Some(m)) => commands::workspace::open(
+            m.value_of("name").unwrap(),
+            m.value_of("vault").or(vault),
+            m.is_present("pick"),
+            m.is_present("external"),
+        ),
+        _ => commands::workspace::list(vault),
+    }
+}
+
+fn list_options_from_matches(matches: &ArgMatches) -> Result<ListOptions> {
+    Ok(ListOptions {
+        vault: matches.value_of("vault").map(|v| v.to_string()),
+        filter: note_filter_from_matches(matches)?,
+        sort: parse_sort_key(matches.value_of("sort").unwrap())?,
+        reverse: matches.is_present("reverse"),
+        format: parse_output_format(matches.value_of("format").unwrap())?,
+    })
+}
@@ -0,0 +1,420 @@
+// src/metadata.rs
+use crate::index::NoteIndex;
+use crate::utils::yaml_to_json;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use notemancy_core::notes::utils::{get_file_path, get_title, list_all_notes_alt};
+use serde_json::{Map, Value as JsonValue, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Everything ncy knows about a single note without opening it in an editor.
+#[derive(Debug, Clone)]
+pub struct NoteMeta {
+    pub path: PathBuf,
+    pub title: String,
+    /// Directory of the note relative to the vault root, using '/' separators
+    pub project: String,
+    pub frontmatter: JsonValue,
+    pub tags: Vec<String>,
+    pub created: DateTime<Local>,
+    pub modified: DateTime<Local>,
+}
+
+impl NoteMeta {
+    pub fn to_json(&self) -> JsonValue {
+        json!({
+            "title": self.title,
+            "path": self.path.to_string_lossy(),
+            "project": self.project,
+            "tags": self.tags,
+            "created": self.created.to_rfc3339(),
+            "modified": self.modified.to_rfc3339(),
+            "frontmatter": self.frontmatter,
+        })
+    }
+}
+
+/// Splits note content into its YAML frontmatter (without the `---` fences)
+/// and the body that follows the closing fence.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let after_open = if let Some(rest) = content.strip_prefix("---\n") {
+        rest
+    } else if let Some(rest) = content.strip_prefix("---\r\n") {
+        rest
+    } else {
+        return (None, content);
+    };
+
+    let yaml_start = content.len() - after_open.len();
+    let mut offset = 0;
+
+    for line in after_open.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == "---" {
+            let yaml = &content[yaml_start..yaml_start + offset];
+            let body = &content[yaml_start + offset + line.len()..];
+            return (Some(yaml), body);
+        }
+        offset += line.len();
+    }
+
+    // No closing fence, so there is no frontmatter
+    (None, content)
+}
+
+/// Parses the frontmatter of a note into a JSON object. Notes without
+/// frontmatter yield an empty object.
+pub fn parse_frontmatter(content: &str) -> Result<JsonValue> {
+    let yaml = match split_frontmatter(content).0 {
+        Some(yaml) if !yaml.trim().is_empty() => yaml,
+        _ => return Ok(JsonValue::Object(Map::new())),
+    };
+
+    let yaml_value = serde_yaml::from_str::<serde_yaml::Value>(yaml)
+        .context("Failed to parse note frontmatter")?;
+
+    match yaml_to_json(yaml_value) {
+        JsonValue::Object(map) => Ok(JsonValue::Object(map)),
+        _ => Ok(JsonValue::Object(Map::new())),
+    }
+}
+
+/// Rewrites the frontmatter of a note with `update` and returns the new
+/// content. The body after the frontmatter is kept byte-for-byte; notes
+/// without frontmatter get one.
+pub fn update_frontmatter<F>(content: &str, update: F) -> Result<String>
+where
+    F: FnOnce(&mut serde_yaml::Mapping) -> Result<()>,
+{
+    let (yaml, body) = split_frontmatter(content);
+
+    let mut mapping = match yaml {
+        Some(yaml) if !yaml.trim().is_empty() => {
+            match serde_yaml::from_str::<serde_yaml::Value>(yaml)
+                .context("Failed to parse note frontmatter")?
+            {
+                serde_yaml::Value::Mapping(mapping) => mapping,
+                _ => return Err(anyhow::anyhow!("Note frontmatter is not a mapping")),
+            }
+        }
+        _ => serde_yaml::Mapping::new(),
+    };
+
+    update(&mut mapping)?;
+
+    let mut new_content = String::from("---\n");
+    if !mapping.is_empty() {
+        let yaml = serde_yaml::to_string(&mapping).context("Failed to serialize frontmatter")?;
+        new_content.push_str(yaml.strip_prefix("---\n").unwrap_or(&yaml).trim_end());
+        new_content.push('\n');
+    }
+    new_content.push_str("---\n");
+    new_content.push_str(body);
+
+    Ok(new_content)
+}
+
+/// Adds a tag to the `tags` field of a frontmatter mapping, converting a
+/// string field to a list. Returns false if the note already had the tag.
+pub fn add_tag(mapping: &mut serde_yaml::Mapping, tag: &str) -> bool {
+    let key = serde_yaml::Value::String("tags".to_string());
+    let tag = tag.trim().trim_start_matches('#').to_string();
+
+    let mut tags = frontmatter_tags(
+        &mapping
+            .get(&key)
+            .map(|tags| serde_json::json!({ "tags": yaml_to_json(tags.clone()) }))
+            .unwrap_or_default(),
+    );
+
+    if tags.iter().any(|t| t == &tag) {
+        return false;
+    }
+    tags.push(tag);
+
+    mapping.insert(
+        key,
+        serde_yaml::Value::Sequence(tags.into_iter().map(serde_yaml::Value::String).collect()),
+    );
+    true
+}
+
+/// Reads the `tags` frontmatter field, which may be a list or a comma or
+/// space separated string. Leading '#' characters are stripped.
+pub fn frontmatter_tags(frontmatter: &JsonValue) -> Vec<String> {
+    let raw: Vec<String> = match frontmatter.get("tags") {
+        Some(JsonValue::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                JsonValue::String(s) => Some(s.clone()),
+                JsonValue::Null => None,
+                other => Some(other.to_string()),
+            })
+            .collect(),
+        Some(JsonValue::String(s)) => s.split([',', ' ']).map(|tag| tag.to_string()).collect(),
+        _ => Vec::new(),
+    };
+
+    raw.iter()
+        .map(|tag| tag.trim().trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Parses the date formats that show up in frontmatter and on the command line.
+/// Date-only values are interpreted as local midnight.
+pub fn parse_date(value: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.with_timezone(&Local));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Local.from_local_datetime(&naive).earliest();
+        }
+    }
+
+    // MM-DD-YYYY is the format used for journal entries
+    for format in ["%Y-%m-%d", "%m-%d-%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Local
+                .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+                .earliest();
+        }
+    }
+
+    None
+}
+
+/// Returns the path of a note relative to the vault, using '/' separators.
+pub fn relative_path(vault_path: &Path, note_path: &Path) -> String {
+    let relative = note_path.strip_prefix(vault_path).unwrap_or(note_path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Turns a note argument (absolute, relative to the current directory or
+/// relative to the vault) into a path relative to the vault.
+pub fn resolve_note_path(vault_path: &Path, note: &str) -> Result<String> {
+    let candidates = [PathBuf::from(note), vault_path.join(note)];
+    let path = candidates
+        .iter()
+        .find(|path| path.is_file())
+        .context(format!("Note not found: {}", note))?;
+
+    let path = path
+        .canonicalize()
+        .context(format!("Failed to resolve note path: {}", note))?;
+    let vault_path = vault_path
+        .canonicalize()
+        .context("Failed to resolve vault directory")?;
+
+    if !path.starts_with(&vault_path) {
+        return Err(anyhow!("Note is not inside the vault: {}", note));
+    }
+
+    Ok(relative_path(&vault_path, &path))
+}
+
+/// Finds a note by path (absolute, relative to the current directory or to
+/// the vault) or by title.
+pub fn find_note(vault_path: &Path, note: &str) -> Result<PathBuf> {
+    if let Ok(relative) = resolve_note_path(vault_path, note) {
+        return Ok(vault_path.join(relative));
+    }
+
+    get_file_path(note, vault_path)
+        .map(PathBuf::from)
+        .map_err(|_| anyhow!("Note not found: {}", note))
+}
+
+pub fn load_note(vault_path: &Path, note_path: &Path) -> Result<NoteMeta> {
+    let content = fs::read_to_string(note_path)
+        .context(format!("Failed to read note: {}", note_path.display()))?;
+
+    // A note with broken frontmatter is still listed, just without metadata
+    let frontmatter = parse_frontmatter(&content).unwrap_or(JsonValue::Object(Map::new()));
+    let tags = frontmatter_tags(&frontmatter);
+    let title = get_title(note_path)?;
+
+    let project = note_path
+        .parent()
+        .map(|parent| relative_path(vault_path, parent))
+        .unwrap_or_default();
+
+    let file_meta = fs::metadata(note_path).context(format!(
+        "Failed to read metadata for: {}",
+        note_path.display()
+    ))?;
+    let modified: DateTime<Local> = file_meta
+        .modified()
+        .map(DateTime::from)
+        .unwrap_or_else(|_| Local::now());
+
+    // Prefer the creation date recorded in frontmatter over the filesystem
+    let created = ["created", "date"]
+        .iter()
+        .filter_map(|key| frontmatter.get(*key).and_then(|v| v.as_str()))
+        .find_map(parse_date)
+        .or_else(|| file_meta.created().ok().map(DateTime::from))
+        .unwrap_or(modified);
+
+    Ok(NoteMeta {
+        path: note_path.to_path_buf(),
+        title,
+        project,
+        frontmatter,
+        tags,
+        created,
+        modified,
+    })
+}
+
+/// Lists the projects of a vault: every directory below it outside hidden
+/// directories, as sorted paths relative to the vault.
+pub fn list_projects(vault_path: &Path) -> Vec<String> {
+    let mut projects = Vec::new();
+    collect_projects(vault_path, "", &mut projects);
+    projects.sort();
+    projects
+}
+
+fn collect_projects(dir: &Path, relative: &str, projects: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || !entry.path().is_dir() {
+            continue;
+        }
+        let project = if relative.is_empty() {
+            name
+        } else {
+            format!("{}/{}", relative, name)
+        };
+        collect_projects(&entry.path(), &project, projects);
+        projects.push(project);
+    }
+}
+
+/// Loads every note of a vault, from the index when `ncy watch` keeps one
+/// current and by scanning the vault otherwise.
+pub fn load_vault(vault_path: &Path) -> Result<Vec<NoteMeta>> {
+    if let Some(index) = NoteIndex::load_live(vault_path) {
+        return Ok(index.into_notes());
+    }
+    scan_vault(vault_path)
+}
+
+/// Loads every note of a vault from disk.
+pub fn scan_vault(vault_path: &Path) -> Result<Vec<NoteMeta>> {
+    let all_notes = list_all_notes_alt(vault_path, false)?;
+
+    let mut notes = Vec::with_capacity(all_notes.len());
+    for note_path in all_notes {
+        notes.push(load_note(vault_path, Path::new(&note_path))?);
+    }
+
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let content = "---\ntitle: Hello\ntags: [a, b]\n---\nBody text\n";
+        let (yaml, body) = split_frontmatter(content);
+        assert_eq!(yaml, Some("title: Hello\ntags: [a, b]\n"));
+        assert_eq!(body, "Body text\n");
+    }
+
+    #[test]
+    fn test_split_frontmatter_without_frontmatter() {
+        let content = "# Heading\n---\nnot frontmatter\n";
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn test_split_frontmatter_without_closing_fence() {
+        let content = "---\ntitle: Hello\n";
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn test_split_frontmatter_with_crlf() {
+        let content = "---\r\ntitle: Hello\r\n---\r\nBody\r\n";
+        let (yaml, body) = split_frontmatter(content);
+        assert_eq!(yaml, Some("title: Hello\r\n"));
+        assert_eq!(body, "Body\r\n");
+    }
+
+    #[test]
+    fn test_frontmatter_tags() {
+        let list = parse_frontmatter("---\ntags: [work, '#q4']\n---\n").unwrap();
+        assert_eq!(frontmatter_tags(&list), vec!["work", "q4"]);
+
+        let string = parse_frontmatter("---\ntags: work, q4 ideas\n---\n").unwrap();
+        assert_eq!(frontmatter_tags(&string), vec!["work", "q4", "ideas"]);
+    }
+
+    #[test]
+    fn test_update_frontmatter_keeps_body() {
+        let content = "---\ntitle: Hello\n---\n\nBody  with trailing spaces  \n\n";
+        let updated = update_frontmatter(content, |mapping| {
+            mapping.insert("status".into(), "archived".into());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            updated,
+            "---\ntitle: Hello\nstatus: archived\n---\n\nBody  with trailing spaces  \n\n"
+        );
+    }
+
+    #[test]
+    fn test_update_frontmatter_without_frontmatter() {
+        let updated = update_frontmatter("Just text\n", |mapping| {
+            mapping.insert("title".into(), "Text".into());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(updated, "---\ntitle: Text\n---\nJust text\n");
+    }
+
+    #[test]
+    fn test_add_tag() {
+        let mut mapping: serde_yaml::Mapping = serde_yaml::from_str("tags: work, q4").unwrap();
+        assert!(add_tag(&mut mapping, "#ideas"));
+        assert!(!add_tag(&mut mapping, "q4"));
+
+        let tags = parse_frontmatter(
+            &update_frontmatter("", |m| {
+                *m = mapping;
+                Ok(())
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(frontmatter_tags(&tags), vec!["work", "q4", "ideas"]);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = parse_date("2024-03-01").unwrap();
+        assert_eq!(date.format("%Y-%m-%d").to_string(), "2024-03-01");
+
+        let journal = parse_date("03-01-2024").unwrap();
+        assert_eq!(journal, date);
+
+        assert!(parse_date("2024-03-01T10:30:00+00:00").is_some());
+        assert!(parse_date("yesterday").is_none());
+    }
+}
@@ -0,0 +1,412 @@
+// src/picker.rs
+use crate::history;
+use crate::index::NoteIndex;
+use anyhow::{Context, Result, anyhow};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use notemancy_core::notes::utils::{get_title, list_all_notes_alt};
+use nucleo_picker::event::{
+    Event, EventSource, RecvError, StdinReader, keybind_default, keybind_no_multi,
+};
+use nucleo_picker::{Picker, error::PickError, render::DisplayRenderer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::mem;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Key that creates a note from the typed query, in both nucleo and fzf.
+pub const CREATE_KEY: &str = "ctrl-x";
+
+/// A note offered by the picker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteChoice {
+    pub title: String,
+    pub path: String,
+}
+
+/// What the user did in the picker.
+#[derive(Debug, PartialEq)]
+pub enum Picked {
+    /// One or more notes were selected
+    Notes(Vec<NoteChoice>),
+    /// The create key, or Enter with nothing matching, was pressed with this query
+    Create(String),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PickOptions {
+    /// Use fzf instead of nucleo_picker
+    pub external: bool,
+    /// Allow marking several notes with Tab
+    pub multi: bool,
+    /// Allow creating a note from the query
+    pub allow_create: bool,
+}
+
+/// Lists every note of a vault as picker choices, most frecently used first.
+pub fn vault_choices(vault_path: &Path) -> Result<Vec<NoteChoice>> {
+    // Titles come from the index while `ncy watch` keeps it current
+    let indexed: Option<BTreeMap<String, String>> = NoteIndex::load_live(vault_path).map(|index| {
+        index
+            .notes()
+            .map(|note| {
+                let path = note.meta.path.to_string_lossy().to_string();
+                (path, note.meta.title.clone())
+            })
+            .collect()
+    });
+
+    let mut all_notes = match &indexed {
+        Some(titles) => titles.keys().cloned().collect(),
+        None => list_all_notes_alt(vault_path, false)?,
+    };
+    history::sort_by_frecency(&mut all_notes);
+
+    let mut choices = Vec::with_capacity(all_notes.len());
+    for note_path in all_notes {
+        let title = match &indexed {
+            Some(titles) => titles[&note_path].clone(),
+            None => get_title(Path::new(&note_path))?,
+        };
+        choices.push(NoteChoice {
+            title,
+            path: note_path,
+        });
+    }
+
+    Ok(choices)
+}
+
+/// Lets the user pick notes with nucleo_picker, or with fzf in external mode.
+/// Cancelling the picker is an error.
+pub fn pick_notes(choices: &[NoteChoice], options: PickOptions) -> Result<Picked> {
+    if choices.is_empty() {
+        return Err(anyhow!("No notes to pick from"));
+    }
+
+    let picked = if options.external {
+        pick_with_fzf(choices, options)?
+    } else {
+        pick_with_nucleo(choices, options)?
+    };
+
+    match picked {
+        Picked::Create(query) if query.trim().is_empty() => Err(anyhow!("No note selected")),
+        Picked::Create(query) => Ok(Picked::Create(query.trim().to_string())),
+        Picked::Notes(notes) if notes.is_empty() => Err(anyhow!("No note selected")),
+        notes => Ok(notes),
+    }
+}
+
+/// A picker row, showing the title of the note.
+struct Candidate {
+    index: usize,
+    title: String,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)
+    }
+}
+
+fn pick_with_nucleo(choices: &[NoteChoice], options: PickOptions) -> Result<Picked> {
+    if !io::stderr().is_terminal() {
+        return Err(anyhow!("The note picker requires an interactive terminal"));
+    }
+
+    let mut picker = Picker::new(DisplayRenderer);
+    let injector = picker.injector();
+    for (index, choice) in choices.iter().enumerate() {
+        injector.push(Candidate {
+            index,
+            title: choice.title.clone(),
+        });
+    }
+
+    let events = PickerEvents::new(StdinReader::new(keybind_for(options)), options);
+    let mut writer = BufWriter::new(io::stderr());
+    // Like fzf, Enter picks the notes queued with Tab, or the highlighted one if none are
+    let picked: Result<Vec<usize>, _> = if options.multi {
+        picker
+            .pick_multi_with_io(events, &mut writer)
+            .map(|selection| selection.iter().map(|candidate| candidate.index).collect())
+    } else {
+        picker
+            .pick_with_io(events, &mut writer)
+            .map(|candidate| candidate.map(|c| c.index).into_iter().collect())
+    };
+
+    match picked {
+        Ok(indices) => Ok(Picked::Notes(
+            indices.into_iter().map(|i| choices[i].clone()).collect(),
+        )),
+        Err(PickError::Aborted(CreateNote)) => Ok(Picked::Create(picker.query().to_string())),
+        Err(e) => Err(io::Error::from(e.factor().unwrap_err()).into()),
+    }
+}
+
+fn pick_with_fzf(choices: &[NoteChoice], options: PickOptions) -> Result<Picked> {
+    // Each line is "title<TAB>path"; only the title is shown and matched
+    let mut lines = String::new();
+    for choice in choices {
+        lines.push_str(&choice.title);
+        lines.push('\t');
+        lines.push_str(&choice.path);
+        lines.push('\n');
+    }
+
+    // Set up fzf command with full screen options
+    let mut command = Command::new("fzf");
+    command
+        .arg("--no-mouse")
+        .arg("--border")
+        .arg("--delimiter=\t")
+        .arg("--with-nth=1")
+        // Print the query so a note can be created from it
+        .arg("--print-query");
+
+    if options.multi {
+        command.arg("--multi");
+    }
+    if options.allow_create {
+        command.arg(format!("--expect={}", CREATE_KEY)).arg(format!(
+            "--header=Enter on no match or {} creates a note",
+            CREATE_KEY
+        ));
+    }
+
+    let mut fzf_cmd = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null()) // Suppress stderr
+        .spawn()
+        .context("Failed to spawn fzf process. Is fzf installed?")?;
+
+    // Feed the notes to fzf
+    if let Some(mut stdin) = fzf_cmd.stdin.take() {
+        stdin
+            .write_all(lines.as_bytes())
+            .context("Failed to write to fzf stdin")?;
+    }
+
+    let output = fzf_cmd
+        .wait_with_output()
+        .context("Failed to get output from fzf")?;
+
+    // Exit code 1 means nothing matched the query; anything else is a cancel or error
+    if !output.status.success() && output.status.code() != Some(1) {
+        // User cancelled (ESC, Ctrl+C, etc.)
+        return Err(anyhow!("No note selected"));
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Failed to parse fzf output")?;
+    let (query, key, selected) = parse_fzf_output(&stdout, options.allow_create);
+
+    // Create a note from the query when nothing matched or the create key was used
+    if options.allow_create && (key == CREATE_KEY || selected.is_empty()) {
+        return Ok(Picked::Create(query));
+    }
+
+    Ok(Picked::Notes(selected))
+}
+
+/// Splits the output of `fzf --print-query [--expect=...]` into the query,
+/// the key that accepted it (empty for Enter) and the selected notes.
+fn parse_fzf_output(output: &str, has_expect: bool) -> (String, String, Vec<NoteChoice>) {
+    let mut lines = output.lines();
+    let query = lines.next().unwrap_or_default().trim().to_string();
+    let key = if has_expect {
+        lines.next().unwrap_or_default().trim().to_string()
+    } else {
+        String::new()
+    };
+
+    let selected = lines
+        .filter_map(|line| {
+            let (title, path) = line.rsplit_once('\t')?;
+            Some(NoteChoice {
+                title: title.to_string(),
+                path: path.to_string(),
+            })
+        })
+        .collect();
+
+    (query, key, selected)
+}
+
+/// Asks the picker to close so a note is created from its query.
+#[derive(Debug, PartialEq)]
+struct CreateNote;
+
+type Keybind = fn(KeyEvent) -> Option<Event<CreateNote>>;
+
+/// The nucleo keybindings, where Tab queues notes only when several can be
+/// picked and `ctrl-x` creates a note only when that is allowed.
+fn keybind_for(options: PickOptions) -> Keybind {
+    match (options.allow_create, options.multi) {
+        (true, true) => |key| create_event(&key).or_else(|| keybind_default(key)),
+        (true, false) => |key| create_event(&key).or_else(|| keybind_no_multi(key)),
+        (false, true) => keybind_default,
+        (false, false) => keybind_no_multi,
+    }
+}
+
+fn create_event(key_event: &KeyEvent) -> Option<Event<CreateNote>> {
+    match key_event {
+        KeyEvent {
+            kind: KeyEventKind::Press,
+            modifiers: KeyModifiers::CONTROL,
+            code: KeyCode::Char('x'),
+            ..
+        } => Some(Event::Abort(CreateNote)),
+        _ => None,
+    }
+}
+
+/// Passes on the events of the picker, and closes it to create a note when
+/// Enter is pressed with nothing matching the query.
+struct PickerEvents<E> {
+    events: E,
+    allow_create: bool,
+    // Set when Enter was passed on. The picker closes when Enter selects a
+    // note, so being asked for another event means nothing matched
+    select_sent: bool,
+}
+
+impl<E> PickerEvents<E> {
+    fn new(events: E, options: PickOptions) -> Self {
+        Self {
+            events,
+            allow_create: options.allow_create,
+            select_sent: false,
+        }
+    }
+}
+
+impl<E: EventSource<AbortErr = CreateNote>> EventSource for PickerEvents<E> {
+    type AbortErr = CreateNote;
+
+    fn recv_timeout(&mut self, duration: Duration) -> Result<Event<CreateNote>, RecvError> {
+        if mem::take(&mut self.select_sent) {
+            return Ok(Event::Abort(CreateNote));
+        }
+
+        let event = self.events.recv_timeout(duration)?;
+        if self.allow_create && matches!(event, Event::Select) {
+            self.select_sent = true;
+        }
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nucleo_picker::event::PromptEvent;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_parse_fzf_output_with_selection() {
+        let (query, key, selected) = parse_fzf_output("proj\n\nProject Plan\t/v/plan.md\n", true);
+        assert_eq!(query, "proj");
+        assert_eq!(key, "");
+        assert_eq!(
+            selected,
+            vec![NoteChoice {
+                title: "Project Plan".to_string(),
+                path: "/v/plan.md".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_fzf_output_without_match() {
+        let (query, key, selected) = parse_fzf_output("Idea @ inbox +work\n\n", true);
+        assert_eq!(query, "Idea @ inbox +work");
+        assert_eq!(key, "");
+        assert!(selected.is_empty());
+    }
+
+    #[test]
+    fn test_parse_fzf_output_with_create_key() {
+        let (query, key, _) = parse_fzf_output("Plan\nctrl-x\nProject Plan\t/v/plan.md\n", true);
+        assert_eq!(query, "Plan");
+        assert_eq!(key, CREATE_KEY);
+    }
+
+    #[test]
+    fn test_parse_fzf_output_with_multiple_selections() {
+        let output = "\nA\t/v/a.md\nB\twith tab\t/v/b.md\n";
+        let (_, _, selected) = parse_fzf_output(output, false);
+        let paths: Vec<&str> = selected.iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(paths, vec!["/v/a.md", "/v/b.md"]);
+        assert_eq!(selected[1].title, "B\twith tab");
+    }
+
+    #[test]
+    fn test_enter_without_match_creates_note() {
+        let (sender, receiver) = mpsc::channel();
+        let options = PickOptions {
+            allow_create: true,
+            ..Default::default()
+        };
+        let mut events = PickerEvents::new(receiver, options);
+
+        sender
+            .send(Event::Prompt(PromptEvent::Insert('x')))
+            .unwrap();
+        sender.send(Event::Select).unwrap();
+        assert!(matches!(
+            events.recv_timeout(Duration::ZERO),
+            Ok(Event::Prompt(_))
+        ));
+        assert!(matches!(
+            events.recv_timeout(Duration::ZERO),
+            Ok(Event::Select)
+        ));
+        // Asked again after Enter: the picker found nothing to select
+        assert!(matches!(
+            events.recv_timeout(Duration::ZERO),
+            Ok(Event::Abort(CreateNote))
+        ));
+        assert!(matches!(
+            events.recv_timeout(Duration::ZERO),
+            Err(RecvError::Timeout)
+        ));
+
+        // Without creating, Enter on no match leaves the picker open
+        let (sender, receiver) = mpsc::channel();
+        let mut events = PickerEvents::new(receiver, PickOptions::default());
+        sender.send(Event::Select).unwrap();
+        assert!(matches!(
+            events.recv_timeout(Duration::ZERO),
+            Ok(Event::Select)
+        ));
+        assert!(matches!(
+            events.recv_timeout(Duration::ZERO),
+            Err(RecvError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_keybindings_follow_options() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        let create = key(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        let tab = key(KeyCode::Tab, KeyModifiers::NONE);
+
+        let both = keybind_for(PickOptions {
+            multi: true,
+            allow_create: true,
+            ..Default::default()
+        });
+        assert!(matches!(both(create), Some(Event::Abort(CreateNote))));
+        assert!(matches!(both(tab), Some(Event::MatchList(_))));
+
+        let single = keybind_for(PickOptions::default());
+        assert!(single(create).is_none());
+        assert!(single(tab).is_none());
+    }
+}
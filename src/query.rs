@@ -0,0 +1,784 @@
+// src/query.rs
+//! A small query language over notes, in the spirit of Dataview:
+//!
+//! ```text
+//! table status, due from "projects" or #work
+//! where status = "active" and (tags contains "q4" or not archived)
+//! sort modified desc limit 10
+//! ```
+//!
+//! Every clause is optional. Fields are `title`, `path`, `project`, `tags`,
+//! `created`, `modified` and any frontmatter key (`fm.<key>` reaches keys
+//! shadowed by those names). Text comparisons ignore case. Dates can be
+//! written as strings or relative to `today`/`now`, e.g. `today - 7d`.
+use crate::commands::ls::{normalize_project, project_matches};
+use crate::metadata::{NoteMeta, parse_date};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Extra columns for table output
+    pub columns: Vec<String>,
+    /// Notes must come from one of these; empty means the whole vault
+    pub sources: Vec<Source>,
+    pub filter: Option<Expr>,
+    /// Fields to sort by, with `true` for descending order
+    pub sort: Vec<(String, bool)>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Project(String),
+    Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// A bare field is true when it is set to anything but false or empty
+    Truthy(String),
+    Compare(String, Op, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Date(DateTime<Local>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn from_json(json: &JsonValue) -> Value {
+        match json {
+            JsonValue::Null => Value::Null,
+            JsonValue::Bool(b) => Value::Bool(*b),
+            JsonValue::Number(n) => n.as_f64().map_or(Value::Null, Value::Number),
+            JsonValue::String(s) => Value::Text(s.clone()),
+            JsonValue::Array(items) => Value::List(items.iter().map(Value::from_json).collect()),
+            JsonValue::Object(_) => Value::Text(json.to_string()),
+        }
+    }
+
+    /// Formats the value for a table cell.
+    pub fn display(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Text(s) => s.clone(),
+            Value::Date(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+            Value::List(items) => items
+                .iter()
+                .map(Value::display)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null | Value::Bool(false) => false,
+            Value::Text(s) => !s.is_empty(),
+            Value::List(items) => !items.is_empty(),
+            _ => true,
+        }
+    }
+
+    fn as_date(&self) -> Option<DateTime<Local>> {
+        match self {
+            Value::Date(date) => Some(*date),
+            Value::Text(text) => parse_date(text),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the value of a field for a note. `path` is the note's path
+/// relative to the vault.
+pub fn field_value(note: &NoteMeta, path: &str, field: &str) -> Value {
+    match field {
+        "title" => Value::Text(note.title.clone()),
+        "path" => Value::Text(path.to_string()),
+        "project" => Value::Text(note.project.clone()),
+        "tags" => Value::List(note.tags.iter().cloned().map(Value::Text).collect()),
+        "created" => Value::Date(note.created),
+        "modified" => Value::Date(note.modified),
+        _ => {
+            let key = field.strip_prefix("fm.").unwrap_or(field);
+            note.frontmatter
+                .get(key)
+                .map_or(Value::Null, Value::from_json)
+        }
+    }
+}
+
+impl Query {
+    pub fn matches(&self, note: &NoteMeta, path: &str) -> bool {
+        let from_source = self.sources.is_empty()
+            || self.sources.iter().any(|source| match source {
+                Source::Project(project) => project_matches(&note.project, project),
+                Source::Tag(tag) => note.tags.iter().any(|t| tag_matches(t, tag)),
+            });
+
+        from_source
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.evaluate(note, path))
+    }
+
+    /// Orders notes, given with their relative paths, by the sort clause.
+    /// Missing values sort last; ties keep path order.
+    pub fn sort(&self, notes: &mut [(NoteMeta, String)]) {
+        notes.sort_by(|(a, a_path), (b, b_path)| {
+            for (field, descending) in &self.sort {
+                let a_value = field_value(a, a_path, field);
+                let b_value = field_value(b, b_path, field);
+
+                let ordering = match (&a_value, &b_value) {
+                    (Value::Null, Value::Null) => Ordering::Equal,
+                    (Value::Null, _) => Ordering::Greater,
+                    (_, Value::Null) => Ordering::Less,
+                    _ => {
+                        let ordering = compare(&a_value, &b_value).unwrap_or(Ordering::Equal);
+                        if *descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    }
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            a_path.cmp(b_path)
+        });
+    }
+}
+
+impl Expr {
+    pub fn evaluate(&self, note: &NoteMeta, path: &str) -> bool {
+        match self {
+            Expr::And(a, b) => a.evaluate(note, path) && b.evaluate(note, path),
+            Expr::Or(a, b) => a.evaluate(note, path) || b.evaluate(note, path),
+            Expr::Not(a) => !a.evaluate(note, path),
+            Expr::Truthy(field) => field_value(note, path, field).is_truthy(),
+            Expr::Compare(field, op, expected) => {
+                let actual = field_value(note, path, field);
+                if field == "tags"
+                    && let Value::Text(tag) = expected
+                {
+                    // Tags match without their '#', like `ls --tag`
+                    let tag = Value::Text(tag.trim_start_matches('#').to_string());
+                    return apply_op(&actual, *op, &tag);
+                }
+                apply_op(&actual, *op, expected)
+            }
+        }
+    }
+}
+
+fn apply_op(actual: &Value, op: Op, expected: &Value) -> bool {
+    match op {
+        Op::Eq => equals(actual, expected),
+        Op::Ne => !equals(actual, expected),
+        Op::Contains => match actual {
+            Value::List(items) => items.iter().any(|item| equals(item, expected)),
+            Value::Text(text) => text
+                .to_lowercase()
+                .contains(&expected.display().to_lowercase()),
+            _ => false,
+        },
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+            let Some(ordering) = compare(actual, expected) else {
+                return false;
+            };
+            match op {
+                Op::Lt => ordering == Ordering::Less,
+                Op::Le => ordering != Ordering::Greater,
+                Op::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }
+        }
+    }
+}
+
+fn equals(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Null, Value::Null) => true,
+        (Value::Null, _) | (_, Value::Null) => false,
+        // A list equals a value it contains, so `tags = "q4"` works
+        (Value::List(items), other) if !matches!(other, Value::List(_)) => {
+            items.iter().any(|item| equals(item, other))
+        }
+        _ => compare(actual, expected) == Some(Ordering::Equal),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Date(_), _) | (_, Value::Date(_)) => Some(a.as_date()?.cmp(&b.as_date()?)),
+        (Value::Text(a), Value::Number(b)) => a.trim().parse::<f64>().ok()?.partial_cmp(b),
+        (Value::Number(a), Value::Text(b)) => a.partial_cmp(&b.trim().parse::<f64>().ok()?),
+        (Value::Text(a), Value::Text(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+        (Value::Text(a), Value::Bool(b)) | (Value::Bool(b), Value::Text(a)) => {
+            a.parse::<bool>().ok().map(|a| a.cmp(b))
+        }
+        _ => None,
+    }
+}
+
+fn tag_matches(tag: &str, wanted: &str) -> bool {
+    let wanted = wanted.trim_start_matches('#');
+    tag.eq_ignore_ascii_case(wanted)
+        || tag
+            .to_lowercase()
+            .starts_with(&format!("{}/", wanted.to_lowercase()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(f64),
+    Duration(Duration),
+    Tag(String),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("'{}'", word),
+            Token::Text(text) => format!("\"{}\"", text),
+            Token::Number(n) => n.to_string(),
+            Token::Duration(d) => format!("{}d", d.num_days()),
+            Token::Tag(tag) => format!("#{}", tag),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let is_word_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '/');
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(anyhow!("Unterminated string in query")),
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&quote) if quote == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&other) => {
+                        text.push(other);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Text(text));
+        } else if c == '#' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+            if i == start {
+                return Err(anyhow!("Expected a tag name after '#'"));
+            }
+            tokens.push(Token::Tag(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number: f64 = chars[start..i]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|_| anyhow!("Invalid number in query"))?;
+
+            // 7d, 2w and 12h are durations
+            let unit_start = i;
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            let unit: String = chars[unit_start..i].iter().collect();
+            let duration = match unit.as_str() {
+                "" => {
+                    tokens.push(Token::Number(number));
+                    continue;
+                }
+                "d" => Duration::days(number as i64),
+                "w" => Duration::weeks(number as i64),
+                "h" => Duration::hours(number as i64),
+                _ => return Err(anyhow!("Unknown duration unit '{}'. Use d, w or h", unit)),
+            };
+            tokens.push(Token::Duration(duration));
+        } else if is_word_char(c) {
+            let start = i;
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else {
+            let next = chars.get(i + 1).copied();
+            let symbol = match (c, next) {
+                ('!', Some('=')) => "!=",
+                ('<', Some('=')) => "<=",
+                ('>', Some('=')) => ">=",
+                ('=', Some('=')) => "==",
+                ('=', _) => "=",
+                ('<', _) => "<",
+                ('>', _) => ">",
+                ('(', _) => "(",
+                (')', _) => ")",
+                (',', _) => ",",
+                ('+', _) => "+",
+                _ => return Err(anyhow!("Unexpected character '{}' in query", c)),
+            };
+            i += symbol.len();
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    now: DateTime<Local>,
+}
+
+/// Parses a query. Relative dates are resolved against the current time.
+pub fn parse(input: &str) -> Result<Query> {
+    parse_at(input, Local::now())
+}
+
+fn parse_at(input: &str, now: DateTime<Local>) -> Result<Query> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        position: 0,
+        now,
+    };
+    parser.query()
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn error(&self, expected: &str) -> anyhow::Error {
+        match self.peek() {
+            Some(token) => anyhow!("Expected {} but found {}", expected, token.describe()),
+            None => anyhow!("Expected {} at the end of the query", expected),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let mut query = Query {
+            columns: Vec::new(),
+            sources: Vec::new(),
+            filter: None,
+            sort: Vec::new(),
+            limit: None,
+        };
+
+        if self.eat_keyword("table") {
+            query.columns.push(self.field()?);
+            while self.eat_symbol(",") {
+                query.columns.push(self.field()?);
+            }
+        }
+
+        if self.eat_keyword("from") {
+            query.sources.push(self.source()?);
+            while self.eat_keyword("or") || self.eat_symbol(",") {
+                query.sources.push(self.source()?);
+            }
+        }
+
+        if self.eat_keyword("where") {
+            query.filter = Some(self.or_expr()?);
+        }
+
+        if self.eat_keyword("sort") {
+            loop {
+                let field = self.field()?;
+                let descending = if self.eat_keyword("desc") {
+                    true
+                } else {
+                    self.eat_keyword("asc");
+                    false
+                };
+                query.sort.push((field, descending));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        if self.eat_keyword("limit") {
+            match self.next() {
+                Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                    query.limit = Some(n as usize)
+                }
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("a number after 'limit'"));
+                }
+            }
+        }
+
+        if self.peek().is_some() {
+            return Err(self.error("'from', 'where', 'sort' or 'limit'"));
+        }
+        Ok(query)
+    }
+
+    fn source(&mut self) -> Result<Source> {
+        match self.next() {
+            Some(Token::Text(project)) => Ok(Source::Project(normalize_project(&project))),
+            Some(Token::Tag(tag)) => Ok(Source::Tag(tag)),
+            _ => {
+                self.position -= 1;
+                Err(self.error("a \"project\" or #tag after 'from'"))
+            }
+        }
+    }
+
+    fn field(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Word(word)) if !is_keyword(word) => {
+                let word = word.clone();
+                self.position += 1;
+                Ok(word)
+            }
+            _ => Err(self.error("a field name")),
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.and_expr()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.not_expr()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        if self.eat_symbol("(") {
+            let expr = self.or_expr()?;
+            if !self.eat_symbol(")") {
+                return Err(self.error("')'"));
+            }
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let field = self.field()?;
+
+        let op = match self.peek() {
+            Some(Token::Symbol("=" | "==")) => Op::Eq,
+            Some(Token::Symbol("!=")) => Op::Ne,
+            Some(Token::Symbol("<")) => Op::Lt,
+            Some(Token::Symbol("<=")) => Op::Le,
+            Some(Token::Symbol(">")) => Op::Gt,
+            Some(Token::Symbol(">=")) => Op::Ge,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("contains") => Op::Contains,
+            _ => return Ok(Expr::Truthy(field)),
+        };
+        self.position += 1;
+
+        Ok(Expr::Compare(field, op, self.value()?))
+    }
+
+    /// A literal, optionally followed by `+ 7d` or `- 2w`.
+    fn value(&mut self) -> Result<Value> {
+        let mut value = match self.next() {
+            Some(Token::Text(text)) => Value::Text(text),
+            Some(Token::Number(n)) => Value::Number(n),
+            Some(Token::Tag(tag)) => Value::Text(tag),
+            Some(Token::Word(word)) => match word.to_lowercase().as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                "now" => Value::Date(self.now),
+                "today" => Value::Date(self.day(0)),
+                "yesterday" => Value::Date(self.day(-1)),
+                "tomorrow" => Value::Date(self.day(1)),
+                _ if is_keyword(&word) => {
+                    self.position -= 1;
+                    return Err(self.error("a value"));
+                }
+                // Bare words are text, so `status = active` works
+                _ => Value::Text(word),
+            },
+            _ => {
+                self.position -= 1;
+                return Err(self.error("a value"));
+            }
+        };
+
+        loop {
+            let sign = if self.eat_symbol("+") {
+                1
+            } else if matches!(self.peek(), Some(Token::Word(w)) if w == "-") {
+                self.position += 1;
+                -1
+            } else {
+                break;
+            };
+
+            let Some(Token::Duration(duration)) = self.next() else {
+                self.position -= 1;
+                return Err(self.error("a duration like 7d"));
+            };
+            let date = value
+                .as_date()
+                .ok_or_else(|| anyhow!("Only dates can be shifted by a duration"))?;
+            value = Value::Date(date + duration * sign);
+        }
+
+        Ok(value)
+    }
+
+    // Midnight of today plus some days
+    fn day(&self, offset: i64) -> DateTime<Local> {
+        let date = (self.now + Duration::days(offset)).date_naive();
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .unwrap_or(self.now)
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    [
+        "table", "from", "where", "sort", "limit", "and", "or", "not", "contains", "asc", "desc",
+    ]
+    .iter()
+    .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn note(path: &str, tags: &[&str], frontmatter: JsonValue) -> (NoteMeta, String) {
+        let date = parse_date("2024-03-10").unwrap();
+        let project = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let meta = NoteMeta {
+            path: PathBuf::from(format!("/vault/{}", path)),
+            title: path.to_string(),
+            project: project.to_string(),
+            frontmatter,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created: date,
+            modified: date,
+        };
+        (meta, path.to_string())
+    }
+
+    fn now() -> DateTime<Local> {
+        parse_date("2024-03-15 10:30:00").unwrap()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_at(
+            "table status from \"projects/\" or #work where status = \"active\" and tags contains \"q4\" sort modified desc, title limit 5",
+            now(),
+        )
+        .unwrap();
+
+        assert_eq!(query.columns, vec!["status"]);
+        assert_eq!(
+            query.sources,
+            vec![
+                Source::Project("projects".to_string()),
+                Source::Tag("work".to_string())
+            ]
+        );
+        assert_eq!(
+            query.filter,
+            Some(Expr::And(
+                Box::new(Expr::Compare(
+                    "status".to_string(),
+                    Op::Eq,
+                    Value::Text("active".to_string())
+                )),
+                Box::new(Expr::Compare(
+                    "tags".to_string(),
+                    Op::Contains,
+                    Value::Text("q4".to_string())
+                )),
+            ))
+        );
+        assert_eq!(
+            query.sort,
+            vec![("modified".to_string(), true), ("title".to_string(), false)]
+        );
+        assert_eq!(query.limit, Some(5));
+    }
+
+    #[test]
+    fn test_parse_relative_dates_and_errors() {
+        let query = parse_at("where modified >= today - 7d", now()).unwrap();
+        assert_eq!(
+            query.filter,
+            Some(Expr::Compare(
+                "modified".to_string(),
+                Op::Ge,
+                Value::Date(parse_date("2024-03-08").unwrap())
+            ))
+        );
+
+        assert!(parse_at("where status =", now()).is_err());
+        assert!(parse_at("from projects", now()).is_err());
+        assert!(parse_at("where (a = 1", now()).is_err());
+        assert!(parse_at("sort title sideways", now()).is_err());
+        assert!(parse_at("where title = \"open", now()).is_err());
+        assert!(parse_at("", now()).unwrap().filter.is_none());
+    }
+
+    #[test]
+    fn test_evaluate_query() {
+        let active = note(
+            "projects/a.md",
+            &["Q4"],
+            json!({"status": "Active", "priority": 2, "due": "2024-03-01"}),
+        );
+        let done = note(
+            "projects/b.md",
+            &["q3"],
+            json!({"status": "done", "priority": "5", "archived": true}),
+        );
+        let other = note("ideas/c.md", &["work/deep"], json!({}));
+
+        let matching = |query: &str| -> Vec<String> {
+            let query = parse_at(query, now()).unwrap();
+            [&active, &done, &other]
+                .iter()
+                .filter(|(meta, path)| query.matches(meta, path))
+                .map(|(_, path)| path.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            matching("from \"projects\" where status = \"active\" and tags contains \"#q4\""),
+            vec!["projects/a.md"]
+        );
+        assert_eq!(matching("from #work"), vec!["ideas/c.md"]);
+        assert_eq!(matching("where priority > 3"), vec!["projects/b.md"]);
+        assert_eq!(matching("where due < today"), vec!["projects/a.md"]);
+        assert_eq!(
+            matching("where not archived and status != null"),
+            vec!["projects/a.md"]
+        );
+        assert_eq!(
+            matching("where title contains \"C.MD\""),
+            vec!["ideas/c.md"]
+        );
+        assert_eq!(
+            matching("where path contains projects or tags = \"work/deep\""),
+            vec!["projects/a.md", "projects/b.md", "ideas/c.md"]
+        );
+    }
+
+    #[test]
+    fn test_sort_query() {
+        let mut notes = vec![
+            note("a.md", &[], json!({"priority": 1})),
+            note("b.md", &[], json!({})),
+            note("c.md", &[], json!({"priority": 3})),
+        ];
+
+        parse_at("sort priority desc", now())
+            .unwrap()
+            .sort(&mut notes);
+        let order: Vec<&str> = notes.iter().map(|(_, path)| path.as_str()).collect();
+        assert_eq!(order, vec!["c.md", "a.md", "b.md"]);
+    }
+}
@@ -0,0 +1,240 @@
+// src/rpc.rs
+//! JSON-RPC 2.0 over a pair of streams, as used by `ncy serve` and `ncy lsp`.
+//! Messages are either framed with `Content-Length` headers, as in the
+//! Language Server Protocol, or sent one per line. Replies use the framing of
+//! the first message received.
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value as JsonValue, json};
+use std::io::{BufRead, Write};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// Any failure of the method itself, such as a note that does not exist
+pub const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        RpcError::new(INVALID_PARAMS, message)
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(error: anyhow::Error) -> Self {
+        RpcError::new(SERVER_ERROR, error.to_string())
+    }
+}
+
+/// A request or notification. Notifications have no id and get no reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub id: Option<JsonValue>,
+    pub method: String,
+    pub params: JsonValue,
+}
+
+impl Request {
+    pub fn parse(message: &JsonValue) -> Result<Self, RpcError> {
+        let method = message
+            .get("method")
+            .and_then(|m| m.as_str())
+            .ok_or_else(|| RpcError::new(INVALID_REQUEST, "Missing method"))?;
+
+        Ok(Request {
+            id: message.get("id").filter(|id| !id.is_null()).cloned(),
+            method: method.to_string(),
+            params: message.get("params").cloned().unwrap_or(JsonValue::Null),
+        })
+    }
+
+    /// A string parameter that must be present.
+    pub fn string(&self, name: &str) -> Result<&str, RpcError> {
+        self.optional_string(name)
+            .ok_or_else(|| RpcError::invalid_params(format!("Missing string parameter '{}'", name)))
+    }
+
+    pub fn optional_string(&self, name: &str) -> Option<&str> {
+        self.params.get(name).and_then(|v| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Framing {
+    Headers,
+    Lines,
+}
+
+pub struct Connection<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    framing: Option<Framing>,
+}
+
+impl<R: BufRead, W: Write> Connection<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Connection {
+            reader,
+            writer,
+            framing: None,
+        }
+    }
+
+    /// Reads the next message. Returns `None` at the end of the input, and an
+    /// error for a message that is not valid JSON.
+    pub fn read_message(&mut self) -> Result<Option<JsonValue>> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let body = match content_length(trimmed) {
+                Some(length) => {
+                    self.framing.get_or_insert(Framing::Headers);
+                    self.read_body(length?)?
+                }
+                None => {
+                    self.framing.get_or_insert(Framing::Lines);
+                    trimmed.to_string()
+                }
+            };
+            return serde_json::from_str(&body)
+                .map(Some)
+                .context("Invalid JSON message");
+        }
+    }
+
+    // Skips the remaining headers, then reads a body of the given length
+    fn read_body(&mut self, length: usize) -> Result<String> {
+        loop {
+            let mut header = String::new();
+            if self.reader.read_line(&mut header)? == 0 {
+                return Err(anyhow!("Input ended inside message headers"));
+            }
+            if header.trim().is_empty() {
+                break;
+            }
+        }
+
+        let mut body = vec![0; length];
+        self.reader
+            .read_exact(&mut body)
+            .context("Input ended inside a message")?;
+        String::from_utf8(body).context("Message is not valid UTF-8")
+    }
+
+    pub fn send(&mut self, message: &JsonValue) -> Result<()> {
+        let text = message.to_string();
+        match self.framing.unwrap_or(Framing::Lines) {
+            Framing::Headers => write!(
+                self.writer,
+                "Content-Length: {}\r\n\r\n{}",
+                text.len(),
+                text
+            )?,
+            Framing::Lines => writeln!(self.writer, "{}", text)?,
+        }
+        self.writer.flush().context("Failed to write reply")
+    }
+
+    pub fn respond(&mut self, id: JsonValue, result: Result<JsonValue, RpcError>) -> Result<()> {
+        let message = match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": error.code, "message": error.message},
+            }),
+        };
+        self.send(&message)
+    }
+
+    pub fn notify(&mut self, method: &str, params: JsonValue) -> Result<()> {
+        self.send(&json!({"jsonrpc": "2.0", "method": method, "params": params}))
+    }
+}
+
+// The length from a `Content-Length` header, or None for any other line
+fn content_length(line: &str) -> Option<Result<usize>> {
+    let (name, value) = line.split_once(':')?;
+    if !name.trim().eq_ignore_ascii_case("content-length") {
+        return None;
+    }
+    Some(
+        value
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Invalid Content-Length: {}", value.trim())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_header_framed_messages() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let input = format!(
+            "Content-Length: {}\r\nContent-Type: application/json\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let mut output = Vec::new();
+        let mut connection = Connection::new(Cursor::new(input), &mut output);
+
+        let message = connection.read_message().unwrap().unwrap();
+        let request = Request::parse(&message).unwrap();
+        assert_eq!(request.method, "ping");
+        assert_eq!(request.id, Some(json!(1)));
+        assert!(connection.read_message().unwrap().is_none());
+
+        connection.respond(json!(1), Ok(json!("pong"))).unwrap();
+        let reply = String::from_utf8(output).unwrap();
+        let expected = r#"{"id":1,"jsonrpc":"2.0","result":"pong"}"#;
+        assert_eq!(
+            reply,
+            format!("Content-Length: {}\r\n\r\n{}", expected.len(), expected)
+        );
+    }
+
+    #[test]
+    fn test_read_line_framed_messages() {
+        let input = "{\"id\": 1, \"method\": \"a\"}\n\nnot json\n{\"method\": \"b\"}\n";
+        let mut output = Vec::new();
+        let mut connection = Connection::new(Cursor::new(input), &mut output);
+
+        let first = connection.read_message().unwrap().unwrap();
+        assert_eq!(Request::parse(&first).unwrap().method, "a");
+        assert!(connection.read_message().is_err());
+        let third = Request::parse(&connection.read_message().unwrap().unwrap()).unwrap();
+        assert_eq!(third.id, None);
+
+        connection
+            .respond(json!(2), Err(RpcError::new(METHOD_NOT_FOUND, "Nope")))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"error\":{\"code\":-32601,\"message\":\"Nope\"},\"id\":2,\"jsonrpc\":\"2.0\"}\n"
+        );
+    }
+}
@@ -0,0 +1,369 @@
+// src/schema.rs
+//! Per-project frontmatter schemas. Schemas are declared under `schemas` in a
+//! vault's entry in config.yaml and in `schemas.yaml` at the vault root, both
+//! keyed by project path:
+//!
+//! ```yaml
+//! people:
+//!   required: [email, company]
+//!   fields:
+//!     status:
+//!       one_of: [active, former]
+//!       default: active
+//!     email:
+//!       type: string
+//! ```
+use crate::commands::ls::{normalize_project, project_matches};
+use crate::metadata::parse_date;
+use crate::utils::yaml_to_json;
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value as JsonValue;
+use std::fs;
+use std::path::Path;
+
+pub const SCHEMA_FILE: &str = "schemas.yaml";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldType {
+    String,
+    Number,
+    Boolean,
+    List,
+    Date,
+}
+
+impl FieldType {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "string" | "text" => Ok(FieldType::String),
+            "number" => Ok(FieldType::Number),
+            "bool" | "boolean" => Ok(FieldType::Boolean),
+            "list" => Ok(FieldType::List),
+            "date" => Ok(FieldType::Date),
+            _ => Err(anyhow!(
+                "Unknown field type '{}'. Use string, number, boolean, list or date",
+                name
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::List => "list",
+            FieldType::Date => "date",
+        }
+    }
+
+    fn accepts(self, value: &JsonValue) -> bool {
+        match self {
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::List => value.is_array(),
+            FieldType::Date => value.as_str().and_then(parse_date).is_some(),
+        }
+    }
+}
+
+/// Rules for a single frontmatter field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldRule {
+    pub kind: Option<FieldType>,
+    /// Allowed values; for lists, every item must be one of them
+    pub one_of: Vec<JsonValue>,
+    pub default: Option<JsonValue>,
+}
+
+impl FieldRule {
+    /// Checks a value that is present against the rule.
+    pub fn check(&self, value: &JsonValue) -> Vec<String> {
+        if let Some(kind) = self.kind
+            && !kind.accepts(value)
+        {
+            return vec![format!("expected a {}, found {}", kind.name(), value)];
+        }
+        if self.one_of.is_empty() {
+            return Vec::new();
+        }
+
+        let items = match value {
+            JsonValue::Array(items) => items.iter().collect(),
+            _ => vec![value],
+        };
+        items
+            .into_iter()
+            .filter(|item| !self.one_of.contains(item))
+            .map(|item| format!("{} is not one of: {}", item, describe_values(&self.one_of)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// Project the schema applies to, including everything below it
+    pub project: String,
+    pub required: Vec<String>,
+    pub fields: Vec<(String, FieldRule)>,
+}
+
+/// A field of a note that does not follow its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub field: String,
+    pub message: String,
+}
+
+impl Schema {
+    pub fn rule(&self, field: &str) -> Option<&FieldRule> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, rule)| rule)
+    }
+
+    /// Checks a note's frontmatter against the schema.
+    pub fn check(&self, frontmatter: &JsonValue) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let violation = |field: &str, message: String| Violation {
+            field: field.to_string(),
+            message,
+        };
+
+        for field in &self.required {
+            if is_blank(frontmatter.get(field)) {
+                violations.push(violation(field, "missing required field".to_string()));
+            }
+        }
+
+        for (field, rule) in &self.fields {
+            if let Some(value) = frontmatter.get(field).filter(|v| !is_blank(Some(v))) {
+                violations.extend(rule.check(value).into_iter().map(|m| violation(field, m)));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Loads the schemas of a vault from its config entry and its schema file.
+pub fn load_schemas(
+    config: &JsonValue,
+    vault_name: &str,
+    vault_path: &Path,
+) -> Result<Vec<Schema>> {
+    let mut schemas = Vec::new();
+
+    let vault_entry = config
+        .get("vaults")
+        .and_then(|v| v.as_array())
+        .and_then(|vaults| {
+            vaults
+                .iter()
+                .find(|vault| vault.get("name").and_then(|n| n.as_str()) == Some(vault_name))
+        });
+    if let Some(declared) = vault_entry.and_then(|vault| vault.get("schemas")) {
+        schemas.extend(parse_schemas(declared).context("Invalid schemas in config.yaml")?);
+    }
+
+    let schema_file = vault_path.join(SCHEMA_FILE);
+    if schema_file.exists() {
+        let content = fs::read_to_string(&schema_file).context("Failed to read schema file")?;
+        let yaml = serde_yaml::from_str::<serde_yaml::Value>(&content)
+            .context("Failed to parse schema file")?;
+        schemas.extend(
+            parse_schemas(&yaml_to_json(yaml))
+                .context(format!("Invalid schemas in {}", schema_file.display()))?,
+        );
+    }
+
+    Ok(schemas)
+}
+
+/// Parses a mapping of project paths to schemas.
+pub fn parse_schemas(value: &JsonValue) -> Result<Vec<Schema>> {
+    let projects = match value {
+        JsonValue::Null => return Ok(Vec::new()),
+        JsonValue::Object(projects) => projects,
+        _ => return Err(anyhow!("Schemas must be a mapping of project paths")),
+    };
+
+    let mut schemas = Vec::new();
+    for (project, declaration) in projects {
+        let schema = parse_schema(project, declaration)
+            .context(format!("Invalid schema for '{}'", project))?;
+        schemas.push(schema);
+    }
+
+    Ok(schemas)
+}
+
+fn parse_schema(project: &str, declaration: &JsonValue) -> Result<Schema> {
+    let mut required: Vec<String> = match declaration.get("required") {
+        None | Some(JsonValue::Null) => Vec::new(),
+        Some(JsonValue::Array(fields)) => fields
+            .iter()
+            .map(|field| {
+                field
+                    .as_str()
+                    .map(|f| f.to_string())
+                    .context("Required fields must be names")
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => return Err(anyhow!("'required' must be a list of field names")),
+    };
+
+    let mut fields = Vec::new();
+    if let Some(declared) = declaration.get("fields").filter(|f| !f.is_null()) {
+        let declared = declared
+            .as_object()
+            .context("'fields' must be a mapping of field names")?;
+
+        for (name, rule) in declared {
+            let kind = rule
+                .get("type")
+                .and_then(|t| t.as_str())
+                .map(FieldType::parse)
+                .transpose()?;
+            let one_of = match rule.get("one_of") {
+                None | Some(JsonValue::Null) => Vec::new(),
+                Some(JsonValue::Array(values)) => values.clone(),
+                Some(_) => return Err(anyhow!("'one_of' of '{}' must be a list", name)),
+            };
+
+            // `required: true` on a field is the same as listing it
+            if rule.get("required").and_then(|r| r.as_bool()) == Some(true)
+                && !required.contains(name)
+            {
+                required.push(name.clone());
+            }
+
+            fields.push((
+                name.clone(),
+                FieldRule {
+                    kind,
+                    one_of,
+                    default: rule.get("default").filter(|d| !d.is_null()).cloned(),
+                },
+            ));
+        }
+    }
+
+    Ok(Schema {
+        project: normalize_project(project),
+        required,
+        fields,
+    })
+}
+
+/// Returns the schemas that apply to notes in a project.
+pub fn schemas_for<'a>(schemas: &'a [Schema], project: &str) -> Vec<&'a Schema> {
+    schemas
+        .iter()
+        .filter(|schema| project_matches(project, &schema.project))
+        .collect()
+}
+
+/// Formats allowed values for messages and prompts: `a, b, c`.
+pub fn describe_values(values: &[JsonValue]) -> String {
+    values
+        .iter()
+        .map(|value| match value {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn is_blank(value: Option<&JsonValue>) -> bool {
+    match value {
+        None | Some(JsonValue::Null) => true,
+        Some(JsonValue::String(s)) => s.trim().is_empty(),
+        Some(JsonValue::Array(items)) => items.is_empty(),
+        Some(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn people_schema() -> Schema {
+        let declared = json!({
+            "people/": {
+                "required": ["email", "company"],
+                "fields": {
+                    "status": {"one_of": ["active", "former"], "default": "active", "required": true},
+                    "age": {"type": "number"},
+                    "roles": {"type": "list", "one_of": ["dev", "pm"]}
+                }
+            }
+        });
+        parse_schemas(&declared).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        let schema = people_schema();
+        assert_eq!(schema.project, "people");
+        assert_eq!(schema.required, vec!["email", "company", "status"]);
+        assert_eq!(
+            schema.rule("status").unwrap().default,
+            Some(json!("active"))
+        );
+        assert_eq!(schema.rule("age").unwrap().kind, Some(FieldType::Number));
+
+        assert!(parse_schemas(&json!({"x": {"fields": {"a": {"type": "color"}}}})).is_err());
+        assert!(parse_schemas(&json!({"x": {"required": "email"}})).is_err());
+        assert!(parse_schemas(&json!(null)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_schema() {
+        let schema = people_schema();
+
+        let valid = json!({"email": "a@b.c", "company": "X", "status": "active", "roles": ["dev"]});
+        assert!(schema.check(&valid).is_empty());
+
+        let invalid = json!({"email": "", "status": "gone", "age": "old", "roles": ["dev", "ceo"]});
+        let violations: Vec<(String, String)> = schema
+            .check(&invalid)
+            .into_iter()
+            .map(|v| (v.field, v.message))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                ("email".to_string(), "missing required field".to_string()),
+                ("company".to_string(), "missing required field".to_string()),
+                (
+                    "age".to_string(),
+                    "expected a number, found \"old\"".to_string()
+                ),
+                (
+                    "roles".to_string(),
+                    "\"ceo\" is not one of: dev, pm".to_string()
+                ),
+                (
+                    "status".to_string(),
+                    "\"gone\" is not one of: active, former".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schemas_for_project() {
+        let schemas = parse_schemas(&json!({"people": {}, "people/vip": {}, "work": {}})).unwrap();
+        let projects: Vec<&str> = schemas_for(&schemas, "people/vip/2024")
+            .iter()
+            .map(|schema| schema.project.as_str())
+            .collect();
+        assert_eq!(projects, vec!["people", "people/vip"]);
+        assert!(schemas_for(&schemas, "peoples").is_empty());
+    }
+}
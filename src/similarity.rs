@@ -0,0 +1,175 @@
+// src/similarity.rs
+//! Offline text similarity between notes, using TF-IDF weighted term vectors
+//! compared by cosine similarity. Everything is computed from the vault on
+//! the fly; there is no model or index to download.
+use std::collections::HashMap;
+
+// Common English words that say nothing about what a note is about
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have", "he",
+    "her", "his", "how", "if", "in", "into", "is", "it", "its", "just", "may", "me", "more",
+    "most", "my", "no", "not", "of", "on", "one", "or", "our", "out", "she", "so", "some", "than",
+    "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "up", "us",
+    "was", "we", "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+/// Splits text into lowercase words, dropping stop words, single characters
+/// and plain numbers.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.chars().count() > 1)
+        .filter(|word| !word.chars().all(|c| c.is_numeric()))
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// TF-IDF vectors for a set of documents, normalized to unit length.
+#[derive(Debug, Default)]
+pub struct TfIdf {
+    idf: HashMap<String, f64>,
+    vectors: Vec<HashMap<String, f64>>,
+}
+
+impl TfIdf {
+    pub fn new<S: AsRef<str>>(documents: &[S]) -> Self {
+        let counts: Vec<HashMap<String, usize>> = documents
+            .iter()
+            .map(|document| word_counts(document.as_ref()))
+            .collect();
+
+        let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+        for words in &counts {
+            for word in words.keys() {
+                *document_frequency.entry(word).or_insert(0) += 1;
+            }
+        }
+
+        // Words found in every document get no weight at all
+        let total = documents.len() as f64;
+        let idf = document_frequency
+            .into_iter()
+            .map(|(word, frequency)| (word.to_string(), (total / frequency as f64).ln()))
+            .collect();
+
+        let mut index = TfIdf {
+            idf,
+            vectors: Vec::new(),
+        };
+        index.vectors = counts.iter().map(|words| index.vector(words)).collect();
+        index
+    }
+
+    fn vector(&self, words: &HashMap<String, usize>) -> HashMap<String, f64> {
+        let mut vector: HashMap<String, f64> = words
+            .iter()
+            .filter_map(|(word, count)| {
+                let idf = self.idf.get(word)?;
+                Some((word.clone(), (1.0 + (*count as f64).ln()) * idf))
+            })
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+
+        let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            vector.values_mut().for_each(|w| *w /= norm);
+        }
+        vector
+    }
+
+    /// The documents that best match a free text query, best first. Words
+    /// that appear in no document are ignored.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(usize, f64)> {
+        let query = self.vector(&word_counts(query));
+        let mut scores: Vec<(usize, f64)> = self
+            .vectors
+            .iter()
+            .enumerate()
+            .map(|(i, vector)| (i, dot(&query, vector)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores.truncate(limit);
+        scores
+    }
+
+    /// Cosine similarity of two documents, between 0 and 1.
+    pub fn similarity(&self, a: usize, b: usize) -> f64 {
+        dot(&self.vectors[a], &self.vectors[b])
+    }
+
+    /// The documents most similar to the given one, best first, leaving out
+    /// the document itself and documents with nothing in common with it.
+    pub fn most_similar(&self, document: usize, limit: usize) -> Vec<(usize, f64)> {
+        let mut scores: Vec<(usize, f64)> = (0..self.vectors.len())
+            .filter(|other| *other != document)
+            .map(|other| (other, self.similarity(document, other)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores.truncate(limit);
+        scores
+    }
+}
+
+fn word_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in tokenize(text) {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn dot(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(word, weight)| large.get(word).map(|other| weight * other))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("The [[Rust]] compiler, 2024 edition: it's fast! Ünïcode"),
+            vec!["rust", "compiler", "edition", "fast", "ünïcode"]
+        );
+    }
+
+    #[test]
+    fn test_most_similar() {
+        let documents = [
+            "rust borrow checker lifetimes",
+            "gardening tomatoes soil compost",
+            "rust lifetimes and the borrow checker explained",
+            "compost for tomatoes",
+            "rust",
+        ];
+        let index = TfIdf::new(&documents);
+
+        let similar: Vec<usize> = index
+            .most_similar(0, 3)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(similar, vec![2, 4]);
+        assert_eq!(index.most_similar(1, 3)[0].0, 3);
+        assert!((index.similarity(0, 2) - index.similarity(2, 0)).abs() < 1e-12);
+        assert!(index.similarity(0, 2) <= 1.0 + 1e-12);
+
+        let found: Vec<usize> = index
+            .search("Tomatoes and unknownword", 5)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(found, vec![3, 1]);
+        assert!(index.search("unknownword", 5).is_empty());
+    }
+}
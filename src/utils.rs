@@ -3,16 +3,20 @@ use serde_json::Value as JsonValue;
 use serde_yaml;
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
 
-// Function to read and parse the configuration file
-pub fn read_config() -> Result<JsonValue> {
-    // Read the NOTEMANCY_CONF_DIR environment variable
+// Function to get the configuration directory from NOTEMANCY_CONF_DIR
+pub fn config_dir() -> Result<PathBuf> {
     let conf_dir = env::var("NOTEMANCY_CONF_DIR")
         .context("NOTEMANCY_CONF_DIR environment variable is not set")?;
 
+    Ok(PathBuf::from(conf_dir))
+}
+
+// Function to read and parse the configuration file
+pub fn read_config() -> Result<JsonValue> {
     // Construct the path to the config file
-    let config_path = Path::new(&conf_dir).join("config.yaml");
+    let config_path = config_dir()?.join("config.yaml");
 
     // Check if the file exists
     if !config_path.exists() {
@@ -35,8 +39,60 @@ pub fn read_config() -> Result<JsonValue> {
     Ok(json_value)
 }
 
+// Function to find the directory of a vault by name
+pub fn find_vault_directory(config: &JsonValue, vault_name: &str) -> Result<String> {
+    // Get the vaults array from config
+    let vaults = config
+        .get("vaults")
+        .and_then(|v| v.as_array())
+        .context("No vaults defined in configuration")?;
+
+    // Find the vault with the specified name
+    for vault in vaults {
+        if vault.get("name").and_then(|n| n.as_str()) == Some(vault_name)
+            && let Some(dir) = vault.get("vault_directory").and_then(|d| d.as_str())
+        {
+            return Ok(dir.to_string());
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "Could not find directory for vault: {}",
+        vault_name
+    ))
+}
+
+// Function to resolve a vault name (or the default vault) to its name and directory
+pub fn resolve_vault(config: &JsonValue, vault_name: Option<&str>) -> Result<(String, PathBuf)> {
+    let vault_name = match vault_name {
+        Some(name) => name.to_string(),
+        None => config
+            .get("default_vault")
+            .and_then(|v| v.as_str())
+            .context("No default vault set. Run 'ncy set <vault-name>' first.")?
+            .to_string(),
+    };
+
+    let vault_directory = find_vault_directory(config, &vault_name)?;
+    Ok((vault_name, PathBuf::from(vault_directory)))
+}
+
+// Function to read a string setting of a vault, such as `inbox`, from its entry in config.yaml
+pub fn vault_setting<'a>(config: &'a JsonValue, vault_name: &str, key: &str) -> Option<&'a str> {
+    config
+        .get("vaults")
+        .and_then(|v| v.as_array())
+        .and_then(|vaults| {
+            vaults
+                .iter()
+                .find(|vault| vault.get("name").and_then(|n| n.as_str()) == Some(vault_name))
+        })
+        .and_then(|vault| vault.get(key))
+        .and_then(|value| value.as_str())
+}
+
 // Function to convert serde_yaml::Value to serde_json::Value
-fn yaml_to_json(yaml: serde_yaml::Value) -> JsonValue {
+pub fn yaml_to_json(yaml: serde_yaml::Value) -> JsonValue {
     match yaml {
         serde_yaml::Value::Null => JsonValue::Null,
         serde_yaml::Value::Bool(b) => JsonValue::Bool(b),